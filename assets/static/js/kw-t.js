@@ -74,6 +74,30 @@ class KeywordTags extends HTMLElement {
         document.getElementById("topic_names").value = topic_names.toString()
     }
 
+    // autocomplete the typing keyword by the names from suggest url
+    suggest(suggestUrl) {
+        var datalist = this.shadow.querySelector("datalist")
+        if (datalist === null)
+            return
+
+        this.input.addEventListener("input", () => {
+            var prefix = this.input.value.trim()
+            if (prefix === '')
+                return
+
+            fetch(suggestUrl + "?prefix=" + encodeURIComponent(prefix))
+                .then((res) => res.json())
+                .then((names) => {
+                    datalist.innerHTML = ''
+                    names.forEach((name) => {
+                        var option = document.createElement("option")
+                        option.value = name
+                        datalist.appendChild(option)
+                    })
+                })
+        })
+    }
+
     connectedCallback() {
         var tagValues = this.getAttribute('tag-values')
         tagValues = tagValues.split(',')
@@ -83,6 +107,10 @@ class KeywordTags extends HTMLElement {
             if (tagValue !== '')
                 this.addTag(tagValue)
         })
+
+        var suggestUrl = this.getAttribute('suggest-url')
+        if (suggestUrl)
+            this.suggest(suggestUrl)
     }
 
 }
@@ -0,0 +1,78 @@
+"use strict";
+
+// Subscribe to a graphql stream over websocket, by graphql-transport-ws
+// protocol. The callback is called with the data of every event.
+function gqlSubscribe(url, query, variables, onData) {
+    let ws = new WebSocket(url, "graphql-transport-ws");
+
+    ws.onopen = function () {
+        ws.send(JSON.stringify({ type: "connection_init", payload: {} }));
+    };
+
+    ws.onmessage = function (event) {
+        let msg = JSON.parse(event.data);
+
+        if (msg.type === "connection_ack") {
+            ws.send(JSON.stringify({
+                id: "1",
+                type: "subscribe",
+                payload: { query: query, variables: variables }
+            }));
+        } else if (msg.type === "ping") {
+            ws.send(JSON.stringify({ type: "pong" }));
+        } else if (msg.type === "next" && msg.payload.data) {
+            onData(msg.payload.data);
+        }
+    };
+
+    return ws;
+}
+
+$(function () {
+    // badge of unread notifications in header
+    $(".js-notifications").each(function () {
+        let bell = $(this);
+        let count = bell.find(".js-notifications-count");
+
+        // a short-lived ticket, the session token stays in its cookie
+        $.getJSON(bell.data("ticket-uri"), function (res) {
+            gqlSubscribe(
+                bell.data("gql-ws"),
+                "subscription ($ticket: String!) { notifications(ticket: $ticket) { id } }",
+                { ticket: res.ticket },
+                function () {
+                    count.text((parseInt(count.text()) || 0) + 1);
+                    count.removeClass("d-none");
+                }
+            );
+        });
+    });
+
+    // new comments of the project in detail page
+    $(".js-comments-new").each(function () {
+        let tip = $(this);
+
+        gqlSubscribe(
+            tip.data("gql-ws"),
+            "subscription ($projectId: ObjectId!) { comments(projectId: $projectId) { id } }",
+            { projectId: tip.data("project-id") },
+            function () {
+                tip.removeClass("d-none");
+            }
+        );
+    });
+
+    // newly published projects in projects list
+    $(".js-projects-new").each(function () {
+        let tip = $(this);
+
+        gqlSubscribe(
+            tip.data("gql-ws"),
+            "subscription { projectsPublished { id } }",
+            {},
+            function () {
+                tip.removeClass("d-none");
+            }
+        );
+    });
+});
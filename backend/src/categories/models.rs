@@ -1,8 +1,9 @@
+use std::collections::BTreeMap;
 use serde::{Serialize, Deserialize};
 use mongodb::bson::oid::ObjectId;
 
 use crate::dbs::mongo::DataSource;
-use crate::util::{constant::GqlResult, pagination::ProjectsResult};
+use crate::util::{config::Config, constant::GqlResult, pagination::ProjectsResult};
 
 use crate::projects::services::projects_by_category_id;
 
@@ -10,20 +11,105 @@ use crate::projects::services::projects_by_category_id;
 #[graphql(complex)]
 pub struct Category {
     pub _id: ObjectId,
-    pub name_zh: String,
-    pub name_en: String,
+    // names of the category keyed by locale, such as "zh-cn" or "en-us"
+    #[graphql(skip)]
+    #[serde(default)]
+    pub names: BTreeMap<String, String>,
     pub slug: String,
+    #[serde(default)]
+    pub parent_id: Option<ObjectId>,
+    #[serde(default)]
+    pub position: i64,
+}
+
+// locales tried after the requested one, when it has no translation
+const FALLBACK_LOCALES: [&str; 2] = ["en-us", "zh-cn"];
+
+impl Category {
+    // name of the locale, falls back to the same language, the fallback
+    // locales, then any translation and finally the slug
+    pub fn name_by_locale(&self, locale: &str) -> String {
+        let locale = locale.trim().to_lowercase();
+        if let Some(name) = self.names.get(&locale) {
+            return name.clone();
+        }
+
+        let language = locale.split('-').next().unwrap_or_default();
+        let same_language = self.names.iter().find(|(key, _)| {
+            key.split('-').next().unwrap_or_default() == language
+        });
+        if let Some((_, name)) = same_language {
+            return name.clone();
+        }
+
+        for fallback in FALLBACK_LOCALES {
+            if let Some(name) = self.names.get(fallback) {
+                return name.clone();
+            }
+        }
+
+        match self.names.values().next() {
+            Some(name) => name.clone(),
+            None => self.slug.clone(),
+        }
+    }
 }
 
 #[async_graphql::ComplexObject]
 impl Category {
+    pub async fn name(&self, locale: String) -> String {
+        self.name_by_locale(&locale)
+    }
+
+    pub async fn names(&self) -> Vec<CategoryName> {
+        self.names
+            .iter()
+            .map(|(locale, name)| CategoryName {
+                locale: locale.clone(),
+                name: name.clone(),
+            })
+            .collect()
+    }
+
+    pub async fn parent(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+    ) -> GqlResult<Option<Category>> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        match self.parent_id {
+            Some(parent_id) => {
+                Ok(Some(super::services::category_by_id(db, parent_id).await?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub async fn children(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+    ) -> GqlResult<Vec<Category>> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        super::services::categories_by_parent_id(db, Some(self._id)).await
+    }
+
+    // ancestors from the root category down to this one
+    pub async fn breadcrumb(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+    ) -> GqlResult<Vec<Category>> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        super::services::category_breadcrumb(db, self._id).await
+    }
+
     pub async fn projects(
         &self,
         ctx: &async_graphql::Context<'_>,
     ) -> GqlResult<ProjectsResult> {
         let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
         projects_by_category_id(
             db,
+            cfg,
             self._id,
             1,
             String::from("-"),
@@ -36,10 +122,37 @@ impl Category {
 
 #[derive(async_graphql::InputObject, Serialize, Deserialize)]
 pub struct CategoryNew {
-    pub name_zh: String,
-    pub name_en: String,
+    #[graphql(skip)]
+    pub names: BTreeMap<String, String>,
+    #[serde(skip)]
+    pub translations: Vec<CategoryName>,
     #[graphql(skip)]
     pub slug: String,
+    pub parent_id: Option<ObjectId>,
+    #[graphql(skip)]
+    pub position: i64,
+}
+
+// one translation of the category name
+#[derive(
+    async_graphql::SimpleObject,
+    async_graphql::InputObject,
+    Serialize,
+    Deserialize,
+    Clone,
+    Debug,
+)]
+#[graphql(input_name = "CategoryNameInput")]
+pub struct CategoryName {
+    pub locale: String,
+    pub name: String,
+}
+
+// one category of the categories tree, depth 0 is a root category
+#[derive(async_graphql::SimpleObject, Clone, Debug)]
+pub struct CategoryNode {
+    pub depth: u32,
+    pub category: Category,
 }
 
 #[derive(async_graphql::SimpleObject, Serialize, Deserialize, Clone, Debug)]
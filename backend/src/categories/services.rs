@@ -1,17 +1,25 @@
+use std::collections::BTreeMap;
 use futures::stream::StreamExt;
 use mongodb::{
     Database,
     bson::{
         oid::ObjectId, Document, doc, from_document, to_document, from_bson,
-        DateTime,
     },
     options::FindOptions,
 };
-use async_graphql::Error;
+use async_graphql::ErrorExtensions;
 
-use crate::util::{constant::GqlResult, common::slugify};
+use crate::util::{config::Config, constant::GqlResult, error::AppError};
 
-use super::models::{Category, CategoryUser, CategoryNew, CategoryUserNew};
+use crate::{users, users::models::UserStatus};
+use crate::slugs::services::{
+    slug_unique, slug_change, slug_history_target_id, slugs_history_delete,
+    slugs_clean_migrate,
+};
+use super::models::{
+    Category, CategoryUser, CategoryNew, CategoryUserNew, CategoryNode,
+    CategoryName,
+};
 
 // Create new category
 pub async fn category_new(
@@ -20,54 +28,148 @@ pub async fn category_new(
 ) -> GqlResult<Category> {
     let coll = db.collection::<Document>("categories");
 
-    category_new.name_zh = category_new.name_zh.trim().to_string();
-    let name_check = "".ne(&category_new.name_zh)
-        && "-".ne(&category_new.name_zh)
-        && "".ne(&category_new.name_en)
-        && "-".ne(&category_new.name_en);
-    match name_check {
-        true => {
-            let exist_document = coll
-                .find_one(
+    category_new.names = category_names(&category_new.translations)?;
+    names_exist_check(db, &category_new.names, None).await?;
+
+    if let Some(parent_id) = category_new.parent_id {
+        category_by_id(db, parent_id).await?;
+    }
+    category_new.position = next_position(db, category_new.parent_id).await?;
+
+    category_new.slug =
+        slug_unique(db, "categories", slug_name(&category_new.names), None)
+            .await?;
+
+    let new_document = to_document(&category_new)?;
+    let category_res = coll.insert_one(new_document, None).await?;
+    let category_id = from_bson(category_res.inserted_id)?;
+
+    category_by_id(db, category_id).await
+}
+
+// the slug is built from one name only, so adding other translations never
+// changes it
+fn slug_name(names: &BTreeMap<String, String>) -> &str {
+    match names.get("en-us").or_else(|| names.values().next()) {
+        Some(name) => name,
+        None => "",
+    }
+}
+
+// locale -> name map from the translations, empty names are ignored
+fn category_names(
+    translations: &Vec<CategoryName>,
+) -> GqlResult<BTreeMap<String, String>> {
+    let mut names = BTreeMap::new();
+    for translation in translations {
+        let locale = translation.locale.trim().to_lowercase();
+        let name = translation.name.trim();
+        if "".eq(name) || "-".eq(name) {
+            continue;
+        }
+        if "".eq(&locale) || locale.contains('.') || locale.starts_with('$') {
+            return Err(AppError::LocaleInvalid.extend());
+        }
+
+        names.insert(locale, name.to_string());
+    }
+
+    match names.is_empty() {
+        true => Err(AppError::NameInvalid.extend()),
+        false => Ok(names),
+    }
+}
+
+// no other category has the same name in the same locale
+async fn names_exist_check(
+    db: &Database,
+    names: &BTreeMap<String, String>,
+    category_id: Option<ObjectId>,
+) -> GqlResult<()> {
+    let coll = db.collection::<Document>("categories");
+
+    let names_filter: Vec<Document> = names
+        .iter()
+        .map(|(locale, name)| {
+            let mut name_doc = Document::new();
+            name_doc.insert(format!("names.{}", locale), name);
+            name_doc
+        })
+        .collect();
+    let mut filter_doc = doc! {"$or": names_filter};
+    if let Some(category_id) = category_id {
+        filter_doc.insert("_id", doc! {"$ne": category_id});
+    }
+
+    match coll.find_one(filter_doc, None).await? {
+        Some(exist_document) => {
+            let category: Category = from_document(exist_document)?;
+            let (locale, name) = names
+                .iter()
+                .find(|(locale, name)| {
+                    category.names.get(*locale) == Some(*name)
+                })
+                .map(|(locale, name)| (locale.to_string(), name.to_string()))
+                .unwrap_or_default();
+
+            Err(AppError::CategoryExists { name, locale }.extend())
+        }
+        None => Ok(()),
+    }
+}
+
+// replace the timestamped slugs of categories with clean ones, should be run
+// after the names migration
+pub async fn categories_slugs_migrate(db: &Database) -> GqlResult<u64> {
+    slugs_clean_migrate(db, "categories", |category| {
+        let names = category.get_document("names").ok()?;
+        match names.get_str("en-us") {
+            Ok(name) => Some(name.to_string()),
+            Err(_) => names
+                .iter()
+                .find_map(|(_, name)| name.as_str().map(String::from)),
+        }
+    })
+    .await
+}
+
+// move name_zh & name_en of legacy categories into the names map
+pub async fn categories_names_migrate(db: &Database) -> GqlResult<u64> {
+    let coll = db.collection::<Document>("categories");
+
+    let mut cursor =
+        coll.find(doc! {"names": {"$exists": false}}, None).await?;
+
+    let mut migrated_count = 0;
+    while let Some(result) = cursor.next().await {
+        match result {
+            Ok(document) => {
+                let mut names = Document::new();
+                if let Ok(name_zh) = document.get_str("name_zh") {
+                    names.insert("zh-cn", name_zh);
+                }
+                if let Ok(name_en) = document.get_str("name_en") {
+                    names.insert("en-us", name_en);
+                }
+
+                coll.update_one(
+                    doc! {"_id": document.get_object_id("_id")?},
                     doc! {
-                        "name_zh": &category_new.name_zh,
-                        "name_en": &category_new.name_en
+                        "$set": {"names": names},
+                        "$unset": {"name_zh": "", "name_en": ""}
                     },
                     None,
                 )
                 .await?;
-
-            if exist_document.is_none() {
-                let slug_zh = slugify(&category_new.name_zh).await;
-                let slug_en = slugify(&category_new.name_en).await;
-                let slug_ms = DateTime::now().timestamp_millis();
-                if slug_zh == slug_en {
-                    category_new.slug = format!("{}-{}", slug_zh, slug_ms);
-                } else {
-                    category_new.slug =
-                        format!("{}-{}-{}", slug_zh, slug_en, slug_ms);
-                }
-
-                let new_document = to_document(&category_new)?;
-                let category_res = coll
-                    .insert_one(new_document, None)
-                    .await
-                    .expect("写入未成功");
-                let category_id = from_bson(category_res.inserted_id)?;
-
-                category_by_id(db, category_id).await
-            } else {
-                let category: Category =
-                    from_document(exist_document.unwrap())?;
-
-                Err(Error::new(format!(
-                    "{}（中）| {}（英），此类别已创建",
-                    category.name_zh, category.name_en
-                )))
+                migrated_count += 1;
+            }
+            Err(error) => {
+                println!("\n\n\n{}\n\n\n", error);
             }
         }
-        _ => Err(Error::new("名称不合法")),
     }
+
+    Ok(migrated_count)
 }
 
 // Create new category_user
@@ -88,13 +190,12 @@ pub async fn category_user_new(
 
     if exist_document.is_none() {
         let new_document = to_document(&category_user_new)?;
-        let category_user_res =
-            coll.insert_one(new_document, None).await.expect("写入未成功");
+        let category_user_res = coll.insert_one(new_document, None).await?;
         let category_user_id = from_bson(category_user_res.inserted_id)?;
 
         category_user_by_id(db, category_user_id).await
     } else {
-        Err(Error::new("记录已存在"))
+        Err(AppError::RecordExists.extend())
     }
 }
 
@@ -105,21 +206,20 @@ async fn category_user_by_id(
 ) -> GqlResult<CategoryUser> {
     let coll = db.collection::<Document>("categories_users");
 
-    let category_user_document = coll
-        .find_one(doc! {"_id": id}, None)
-        .await
-        .expect("查询未成功")
-        .unwrap();
+    let category_user_document = coll.find_one(doc! {"_id": id}, None).await?;
 
-    let category_user: CategoryUser = from_document(category_user_document)?;
-    Ok(category_user)
+    match category_user_document {
+        Some(document) => Ok(from_document(document)?),
+        None => Err(AppError::RecordNotFound.extend()),
+    }
 }
 
 // get all categories
 pub async fn categories(db: &Database) -> GqlResult<Vec<Category>> {
     let coll = db.collection::<Document>("categories");
 
-    let find_options = FindOptions::builder().sort(doc! {"quotes": -1}).build();
+    let find_options =
+        FindOptions::builder().sort(doc! {"position": 1, "_id": 1}).build();
     let mut cursor = coll.find(None, find_options).await?;
 
     let mut categories: Vec<Category> = vec![];
@@ -138,12 +238,280 @@ pub async fn categories(db: &Database) -> GqlResult<Vec<Category>> {
     Ok(categories)
 }
 
+// get child categories of parent, or the root ones if parent is none
+pub async fn categories_by_parent_id(
+    db: &Database,
+    parent_id: Option<ObjectId>,
+) -> GqlResult<Vec<Category>> {
+    let categories = categories(db).await?;
+
+    Ok(categories
+        .into_iter()
+        .filter(|category| category.parent_id == parent_id)
+        .collect())
+}
+
+// get the whole categories tree, listed depth-first
+pub async fn categories_tree(db: &Database) -> GqlResult<Vec<CategoryNode>> {
+    let categories = categories(db).await?;
+
+    let mut categories_tree: Vec<CategoryNode> = vec![];
+    let mut stack: Vec<CategoryNode> = categories
+        .iter()
+        .rev()
+        .filter(|category| category.parent_id.is_none())
+        .map(|category| CategoryNode { depth: 0, category: category.clone() })
+        .collect();
+    while let Some(node) = stack.pop() {
+        for child in categories
+            .iter()
+            .rev()
+            .filter(|category| category.parent_id == Some(node.category._id))
+        {
+            stack.push(CategoryNode {
+                depth: node.depth + 1,
+                category: child.clone(),
+            });
+        }
+        categories_tree.push(node);
+    }
+
+    Ok(categories_tree)
+}
+
+// get ancestors of category from the root, ended with the category itself
+pub async fn category_breadcrumb(
+    db: &Database,
+    category_id: ObjectId,
+) -> GqlResult<Vec<Category>> {
+    let mut category = category_by_id(db, category_id).await?;
+
+    let mut breadcrumb = vec![category.clone()];
+    while let Some(parent_id) = category.parent_id {
+        // never loop on broken parents
+        if breadcrumb.iter().any(|ancestor| ancestor._id == parent_id) {
+            break;
+        }
+        category = category_by_id(db, parent_id).await?;
+        breadcrumb.push(category.clone());
+    }
+    breadcrumb.reverse();
+
+    Ok(breadcrumb)
+}
+
+// get ids of category and all its descendants
+pub async fn category_descendant_ids(
+    db: &Database,
+    category_id: ObjectId,
+) -> GqlResult<Vec<ObjectId>> {
+    let categories = categories(db).await?;
+
+    let mut category_ids = vec![category_id];
+    let mut index = 0;
+    while index < category_ids.len() {
+        for category in categories.iter() {
+            if category.parent_id == Some(category_ids[index])
+                && !category_ids.contains(&category._id)
+            {
+                category_ids.push(category._id);
+            }
+        }
+        index += 1;
+    }
+
+    Ok(category_ids)
+}
+
+// replace translations of category by moderators, the old slug is kept for
+// redirects when the slug name changes
+pub async fn category_update(
+    db: &Database,
+    cfg: &Config,
+    category_id: ObjectId,
+    category_new: CategoryNew,
+    token: String,
+) -> GqlResult<Category> {
+    moderator_check(db, cfg, token).await?;
+
+    let category = category_by_id(db, category_id).await?;
+    let names = category_names(&category_new.translations)?;
+    names_exist_check(db, &names, Some(category_id)).await?;
+
+    let coll = db.collection::<Document>("categories");
+    coll.update_one(
+        doc! {"_id": category_id},
+        doc! {"$set": {"names": to_document(&names)?}},
+        None,
+    )
+    .await?;
+
+    if slug_name(&names) != slug_name(&category.names) {
+        let slug =
+            slug_unique(db, "categories", slug_name(&names), Some(category_id))
+                .await?;
+        slug_change(db, "categories", category_id, &category.slug, &slug)
+            .await?;
+    }
+
+    category_by_id(db, category_id).await
+}
+
+// move category under the parent (none for root), at the position (from 1)
+// of its new siblings, by moderators
+pub async fn category_move(
+    db: &Database,
+    cfg: &Config,
+    category_id: ObjectId,
+    parent_id: Option<ObjectId>,
+    position: u32,
+    token: String,
+) -> GqlResult<Category> {
+    moderator_check(db, cfg, token).await?;
+
+    category_by_id(db, category_id).await?;
+    if let Some(parent_id) = parent_id {
+        category_by_id(db, parent_id).await?;
+
+        let descendant_ids = category_descendant_ids(db, category_id).await?;
+        if descendant_ids.contains(&parent_id) {
+            return Err(AppError::CategoryMoveCycle.extend());
+        }
+    }
+
+    let mut sibling_ids: Vec<ObjectId> = categories_by_parent_id(db, parent_id)
+        .await?
+        .into_iter()
+        .map(|category| category._id)
+        .filter(|id| *id != category_id)
+        .collect();
+    let position = (position.max(1) as usize).min(sibling_ids.len() + 1);
+    sibling_ids.insert(position - 1, category_id);
+
+    // renumber all siblings, so the positions keep continuous
+    let coll = db.collection::<Document>("categories");
+    for (index, id) in sibling_ids.iter().enumerate() {
+        coll.update_one(
+            doc! {"_id": id},
+            doc! {"$set": {"parent_id": parent_id, "position": index as i64 + 1}},
+            None,
+        )
+        .await?;
+    }
+
+    category_by_id(db, category_id).await
+}
+
+// delete category by moderators: its projects are reassigned to another
+// category, and its children are moved up to its parent.
+pub async fn category_delete(
+    db: &Database,
+    cfg: &Config,
+    category_id: ObjectId,
+    reassign_id: Option<ObjectId>,
+    token: String,
+) -> GqlResult<Category> {
+    moderator_check(db, cfg, token).await?;
+
+    let category = category_by_id(db, category_id).await?;
+
+    let coll_projects = db.collection::<Document>("projects");
+    let projects_count = coll_projects
+        .count_documents(doc! {"category_id": category_id}, None)
+        .await?;
+
+    match reassign_id {
+        Some(reassign_id) => {
+            if reassign_id == category_id {
+                return Err(AppError::CategoryReassignDeleted.extend());
+            }
+            category_by_id(db, reassign_id).await?;
+
+            coll_projects
+                .update_many(
+                    doc! {"category_id": category_id},
+                    doc! {"$set": {"category_id": reassign_id}},
+                    None,
+                )
+                .await?;
+
+            let coll_categories_users =
+                db.collection::<Document>("categories_users");
+            coll_categories_users
+                .update_many(
+                    doc! {"category_id": category_id},
+                    doc! {"$set": {"category_id": reassign_id}},
+                    None,
+                )
+                .await?;
+        }
+        None => {
+            if projects_count > 0 {
+                return Err(AppError::CategoryNotEmpty.extend());
+            }
+
+            let coll_categories_users =
+                db.collection::<Document>("categories_users");
+            coll_categories_users
+                .delete_many(doc! {"category_id": category_id}, None)
+                .await?;
+        }
+    }
+
+    let coll = db.collection::<Document>("categories");
+    let position_base = next_position(db, category.parent_id).await?;
+    let children = categories_by_parent_id(db, Some(category_id)).await?;
+    for (index, child) in children.iter().enumerate() {
+        coll.update_one(
+            doc! {"_id": child._id},
+            doc! {"$set": {
+                "parent_id": category.parent_id,
+                "position": position_base + index as i64
+            }},
+            None,
+        )
+        .await?;
+    }
+
+    coll.delete_one(doc! {"_id": category_id}, None).await?;
+    slugs_history_delete(db, "categories", category_id).await?;
+
+    Ok(category)
+}
+
+// the next position at the end of children of parent
+async fn next_position(
+    db: &Database,
+    parent_id: Option<ObjectId>,
+) -> GqlResult<i64> {
+    let siblings = categories_by_parent_id(db, parent_id).await?;
+    let position = match siblings.iter().map(|sibling| sibling.position).max() {
+        Some(position) => position + 1,
+        None => 1,
+    };
+
+    Ok(position)
+}
+
+async fn moderator_check(
+    db: &Database,
+    cfg: &Config,
+    token: String,
+) -> GqlResult<()> {
+    let user = users::services::user_by_token(db, cfg, token).await?;
+    if user.status < UserStatus::Moderator {
+        return Err(AppError::NoPermission.extend());
+    }
+
+    Ok(())
+}
+
 // get all categories by user_id
 pub async fn categories_by_user_id(
     db: &Database,
     user_id: ObjectId,
 ) -> GqlResult<Vec<Category>> {
-    let categories_users = categories_users_by_user_id(db, user_id).await;
+    let categories_users = categories_users_by_user_id(db, user_id).await?;
 
     let mut category_ids: Vec<ObjectId> = vec![];
     for category_user in categories_users {
@@ -186,50 +554,47 @@ pub async fn category_by_id(
 ) -> GqlResult<Category> {
     let coll = db.collection::<Document>("categories");
 
-    let category_document = coll
-        .find_one(doc! {"_id": id}, None)
-        .await
-        .expect("查询未成功")
-        .unwrap();
+    let category_document = coll.find_one(doc! {"_id": id}, None).await?;
 
-    let category: Category = from_document(category_document)?;
-    Ok(category)
+    match category_document {
+        Some(document) => Ok(from_document(document)?),
+        None => Err(AppError::CategoryNotFound.extend()),
+    }
 }
 
-// get category by its slug
+// get category by its slug, or by an old slug before renaming
 pub async fn category_by_slug(
     db: &Database,
     slug: String,
 ) -> GqlResult<Category> {
     let coll = db.collection::<Document>("categories");
 
-    let category_document = coll
-        .find_one(doc! {"slug": slug.to_lowercase()}, None)
-        .await
-        .expect("查询未成功")
-        .unwrap();
+    let category_document =
+        coll.find_one(doc! {"slug": slug.to_lowercase()}, None).await?;
 
-    let category: Category = from_document(category_document)?;
-    Ok(category)
+    match category_document {
+        Some(document) => Ok(from_document(document)?),
+        None => match slug_history_target_id(db, "categories", &slug).await? {
+            Some(category_id) => category_by_id(db, category_id).await,
+            None => Err(AppError::CategoryNotFound.extend()),
+        },
+    }
 }
 
 // get all CategoryUser list by user_id
 async fn categories_users_by_user_id(
     db: &Database,
     user_id: ObjectId,
-) -> Vec<CategoryUser> {
+) -> GqlResult<Vec<CategoryUser>> {
     let coll_categories_users = db.collection::<Document>("categories_users");
-    let mut cursor_categories_users = coll_categories_users
-        .find(doc! {"user_id": user_id}, None)
-        .await
-        .unwrap();
+    let mut cursor_categories_users =
+        coll_categories_users.find(doc! {"user_id": user_id}, None).await?;
 
     let mut categories_users: Vec<CategoryUser> = vec![];
     while let Some(result) = cursor_categories_users.next().await {
         match result {
             Ok(document) => {
-                let category_user: CategoryUser =
-                    from_document(document).unwrap();
+                let category_user: CategoryUser = from_document(document)?;
                 categories_users.push(category_user);
             }
             Err(error) => {
@@ -238,5 +603,5 @@ async fn categories_users_by_user_id(
         }
     }
 
-    categories_users
+    Ok(categories_users)
 }
@@ -0,0 +1,94 @@
+use serde::{Serialize, Deserialize};
+use mongodb::bson::{oid::ObjectId, DateTime};
+
+use crate::dbs::mongo::DataSource;
+use crate::util::{
+    constant::GqlResult,
+    datetime::{DateStyle, date_formatted, date_nyrsq},
+    common::md2html_safe,
+};
+
+use crate::users::{self, models::User};
+
+#[derive(async_graphql::SimpleObject, Serialize, Deserialize, Clone, Debug)]
+#[graphql(complex)]
+pub struct Comment {
+    pub _id: ObjectId,
+    pub user_id: ObjectId,
+    pub project_id: ObjectId,
+    pub parent_id: Option<ObjectId>,
+    pub thread_id: Option<ObjectId>,
+    pub content: String,
+    pub created_at: DateTime,
+    pub updated_at: DateTime,
+    pub status: i8,
+}
+
+#[async_graphql::ComplexObject]
+impl Comment {
+    pub async fn content_html(&self) -> String {
+        md2html_safe(&self.content).await
+    }
+
+    pub async fn user(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+    ) -> GqlResult<User> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        users::services::user_by_id(db, self.user_id).await
+    }
+
+    // replies of a thread, only resolved on the root comment
+    pub async fn replies(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+    ) -> GqlResult<Vec<Comment>> {
+        if self.thread_id.is_some() {
+            return Ok(vec![]);
+        }
+
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        super::services::comments_by_thread_id(db, self._id).await
+    }
+
+    pub async fn created_at_nyrsq(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+    ) -> String {
+        date_nyrsq(ctx, self.created_at)
+    }
+
+    pub async fn created_at_formatted(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        tz: Option<String>,
+        locale: Option<String>,
+        #[graphql(default)] style: DateStyle,
+    ) -> GqlResult<String> {
+        date_formatted(ctx, self.created_at, tz, locale, style)
+    }
+
+    pub async fn updated_at_nyrsq(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+    ) -> String {
+        date_nyrsq(ctx, self.updated_at)
+    }
+
+    pub async fn updated_at_formatted(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        tz: Option<String>,
+        locale: Option<String>,
+        #[graphql(default)] style: DateStyle,
+    ) -> GqlResult<String> {
+        date_formatted(ctx, self.updated_at, tz, locale, style)
+    }
+}
+
+#[derive(async_graphql::InputObject, Serialize, Deserialize)]
+pub struct CommentNew {
+    pub project_id: ObjectId,
+    pub parent_id: Option<ObjectId>,
+    pub content: String,
+}
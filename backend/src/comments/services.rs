@@ -0,0 +1,381 @@
+use futures::stream::StreamExt;
+use mongodb::{
+    Database,
+    bson::{oid::ObjectId, DateTime, Document, doc, from_document, from_bson},
+    options::FindOptions,
+};
+use async_graphql::ErrorExtensions;
+
+use crate::util::{
+    broker::SimpleBroker,
+    config::Config,
+    constant::GqlResult,
+    error::AppError,
+    pagination::{
+        CommentsResult, PageInfo, ResCount, count_pages_and_total,
+        calculate_current_filter_skip, find_options,
+    },
+};
+
+use crate::{
+    users,
+    users::models::{User, UserStatus},
+};
+use crate::{projects, projects::models::ProjectStatus};
+use crate::notifications::{
+    self,
+    models::{NotificationKind, NotificationNew},
+};
+use crate::idempotency::services::{idempotency_key_check, idempotency_key_settle};
+use super::models::{Comment, CommentNew};
+
+const COMMENTS_STUFF: &str = "comments";
+// comments of this status and above are visible to everyone
+pub const COMMENT_STATUS_VISIBLE: i32 = 1;
+const COMMENT_STATUS_HIDDEN: i32 = -1;
+const COMMENT_STATUS_DELETED: i32 = -3;
+
+// Create new comment, or reply to a comment. A retry with the same
+// idempotency key gets the comment created at first.
+pub async fn comment_new(
+    db: &Database,
+    cfg: &Config,
+    comment_new: CommentNew,
+    token: String,
+    idempotency_key: Option<String>,
+) -> GqlResult<Comment> {
+    let user = users::services::user_by_token(db, cfg, token).await?;
+    let user_id = Some(user._id);
+    if let Some(comment_id) = idempotency_key_check(
+        db,
+        "comments",
+        user_id,
+        &idempotency_key,
+        &comment_new,
+    )
+    .await?
+    {
+        return comment_by_id(db, comment_id).await;
+    }
+
+    let comment_res = comment_insert(db, comment_new, user).await;
+    let comment_id = comment_res.as_ref().ok().map(|comment| comment._id);
+    idempotency_key_settle(
+        db,
+        "comments",
+        user_id,
+        &idempotency_key,
+        comment_id,
+    )
+    .await?;
+
+    comment_res
+}
+
+async fn comment_insert(
+    db: &Database,
+    comment_new: CommentNew,
+    user: User,
+) -> GqlResult<Comment> {
+    let project =
+        projects::services::project_by_id(db, comment_new.project_id).await?;
+
+    if project.status < ProjectStatus::Published || project.comments_disabled {
+        return Err(AppError::CommentsClosed.extend());
+    }
+
+    let content = comment_new.content.trim();
+    if content.is_empty() {
+        return Err(AppError::CommentEmpty.extend());
+    }
+
+    // replies are flattened into the thread of the root comment
+    let parent = match comment_new.parent_id {
+        Some(parent_id) => Some(comment_by_id(db, parent_id).await?),
+        None => None,
+    };
+    let thread_id = if let Some(parent) = &parent {
+        if parent.project_id != project._id {
+            return Err(AppError::CommentParentMismatch.extend());
+        }
+
+        Some(parent.thread_id.unwrap_or(parent._id))
+    } else {
+        None
+    };
+
+    let now = DateTime::now();
+    let new_document = doc! {
+        "user_id": user._id,
+        "project_id": project._id,
+        "parent_id": comment_new.parent_id,
+        "thread_id": thread_id,
+        "content": content,
+        "created_at": now,
+        "updated_at": now,
+        "status": COMMENT_STATUS_VISIBLE
+    };
+
+    let coll = db.collection::<Document>("comments");
+    let comment_res = coll.insert_one(new_document, None).await?;
+    let comment_id = from_bson(comment_res.inserted_id)?;
+
+    let notification_new = NotificationNew {
+        user_id: project.user_id,
+        actor_id: Some(user._id),
+        kind: NotificationKind::ProjectCommented,
+        project_id: project._id,
+        comment_id: Some(comment_id),
+        project_status: None,
+    };
+    notifications::services::notification_notify(db, notification_new).await;
+
+    // the project owner has been notified above
+    if let Some(parent) = parent {
+        if parent.user_id != project.user_id {
+            let notification_new = NotificationNew {
+                user_id: parent.user_id,
+                actor_id: Some(user._id),
+                kind: NotificationKind::CommentReplied,
+                project_id: project._id,
+                comment_id: Some(comment_id),
+                project_status: None,
+            };
+            notifications::services::notification_notify(db, notification_new)
+                .await;
+        }
+    }
+
+    let comment = comment_by_id(db, comment_id).await?;
+    SimpleBroker::publish(comment.clone());
+
+    Ok(comment)
+}
+
+// update comment content by its author
+pub async fn comment_update(
+    db: &Database,
+    cfg: &Config,
+    comment_id: ObjectId,
+    content: String,
+    token: String,
+) -> GqlResult<Comment> {
+    let user = users::services::user_by_token(db, cfg, token).await?;
+    let comment = comment_by_id(db, comment_id).await?;
+
+    if comment.user_id != user._id
+        || i32::from(comment.status) < COMMENT_STATUS_VISIBLE
+    {
+        return Err(AppError::NoPermission.extend());
+    }
+
+    let content = content.trim();
+    if content.is_empty() {
+        return Err(AppError::CommentEmpty.extend());
+    }
+
+    let coll = db.collection::<Document>("comments");
+    coll.update_one(
+        doc! {"_id": comment_id},
+        doc! {"$set": {"content": content, "updated_at": DateTime::now()}},
+        None,
+    )
+    .await?;
+
+    comment_by_id(db, comment_id).await
+}
+
+// delete comment by its author
+pub async fn comment_delete(
+    db: &Database,
+    cfg: &Config,
+    comment_id: ObjectId,
+    token: String,
+) -> GqlResult<Comment> {
+    let user = users::services::user_by_token(db, cfg, token).await?;
+    let comment = comment_by_id(db, comment_id).await?;
+
+    if comment.user_id != user._id {
+        return Err(AppError::NoPermission.extend());
+    }
+
+    let coll = db.collection::<Document>("comments");
+    coll.update_one(
+        doc! {"_id": comment_id},
+        doc! {"$set": {
+            "status": COMMENT_STATUS_DELETED,
+            "updated_at": DateTime::now()
+        }},
+        None,
+    )
+    .await?;
+
+    comment_by_id(db, comment_id).await
+}
+
+// hide or show comment by moderators
+pub async fn comment_hide(
+    db: &Database,
+    cfg: &Config,
+    comment_id: ObjectId,
+    hidden: bool,
+    token: String,
+) -> GqlResult<Comment> {
+    let user = users::services::user_by_token(db, cfg, token).await?;
+    if user.status < UserStatus::Moderator {
+        return Err(AppError::NoPermission.extend());
+    }
+
+    let status =
+        if hidden { COMMENT_STATUS_HIDDEN } else { COMMENT_STATUS_VISIBLE };
+
+    // comments deleted by their authors could not be shown again
+    let coll = db.collection::<Document>("comments");
+    coll.update_one(
+        doc! {"_id": comment_id, "status": {"$ne": COMMENT_STATUS_DELETED}},
+        doc! {"$set": {"status": status}},
+        None,
+    )
+    .await?;
+
+    comment_by_id(db, comment_id).await
+}
+
+// get comment by its id
+pub async fn comment_by_id(db: &Database, id: ObjectId) -> GqlResult<Comment> {
+    let coll = db.collection::<Document>("comments");
+
+    let comment_document = coll.find_one(doc! {"_id": id}, None).await?;
+    if let Some(document) = comment_document {
+        let comment: Comment = from_document(document)?;
+        Ok(comment)
+    } else {
+        Err(AppError::CommentNotFound.extend())
+    }
+}
+
+// Get root comments of one project by project_id
+pub async fn comments_by_project_id(
+    db: &Database,
+    cfg: &Config,
+    project_id: ObjectId,
+    from_page: u32,
+    first_oid: String,
+    last_oid: String,
+) -> GqlResult<CommentsResult> {
+    let coll = db.collection::<Document>("comments");
+
+    let mut filter_doc = doc! {
+        "project_id": project_id,
+        "thread_id": null,
+        "status": {"$gte": COMMENT_STATUS_VISIBLE}
+    };
+
+    let (pages_count, total_count) = count_pages_and_total(
+        &coll,
+        Some(filter_doc.clone()),
+        None,
+        cfg.page_size,
+    )
+    .await?;
+    let (current_page, skip_x) = calculate_current_filter_skip(
+        from_page,
+        first_oid,
+        last_oid,
+        &mut filter_doc,
+    )
+    .await?;
+
+    let sort_doc = doc! {"_id": -1};
+    let find_options =
+        find_options(Some(sort_doc), skip_x, cfg.page_size).await;
+
+    let mut cursor = coll.find(filter_doc, find_options).await?;
+
+    let mut comments: Vec<Comment> = vec![];
+    while let Some(result) = cursor.next().await {
+        match result {
+            Ok(document) => {
+                let comment = from_document(document)?;
+                comments.push(comment);
+            }
+            Err(error) => {
+                println!("\n\n\n{}\n\n\n", error);
+            }
+        }
+    }
+
+    let comments_result = CommentsResult {
+        page_info: PageInfo {
+            current_stuff: Some(String::from(COMMENTS_STUFF)),
+            current_page: Some(current_page),
+            first_cursor: match comments.first() {
+                Some(comment) => Some(comment._id),
+                _ => None,
+            },
+            last_cursor: match comments.last() {
+                Some(comment) => Some(comment._id),
+                _ => None,
+            },
+            has_previous_page: current_page > 1,
+            has_next_page: current_page < pages_count,
+        },
+        res_count: ResCount {
+            pages_count: Some(pages_count),
+            total_count: Some(total_count),
+        },
+        current_items: comments,
+    };
+
+    Ok(comments_result)
+}
+
+// get all visible replies of one thread, in posted order
+pub async fn comments_by_thread_id(
+    db: &Database,
+    thread_id: ObjectId,
+) -> GqlResult<Vec<Comment>> {
+    let coll = db.collection::<Document>("comments");
+
+    let filter_doc = doc! {
+        "thread_id": thread_id,
+        "status": {"$gte": COMMENT_STATUS_VISIBLE}
+    };
+    let find_options = FindOptions::builder().sort(doc! {"_id": 1}).build();
+    let mut cursor = coll.find(filter_doc, find_options).await?;
+
+    let mut comments: Vec<Comment> = vec![];
+    while let Some(result) = cursor.next().await {
+        match result {
+            Ok(document) => {
+                let comment = from_document(document)?;
+                comments.push(comment);
+            }
+            Err(error) => {
+                println!("\n\n\n{}\n\n\n", error);
+            }
+        }
+    }
+
+    Ok(comments)
+}
+
+// count visible comments of one project
+pub async fn comments_count_by_project_id(
+    db: &Database,
+    project_id: ObjectId,
+) -> GqlResult<u64> {
+    let coll = db.collection::<Document>("comments");
+
+    let comments_count = coll
+        .count_documents(
+            doc! {
+                "project_id": project_id,
+                "status": {"$gte": COMMENT_STATUS_VISIBLE}
+            },
+            None,
+        )
+        .await?;
+
+    Ok(comments_count)
+}
@@ -1,6 +1,26 @@
-use crate::util::constant::CFG;
+use std::time::Duration;
 
-use mongodb::{Client, options::ClientOptions, Database};
+use async_graphql::ErrorExtensions;
+
+use crate::util::{config::Config, constant::GqlResult, error::AppError};
+
+use mongodb::{
+    Client, ClientSession, Database, IndexModel,
+    bson::{Document, doc},
+    error::{
+        Error, ErrorKind, UNKNOWN_TRANSACTION_COMMIT_RESULT, WriteFailure,
+    },
+    options::{ClientOptions, IndexOptions},
+};
+
+// code of the server error on a duplicate key of a unique index
+const DUPLICATE_KEY_CODE: i32 = 11000;
+// code of the server error on creating an existing index with other options
+const INDEX_OPTIONS_CONFLICT_CODE: i32 = 85;
+
+// times a commit with an unknown result is retried, waiting longer each time
+const COMMIT_RETRIES: u32 = 5;
+const COMMIT_RETRY_MILLIS: u64 = 50;
 
 pub struct DataSource {
     client: Client,
@@ -13,24 +33,273 @@ impl DataSource {
         self.client.clone()
     }
 
-    pub async fn init() -> DataSource {
+    pub async fn init(cfg: &Config) -> DataSource {
         // Parse a connection string into an options struct.
-        // environment variables defined in .env file
-        let mut client_options =
-            ClientOptions::parse(CFG.get("MONGODB_URI").unwrap())
-                .await
-                .expect("Failed to parse options!");
-        client_options.app_name =
-            Some(String::from(CFG.get("SITE_KID").unwrap()));
+        let mut client_options = ClientOptions::parse(&cfg.mongodb_uri)
+            .await
+            .expect("Failed to parse options!");
+        client_options.app_name = Some(cfg.site_kid.clone());
 
         // Get a handle to the deployment.
         let client = Client::with_options(client_options)
             .expect("Failed to initialize database!");
 
         // Get a handle to a database.
-        let db = client.database(CFG.get("MONGODB_NAME").unwrap());
+        let db = client.database(&cfg.mongodb_name);
 
         // return mongodb datasource.
         DataSource { client, db }
     }
+
+    // Create indexes which the services rely on. Creating an existing index
+    // is a no-op in mongodb, so it is safe to call on every start.
+    pub async fn init_indexes(&self, cfg: &Config) {
+        let project_stars_index = IndexModel::builder()
+            .keys(doc! {"user_id": 1, "project_id": 1})
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+        self.db
+            .collection::<Document>("project_stars")
+            .create_index(project_stars_index, None)
+            .await
+            .expect("Failed to create index of project_stars!");
+
+        let users_follows_index = IndexModel::builder()
+            .keys(doc! {"follower_id": 1, "user_id": 1})
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+        self.db
+            .collection::<Document>("users_follows")
+            .create_index(users_follows_index, None)
+            .await
+            .expect("Failed to create index of users_follows!");
+
+        let notifications_index =
+            IndexModel::builder().keys(doc! {"user_id": 1, "read": 1}).build();
+        self.db
+            .collection::<Document>("notifications")
+            .create_index(notifications_index, None)
+            .await
+            .expect("Failed to create index of notifications!");
+
+        let projects_indexes = vec![
+            IndexModel::builder().keys(doc! {"remix_of": 1}).build(),
+            IndexModel::builder().keys(doc! {"slug": 1}).build(),
+        ];
+        self.db
+            .collection::<Document>("projects")
+            .create_indexes(projects_indexes, None)
+            .await
+            .expect("Failed to create indexes of projects!");
+
+        let projects_trending_index = IndexModel::builder()
+            .keys(doc! {"window": 1, "computed_at": -1, "rank": 1})
+            .build();
+        self.db
+            .collection::<Document>("projects_trending")
+            .create_index(projects_trending_index, None)
+            .await
+            .expect("Failed to create index of projects_trending!");
+
+        // views of one visitor expire with the dedup window
+        let projects_views_index = IndexModel::builder()
+            .keys(doc! {"project_id": 1, "visitor": 1})
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+        self.db
+            .collection::<Document>("projects_views")
+            .create_index(projects_views_index, None)
+            .await
+            .expect("Failed to create index of projects_views!");
+        self.ttl_index_apply(
+            "projects_views",
+            "viewed_at",
+            cfg.view_dedup_minutes * 60,
+        )
+        .await;
+
+        let projects_views_daily_indexes = vec![
+            IndexModel::builder()
+                .keys(doc! {"project_id": 1, "day": 1})
+                .options(IndexOptions::builder().unique(true).build())
+                .build(),
+            IndexModel::builder().keys(doc! {"day": 1}).build(),
+        ];
+        self.db
+            .collection::<Document>("projects_views_daily")
+            .create_indexes(projects_views_daily_indexes, None)
+            .await
+            .expect("Failed to create indexes of projects_views_daily!");
+
+        let projects_downloads_daily_index = IndexModel::builder()
+            .keys(doc! {"project_id": 1, "day": 1})
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+        self.db
+            .collection::<Document>("projects_downloads_daily")
+            .create_index(projects_downloads_daily_index, None)
+            .await
+            .expect("Failed to create index of projects_downloads_daily!");
+
+        let studios_index =
+            IndexModel::builder().keys(doc! {"slug": 1}).build();
+        self.db
+            .collection::<Document>("studios")
+            .create_index(studios_index, None)
+            .await
+            .expect("Failed to create index of studios!");
+
+        let studios_curators_index = IndexModel::builder()
+            .keys(doc! {"studio_id": 1, "user_id": 1})
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+        self.db
+            .collection::<Document>("studios_curators")
+            .create_index(studios_curators_index, None)
+            .await
+            .expect("Failed to create index of studios_curators!");
+
+        let studios_projects_index = IndexModel::builder()
+            .keys(doc! {"studio_id": 1, "project_id": 1})
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+        self.db
+            .collection::<Document>("studios_projects")
+            .create_index(studios_projects_index, None)
+            .await
+            .expect("Failed to create index of studios_projects!");
+
+        // prefix matching of topics suggestions
+        let topics_indexes = vec![
+            IndexModel::builder().keys(doc! {"name": 1}).build(),
+            IndexModel::builder().keys(doc! {"slug": 1}).build(),
+        ];
+        self.db
+            .collection::<Document>("topics")
+            .create_indexes(topics_indexes, None)
+            .await
+            .expect("Failed to create indexes of topics!");
+
+        let slugs_history_index = IndexModel::builder()
+            .keys(doc! {"kind": 1, "slug": 1})
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+        self.db
+            .collection::<Document>("slugs_history")
+            .create_index(slugs_history_index, None)
+            .await
+            .expect("Failed to create index of slugs_history!");
+
+        // Keys of create mutations expire, then could be used again. They
+        // are unique by the caller, instead of the old index by kind & key.
+        let coll_idempotency_keys =
+            self.db.collection::<Document>("idempotency_keys");
+        coll_idempotency_keys.drop_index("kind_1_key_1", None).await.ok();
+        let idempotency_keys_index = IndexModel::builder()
+            .keys(doc! {"kind": 1, "user_id": 1, "key": 1})
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+        coll_idempotency_keys
+            .create_index(idempotency_keys_index, None)
+            .await
+            .expect("Failed to create index of idempotency_keys!");
+        self.ttl_index_apply(
+            "idempotency_keys",
+            "created_at",
+            cfg.idempotency_key_hours * 3600,
+        )
+        .await;
+    }
+
+    // Create the TTL index of the field. The expiry comes from the config,
+    // and an index created with another expiry is changed by collMod, as
+    // creating it again conflicts. A failure only leaves documents to expire
+    // as before, so it is reported without stopping the start.
+    async fn ttl_index_apply(
+        &self,
+        coll_name: &str,
+        field: &str,
+        expire_secs: u64,
+    ) {
+        let ttl_index = IndexModel::builder()
+            .keys(doc! {field: 1})
+            .options(
+                IndexOptions::builder()
+                    .expire_after(Duration::from_secs(expire_secs))
+                    .build(),
+            )
+            .build();
+        let create_res = self
+            .db
+            .collection::<Document>(coll_name)
+            .create_index(ttl_index, None)
+            .await;
+
+        let error = match create_res {
+            Ok(_) => return,
+            Err(error) if is_index_options_conflict(&error) => {
+                let coll_mod = doc! {
+                    "collMod": coll_name,
+                    "index": {
+                        "keyPattern": {field: 1},
+                        "expireAfterSeconds": expire_secs as i64
+                    }
+                };
+                match self.db.run_command(coll_mod, None).await {
+                    Ok(_) => return,
+                    Err(error) => error,
+                }
+            }
+            Err(error) => error,
+        };
+        println!(
+            "\n\n\nFailed to apply the TTL index of {}.{}: {}\n\n\n",
+            coll_name, field, error
+        );
+    }
+}
+
+// Commit the transaction of the session. A commit whose result is unknown is
+// safe to retry, but only a few times with a growing wait, and the failure is
+// reported after that.
+pub async fn commit_transaction(session: &mut ClientSession) -> GqlResult<()> {
+    let mut retries = 0;
+    loop {
+        match session.commit_transaction().await {
+            Ok(()) => return Ok(()),
+            Err(error)
+                if error.contains_label(UNKNOWN_TRANSACTION_COMMIT_RESULT)
+                    && retries < COMMIT_RETRIES =>
+            {
+                retries += 1;
+                async_std::task::sleep(Duration::from_millis(
+                    COMMIT_RETRY_MILLIS * 2u64.pow(retries - 1),
+                ))
+                .await;
+            }
+            Err(error) => {
+                println!("\n\n\n{}\n\n\n", error);
+                return Err(AppError::DatabaseFailed.extend());
+            }
+        }
+    }
+}
+
+// Whether the write failed only because a unique index already has the key,
+// such as a concurrent upsert of the same document.
+pub fn is_duplicate_key(error: &Error) -> bool {
+    matches!(
+        error.kind.as_ref(),
+        ErrorKind::Write(WriteFailure::WriteError(write_error))
+            if write_error.code == DUPLICATE_KEY_CODE
+    )
+}
+
+// Whether the index exists with other options, such as another expiry
+fn is_index_options_conflict(error: &Error) -> bool {
+    matches!(
+        error.kind.as_ref(),
+        ErrorKind::Command(command_error)
+            if command_error.code == INDEX_OPTIONS_CONFLICT_CODE
+    )
 }
@@ -0,0 +1,478 @@
+use std::collections::HashMap;
+use futures::stream::StreamExt;
+use mongodb::{
+    Database,
+    bson::{oid::ObjectId, Document, doc, from_document},
+};
+use async_graphql::{async_trait, dataloader::Loader, Error};
+
+use crate::users::models::User;
+use crate::categories::models::Category;
+use crate::topics::models::{Topic, TopicProject};
+use crate::projects::models::{File, ProjectFile};
+
+// Loaders batch the lookups of nested fields in one query per field, instead
+// of one query per parent object. They hold no cache, so nothing is stale
+// across requests.
+
+// users by id
+pub struct UserLoader(pub Database);
+
+#[async_trait::async_trait]
+impl Loader<ObjectId> for UserLoader {
+    type Value = User;
+    type Error = Error;
+
+    async fn load(
+        &self,
+        keys: &[ObjectId],
+    ) -> Result<HashMap<ObjectId, User>, Error> {
+        let coll = self.0.collection::<Document>("users");
+        let mut cursor = coll.find(doc! {"_id": {"$in": keys}}, None).await?;
+
+        let mut users: HashMap<ObjectId, User> = HashMap::new();
+        while let Some(result) = cursor.next().await {
+            match result {
+                Ok(document) => {
+                    let user: User = from_document(document)?;
+                    users.insert(user._id, user);
+                }
+                Err(error) => {
+                    println!("\n\n\n{}\n\n\n", error);
+                }
+            }
+        }
+
+        Ok(users)
+    }
+}
+
+// categories by id
+pub struct CategoryLoader(pub Database);
+
+#[async_trait::async_trait]
+impl Loader<ObjectId> for CategoryLoader {
+    type Value = Category;
+    type Error = Error;
+
+    async fn load(
+        &self,
+        keys: &[ObjectId],
+    ) -> Result<HashMap<ObjectId, Category>, Error> {
+        let coll = self.0.collection::<Document>("categories");
+        let mut cursor = coll.find(doc! {"_id": {"$in": keys}}, None).await?;
+
+        let mut categories: HashMap<ObjectId, Category> = HashMap::new();
+        while let Some(result) = cursor.next().await {
+            match result {
+                Ok(document) => {
+                    let category: Category = from_document(document)?;
+                    categories.insert(category._id, category);
+                }
+                Err(error) => {
+                    println!("\n\n\n{}\n\n\n", error);
+                }
+            }
+        }
+
+        Ok(categories)
+    }
+}
+
+// all files of projects by project id, such as cover images & source files
+pub struct ProjectFilesLoader(pub Database);
+
+#[async_trait::async_trait]
+impl Loader<ObjectId> for ProjectFilesLoader {
+    type Value = Vec<File>;
+    type Error = Error;
+
+    async fn load(
+        &self,
+        keys: &[ObjectId],
+    ) -> Result<HashMap<ObjectId, Vec<File>>, Error> {
+        let coll_projects_files =
+            self.0.collection::<Document>("projects_files");
+        let mut cursor = coll_projects_files
+            .find(doc! {"project_id": {"$in": keys}}, None)
+            .await?;
+
+        let mut projects_files: Vec<ProjectFile> = vec![];
+        while let Some(result) = cursor.next().await {
+            match result {
+                Ok(document) => {
+                    let project_file: ProjectFile = from_document(document)?;
+                    projects_files.push(project_file);
+                }
+                Err(error) => {
+                    println!("\n\n\n{}\n\n\n", error);
+                }
+            }
+        }
+
+        let file_ids: Vec<ObjectId> = projects_files
+            .iter()
+            .map(|project_file| project_file.file_id)
+            .collect();
+        let coll_files = self.0.collection::<Document>("files");
+        let mut cursor =
+            coll_files.find(doc! {"_id": {"$in": file_ids}}, None).await?;
+
+        let mut files: HashMap<ObjectId, File> = HashMap::new();
+        while let Some(result) = cursor.next().await {
+            match result {
+                Ok(document) => {
+                    let file: File = from_document(document)?;
+                    files.insert(file._id, file);
+                }
+                Err(error) => {
+                    println!("\n\n\n{}\n\n\n", error);
+                }
+            }
+        }
+
+        let mut files_by_project: HashMap<ObjectId, Vec<File>> = HashMap::new();
+        for project_file in projects_files {
+            if let Some(file) = files.get(&project_file.file_id) {
+                files_by_project
+                    .entry(project_file.project_id)
+                    .or_default()
+                    .push(file.clone());
+            }
+        }
+
+        Ok(files_by_project)
+    }
+}
+
+// topics of projects by project id, the most quoted first
+pub struct ProjectTopicsLoader(pub Database);
+
+#[async_trait::async_trait]
+impl Loader<ObjectId> for ProjectTopicsLoader {
+    type Value = Vec<Topic>;
+    type Error = Error;
+
+    async fn load(
+        &self,
+        keys: &[ObjectId],
+    ) -> Result<HashMap<ObjectId, Vec<Topic>>, Error> {
+        let coll_topics_projects =
+            self.0.collection::<Document>("topics_users_projects");
+        let mut cursor = coll_topics_projects
+            .find(doc! {"project_id": {"$in": keys}}, None)
+            .await?;
+
+        let mut topics_projects: Vec<TopicProject> = vec![];
+        while let Some(result) = cursor.next().await {
+            match result {
+                Ok(document) => {
+                    let topic_project: TopicProject = from_document(document)?;
+                    topics_projects.push(topic_project);
+                }
+                Err(error) => {
+                    println!("\n\n\n{}\n\n\n", error);
+                }
+            }
+        }
+
+        let topic_ids: Vec<ObjectId> = topics_projects
+            .iter()
+            .map(|topic_project| topic_project.topic_id)
+            .collect();
+        let coll_topics = self.0.collection::<Document>("topics");
+        let mut cursor =
+            coll_topics.find(doc! {"_id": {"$in": topic_ids}}, None).await?;
+
+        let mut topics: HashMap<ObjectId, Topic> = HashMap::new();
+        while let Some(result) = cursor.next().await {
+            match result {
+                Ok(document) => {
+                    let topic: Topic = from_document(document)?;
+                    topics.insert(topic._id, topic);
+                }
+                Err(error) => {
+                    println!("\n\n\n{}\n\n\n", error);
+                }
+            }
+        }
+
+        let mut topics_by_project: HashMap<ObjectId, Vec<Topic>> =
+            HashMap::new();
+        for topic_project in topics_projects {
+            if let Some(topic) = topics.get(&topic_project.topic_id) {
+                let project_topics = topics_by_project
+                    .entry(topic_project.project_id)
+                    .or_default();
+                // a topic could be linked to the project by different users
+                if !project_topics.iter().any(|t| t._id == topic._id) {
+                    project_topics.push(topic.clone());
+                }
+            }
+        }
+        for project_topics in topics_by_project.values_mut() {
+            project_topics.sort_by(|a, b| b.quotes.cmp(&a.quotes));
+        }
+
+        Ok(topics_by_project)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    };
+    use async_graphql::{
+        Context, EmptyMutation, EmptySubscription, Object, Schema,
+        dataloader::DataLoader,
+    };
+    use mongodb::{
+        Client,
+        bson::{Bson, DateTime},
+        event::command::{CommandEventHandler, CommandStartedEvent},
+        options::ClientOptions,
+    };
+
+    use crate::projects::models::{Project, FileKind, ProjectStatus};
+    use crate::users::models::UserStatus;
+
+    use super::*;
+
+    // finds sent to the database, counted by the command monitor
+    #[derive(Default)]
+    struct FindCounter(AtomicUsize);
+
+    impl CommandEventHandler for FindCounter {
+        fn handle_command_started_event(&self, event: CommandStartedEvent) {
+            if event.command_name == "find" {
+                self.0.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+    }
+
+    // all projects of the database, in one find
+    struct QueryRoot;
+
+    #[Object]
+    impl QueryRoot {
+        async fn projects(
+            &self,
+            ctx: &Context<'_>,
+        ) -> async_graphql::Result<Vec<Project>> {
+            let db = ctx.data_unchecked::<Database>();
+            let mut cursor =
+                db.collection::<Project>("projects").find(None, None).await?;
+
+            let mut projects = vec![];
+            while let Some(project) = cursor.next().await {
+                projects.push(project?);
+            }
+
+            Ok(projects)
+        }
+    }
+
+    // projects with their user, category, 2 topics, cover & source file
+    async fn projects_seed(db: &Database, n: usize) {
+        let now = DateTime::now();
+        for i in 0..n {
+            let user_id = ObjectId::new();
+            let category_id = ObjectId::new();
+            let project_id = ObjectId::new();
+
+            db.collection::<Document>("users")
+                .insert_one(
+                    doc! {
+                        "_id": user_id,
+                        "username": format!("user-{}", i),
+                        "email": format!("user-{}@example.com", i),
+                        "cred": "-",
+                        "nickname": "-",
+                        "phone_number": "-",
+                        "phone_public": false,
+                        "im_account": "-",
+                        "im_public": false,
+                        "website": "-",
+                        "introduction": "-",
+                        "created_at": now,
+                        "updated_at": now,
+                        "hits": 0_i64,
+                        "status": Bson::from(UserStatus::Active),
+                    },
+                    None,
+                )
+                .await
+                .unwrap();
+            db.collection::<Document>("categories")
+                .insert_one(
+                    doc! {
+                        "_id": category_id,
+                        "names": {"en-us": format!("category-{}", i)},
+                        "slug": format!("category-{}", i),
+                    },
+                    None,
+                )
+                .await
+                .unwrap();
+            db.collection::<Document>("projects")
+                .insert_one(
+                    doc! {
+                        "_id": project_id,
+                        "user_id": user_id,
+                        "category_id": category_id,
+                        "subject": format!("project-{}", i),
+                        "content": "-",
+                        "contact_user": "-",
+                        "contact_phone": "-",
+                        "contact_email": "-",
+                        "contact_im": "-",
+                        "created_at": now,
+                        "updated_at": now,
+                        "hits": 0_i64,
+                        "insides": 0_i64,
+                        "stars": 0_i64,
+                        "language": "en-us",
+                        "status": Bson::from(ProjectStatus::Published),
+                    },
+                    None,
+                )
+                .await
+                .unwrap();
+
+            for t in 0..2 {
+                let topic_id = ObjectId::new();
+                db.collection::<Document>("topics")
+                    .insert_one(
+                        doc! {
+                            "_id": topic_id,
+                            "name": format!("topic-{}-{}", i, t),
+                            "quotes": 1_i64,
+                            "slug": format!("topic-{}-{}", i, t),
+                        },
+                        None,
+                    )
+                    .await
+                    .unwrap();
+                db.collection::<Document>("topics_users_projects")
+                    .insert_one(
+                        doc! {
+                            "user_id": user_id,
+                            "project_id": project_id,
+                            "topic_id": topic_id,
+                        },
+                        None,
+                    )
+                    .await
+                    .unwrap();
+            }
+
+            for kind in [FileKind::Cover, FileKind::Source] {
+                let file_id = ObjectId::new();
+                db.collection::<Document>("files")
+                    .insert_one(
+                        doc! {
+                            "_id": file_id,
+                            "name": format!("file-{}", i),
+                            "kind": Bson::from(kind),
+                            "location": format!("/files/{}", file_id),
+                        },
+                        None,
+                    )
+                    .await
+                    .unwrap();
+                db.collection::<Document>("projects_files")
+                    .insert_one(
+                        doc! {
+                            "user_id": user_id,
+                            "project_id": project_id,
+                            "file_id": file_id,
+                        },
+                        None,
+                    )
+                    .await
+                    .unwrap();
+            }
+        }
+    }
+
+    // finds of one page of n projects with all their nested fields
+    async fn projects_finds(
+        client: &Client,
+        counter: &FindCounter,
+        n: usize,
+    ) -> usize {
+        let db = client.database(&format!("kids_loaders_test_{}", n));
+        db.drop(None).await.unwrap();
+        projects_seed(&db, n).await;
+
+        let schema = Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+            .data(db.clone())
+            .data(DataLoader::new(
+                UserLoader(db.clone()),
+                async_std::task::spawn,
+            ))
+            .data(DataLoader::new(
+                CategoryLoader(db.clone()),
+                async_std::task::spawn,
+            ))
+            .data(DataLoader::new(
+                ProjectFilesLoader(db.clone()),
+                async_std::task::spawn,
+            ))
+            .data(DataLoader::new(
+                ProjectTopicsLoader(db.clone()),
+                async_std::task::spawn,
+            ))
+            .finish();
+
+        counter.0.store(0, Ordering::SeqCst);
+        let resp = schema
+            .execute(
+                "{ projects { subject user { username } category { slug } \
+                 topics { name } coverImage { location } \
+                 sourceFile { location } } }",
+            )
+            .await;
+        let finds = counter.0.load(Ordering::SeqCst);
+
+        assert!(resp.errors.is_empty(), "{:?}", resp.errors);
+        let projects = resp.data.into_json().unwrap()["projects"].clone();
+        assert_eq!(projects.as_array().map(Vec::len), Some(n));
+        for project in projects.as_array().unwrap() {
+            assert_eq!(project["topics"].as_array().map(Vec::len), Some(2));
+        }
+
+        db.drop(None).await.unwrap();
+        finds
+    }
+
+    // The finds of a page are constant: the projects, users, categories,
+    // projects_files, files, topics_users_projects & topics. Needs a MongoDB
+    // of MONGODB_URI, and is skipped without it.
+    #[async_std::test]
+    async fn projects_nested_fields_finds_constant() {
+        dotenv::dotenv().ok();
+        let mongodb_uri = match dotenv::var("MONGODB_URI") {
+            Ok(mongodb_uri) => mongodb_uri,
+            Err(_) => {
+                println!("MONGODB_URI is not set, skipped");
+                return;
+            }
+        };
+
+        let counter = Arc::new(FindCounter::default());
+        let mut client_options =
+            ClientOptions::parse(&mongodb_uri).await.unwrap();
+        client_options.command_event_handler =
+            Some(counter.clone() as Arc<dyn CommandEventHandler>);
+        let client = Client::with_options(client_options).unwrap();
+
+        let finds_few = projects_finds(&client, &counter, 2).await;
+        let finds_many = projects_finds(&client, &counter, 12).await;
+
+        assert_eq!(finds_few, 7);
+        assert_eq!(finds_many, finds_few);
+    }
+}
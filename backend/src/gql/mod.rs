@@ -1,40 +1,98 @@
 pub mod queries;
 pub mod mutations;
+pub mod subscriptions;
+pub mod loaders;
 
 use tide::{http::mime, Request, Response, StatusCode, Body};
 
 use async_graphql::{
-    Schema, EmptySubscription,
+    Schema,
+    dataloader::DataLoader,
     http::{playground_source, GraphQLPlaygroundConfig, receive_json},
 };
 
+use mongodb::Database;
+
 use crate::State;
+use crate::users;
 
-use crate::util::constant::CFG;
+use crate::util::{config::Config, datetime::DatePrefs};
 use crate::dbs::mongo;
 
 use crate::gql::queries::QueryRoot;
 use crate::gql::mutations::MutationRoot;
+use crate::gql::subscriptions::SubscriptionRoot;
+use crate::gql::loaders::{
+    UserLoader, CategoryLoader, ProjectFilesLoader, ProjectTopicsLoader,
+};
 
-pub async fn build_schema() -> Schema<QueryRoot, MutationRoot, EmptySubscription>
-{
+// The schema, and the database for the handlers out of it, such as the
+// timezone lookup of the signed-in user.
+pub async fn build_schema(
+    cfg: &Config,
+) -> (Schema<QueryRoot, MutationRoot, SubscriptionRoot>, Database) {
     // get mongodb datasource. It can be added to:
     // 1. As global data for async-graphql.
     // 2. As application scope state of Tide
     // 3. Use lazy-static.rs.
-    let mongo_ds = mongo::DataSource::init().await;
+    let mongo_ds = mongo::DataSource::init(cfg).await;
+    mongo_ds.init_indexes(cfg).await;
 
-    // The root object for the query and Mutation, and use EmptySubscription.
-    // Add global mongodb datasource  in the schema object.
-    // let mut schema = Schema::new(QueryRoot, MutationRoot, EmptySubscription)
-    Schema::build(QueryRoot, MutationRoot, EmptySubscription)
+    // The root object for the query, mutation and subscription.
+    // Add global mongodb datasource  in the schema object, and the config
+    // for the services.
+    // let mut schema = Schema::new(QueryRoot, MutationRoot, SubscriptionRoot)
+    // Loaders batch the nested fields of lists, such as users of projects.
+    let db = mongo_ds.db.clone();
+    let schema = Schema::build(QueryRoot, MutationRoot, SubscriptionRoot)
         .data(mongo_ds)
-        .finish()
+        .data(cfg.clone())
+        .data(DataLoader::new(UserLoader(db.clone()), async_std::task::spawn))
+        .data(DataLoader::new(
+            CategoryLoader(db.clone()),
+            async_std::task::spawn,
+        ))
+        .data(DataLoader::new(
+            ProjectFilesLoader(db.clone()),
+            async_std::task::spawn,
+        ))
+        .data(DataLoader::new(
+            ProjectTopicsLoader(db.clone()),
+            async_std::task::spawn,
+        ))
+        .finish();
+
+    (schema, db)
 }
 
 pub async fn graphql(req: Request<State>) -> tide::Result {
     let schema = req.state().schema.clone();
-    let gql_resp = schema.execute(receive_json(req).await?).await;
+
+    // Timezone & locale of the formatted dates, such as "Europe/Paris" and
+    // the first language of "en-US,en;q=0.9". Without the timezone, it is
+    // the one in the profile of the signed-in user, by the bearer token.
+    let mut tz = req.header("Time-Zone").map(|tz| tz.as_str().to_string());
+    let token = req.header("Authorization").and_then(|authorization| {
+        authorization.as_str().strip_prefix("Bearer ").map(String::from)
+    });
+    if let Some(token) = token.filter(|_| tz.is_none()) {
+        let state = req.state();
+        if let Ok(user) =
+            users::services::user_by_token(&state.db, &state.config, token)
+                .await
+        {
+            tz = user.timezone;
+        }
+    }
+    let date_prefs = DatePrefs {
+        tz,
+        locale: req.header("Accept-Language").and_then(|languages| {
+            languages.as_str().split([',', ';']).next().map(String::from)
+        }),
+    };
+
+    let gql_req = receive_json(req).await?.data(date_prefs);
+    let gql_resp = schema.execute(gql_req).await;
 
     let mut resp = Response::new(StatusCode::Ok);
     resp.set_body(Body::from_json(&gql_resp)?);
@@ -42,10 +100,10 @@ pub async fn graphql(req: Request<State>) -> tide::Result {
     Ok(resp.into())
 }
 
-pub async fn graphiql(_: Request<State>) -> tide::Result {
+pub async fn graphiql(req: Request<State>) -> tide::Result {
     let mut resp = Response::new(StatusCode::Ok);
     resp.set_body(playground_source(GraphQLPlaygroundConfig::new(
-        CFG.get("GQL_VER").unwrap(),
+        &req.state().config.gql_ver,
     )));
     resp.set_content_type(mime::HTML);
 
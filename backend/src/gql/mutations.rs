@@ -2,16 +2,21 @@ use async_graphql::Context;
 use mongodb::bson::oid::ObjectId;
 
 use crate::dbs::mongo::DataSource;
-use crate::util::constant::GqlResult;
+use crate::util::{config::Config, constant::GqlResult};
 
 use crate::users::{
     self,
-    models::{User, UserNew, Wish, WishNew},
+    models::{User, UserNew, UserStatusKind, Wish, WishNew},
 };
 use crate::projects::{
     self,
-    models::{Project, ProjectNew, File, FileNew, ProjectFile, ProjectFileNew},
+    models::{
+        Project, ProjectNew, ProjectPublish, ProjectStatusKind, File, FileNew,
+        ProjectFile, ProjectFileNew,
+    },
 };
+use crate::views;
+use crate::remixes;
 use crate::categories::{
     self,
     models::{Category, CategoryNew, CategoryUser, CategoryUserNew},
@@ -22,6 +27,15 @@ use crate::topics::{
         Topic, TopicNew, TopicUser, TopicUserNew, TopicProject, TopicProjectNew,
     },
 };
+use crate::comments::{
+    self,
+    models::{Comment, CommentNew},
+};
+use crate::notifications::{self, models::Notification};
+use crate::studios::{
+    self,
+    models::{Studio, StudioNew},
+};
 
 pub struct MutationRoot;
 
@@ -46,7 +60,9 @@ impl MutationRoot {
         token: String,
     ) -> GqlResult<User> {
         let db = &ctx.data_unchecked::<DataSource>().db;
-        users::services::user_change_password(db, pwd_cur, pwd_new, token).await
+        let cfg = ctx.data_unchecked::<Config>();
+        users::services::user_change_password(db, cfg, pwd_cur, pwd_new, token)
+            .await
     }
 
     // update user profile
@@ -57,45 +73,293 @@ impl MutationRoot {
         token: String,
     ) -> GqlResult<User> {
         let db = &ctx.data_unchecked::<DataSource>().db;
-        users::services::user_update_profile(db, user_new, token).await
+        let cfg = ctx.data_unchecked::<Config>();
+        users::services::user_update_profile(db, cfg, user_new, token).await
+    }
+
+    // set status of the user by moderators
+    async fn user_set_status(
+        &self,
+        ctx: &Context<'_>,
+        user_id: ObjectId,
+        status: UserStatusKind,
+        token: String,
+    ) -> GqlResult<User> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
+        users::services::user_set_status(db, cfg, user_id, status.into(), token)
+            .await
+    }
+
+    // activate the account by the code of the activation email
+    async fn user_activate(
+        &self,
+        ctx: &Context<'_>,
+        user_id: ObjectId,
+        code: String,
+    ) -> GqlResult<User> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
+        users::services::user_activate(db, cfg, user_id, code).await
     }
 
     // modify user's one field by its id
+    #[graphql(deprecation = "Use `userActivate` or `userSetStatus`.")]
     async fn user_update_one_field_by_id(
         &self,
         ctx: &Context<'_>,
         user_id: ObjectId,
         field_name: String,
         field_val: String,
+        code: String,
     ) -> GqlResult<User> {
         let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
         users::services::user_update_one_field_by_id(
-            db, user_id, field_name, field_val,
+            db, cfg, user_id, field_name, field_val, code,
         )
         .await
     }
 
+    // follow user by the signed-in user
+    async fn user_follow(
+        &self,
+        ctx: &Context<'_>,
+        user_id: ObjectId,
+        token: String,
+    ) -> GqlResult<User> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
+        users::services::user_follow(db, cfg, user_id, token).await
+    }
+
+    // unfollow user by the signed-in user
+    async fn user_unfollow(
+        &self,
+        ctx: &Context<'_>,
+        user_id: ObjectId,
+        token: String,
+    ) -> GqlResult<User> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
+        users::services::user_unfollow(db, cfg, user_id, token).await
+    }
+
     // Add new project
     async fn project_new(
         &self,
         ctx: &Context<'_>,
         project_new: ProjectNew,
+        idempotency_key: Option<String>,
     ) -> GqlResult<Project> {
         let db = &ctx.data_unchecked::<DataSource>().db;
-        projects::services::project_new(db, project_new).await
+        let cfg = ctx.data_unchecked::<Config>();
+        projects::services::project_new(db, cfg, project_new, idempotency_key)
+            .await
+    }
+
+    // Publish new project with its topics & files of the signed-in user, in
+    // one transaction
+    async fn project_publish(
+        &self,
+        ctx: &Context<'_>,
+        project_publish: ProjectPublish,
+        idempotency_key: Option<String>,
+        token: String,
+    ) -> GqlResult<Project> {
+        let ds = ctx.data_unchecked::<DataSource>();
+        let cfg = ctx.data_unchecked::<Config>();
+        projects::services::project_publish(
+            &ds.db,
+            cfg,
+            ds.client().await,
+            project_publish,
+            idempotency_key,
+            token,
+        )
+        .await
+    }
+
+    // set status of the project by moderators
+    async fn project_set_status(
+        &self,
+        ctx: &Context<'_>,
+        project_id: ObjectId,
+        status: ProjectStatusKind,
+        token: String,
+    ) -> GqlResult<Project> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
+        projects::services::project_set_status(
+            db,
+            cfg,
+            project_id,
+            status.into(),
+            token,
+        )
+        .await
     }
 
     // modify project's one field by its id
+    #[graphql(deprecation = "Use `projectSetStatus`.")]
     async fn project_update_one_field_by_id(
         &self,
         ctx: &Context<'_>,
         project_id: ObjectId,
         field_name: String,
         field_val: String,
+        token: String,
     ) -> GqlResult<Project> {
         let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
         projects::services::project_update_one_field_by_id(
-            db, project_id, field_name, field_val,
+            db, cfg, project_id, field_name, field_val, token,
+        )
+        .await
+    }
+
+    // record a view of the project, deduplicated per visitor
+    async fn project_view_record(
+        &self,
+        ctx: &Context<'_>,
+        project_id: ObjectId,
+        fingerprint: String,
+        signature: String,
+        token: String,
+    ) -> GqlResult<Project> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
+        views::services::project_view_record(
+            db,
+            cfg,
+            project_id,
+            fingerprint,
+            signature,
+            token,
+        )
+        .await
+    }
+
+    // record a download of the source file, and get the file
+    async fn project_download_record(
+        &self,
+        ctx: &Context<'_>,
+        project_id: ObjectId,
+        token: String,
+    ) -> GqlResult<File> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
+        views::services::project_download_record(db, cfg, project_id, token)
+            .await
+    }
+
+    // rename project by its owner, the old slug keeps resolving
+    async fn project_rename(
+        &self,
+        ctx: &Context<'_>,
+        project_id: ObjectId,
+        subject: String,
+        token: String,
+    ) -> GqlResult<Project> {
+        let ds = ctx.data_unchecked::<DataSource>();
+        let cfg = ctx.data_unchecked::<Config>();
+        projects::services::project_rename(
+            &ds.db,
+            cfg,
+            ds.client().await,
+            project_id,
+            subject,
+            token,
+        )
+        .await
+    }
+
+    // move project into trash by its owner
+    async fn project_delete(
+        &self,
+        ctx: &Context<'_>,
+        project_id: ObjectId,
+        token: String,
+    ) -> GqlResult<Project> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
+        projects::services::project_delete(db, cfg, project_id, token).await
+    }
+
+    // restore project from trash by its owner
+    async fn project_restore(
+        &self,
+        ctx: &Context<'_>,
+        project_id: ObjectId,
+        token: String,
+    ) -> GqlResult<Project> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
+        projects::services::project_restore(db, cfg, project_id, token).await
+    }
+
+    // star project by the signed-in user
+    async fn project_star(
+        &self,
+        ctx: &Context<'_>,
+        project_id: ObjectId,
+        token: String,
+    ) -> GqlResult<Project> {
+        let ds = ctx.data_unchecked::<DataSource>();
+        let cfg = ctx.data_unchecked::<Config>();
+        projects::services::project_star(
+            &ds.db,
+            cfg,
+            ds.client().await,
+            project_id,
+            token,
+        )
+        .await
+    }
+
+    // unstar project by the signed-in user
+    async fn project_unstar(
+        &self,
+        ctx: &Context<'_>,
+        project_id: ObjectId,
+        token: String,
+    ) -> GqlResult<Project> {
+        let ds = ctx.data_unchecked::<DataSource>();
+        let cfg = ctx.data_unchecked::<Config>();
+        projects::services::project_unstar(
+            &ds.db,
+            cfg,
+            ds.client().await,
+            project_id,
+            token,
+        )
+        .await
+    }
+
+    // remix published project by the signed-in user
+    async fn project_remix(
+        &self,
+        ctx: &Context<'_>,
+        project_id: ObjectId,
+        token: String,
+    ) -> GqlResult<Project> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
+        remixes::services::project_remix(db, cfg, project_id, token).await
+    }
+
+    // enable or disable comments of project
+    async fn project_comments_enable(
+        &self,
+        ctx: &Context<'_>,
+        project_id: ObjectId,
+        enabled: bool,
+        token: String,
+    ) -> GqlResult<Project> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
+        projects::services::project_comments_enable(
+            db, cfg, project_id, enabled, token,
         )
         .await
     }
@@ -105,9 +369,10 @@ impl MutationRoot {
         &self,
         ctx: &Context<'_>,
         file_new: FileNew,
+        idempotency_key: Option<String>,
     ) -> GqlResult<File> {
         let db = &ctx.data_unchecked::<DataSource>().db;
-        projects::services::file_new(db, file_new).await
+        projects::services::file_new(db, file_new, idempotency_key).await
     }
 
     // Add new project_file
@@ -130,6 +395,68 @@ impl MutationRoot {
         categories::services::category_new(db, category_new).await
     }
 
+    // update names of category by moderators
+    async fn category_update(
+        &self,
+        ctx: &Context<'_>,
+        category_id: ObjectId,
+        category_new: CategoryNew,
+        token: String,
+    ) -> GqlResult<Category> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
+        categories::services::category_update(
+            db,
+            cfg,
+            category_id,
+            category_new,
+            token,
+        )
+        .await
+    }
+
+    // move category under another parent or reorder it, by moderators
+    async fn category_move(
+        &self,
+        ctx: &Context<'_>,
+        category_id: ObjectId,
+        parent_id: Option<ObjectId>,
+        position: u32,
+        token: String,
+    ) -> GqlResult<Category> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
+        categories::services::category_move(
+            db,
+            cfg,
+            category_id,
+            parent_id,
+            position,
+            token,
+        )
+        .await
+    }
+
+    // delete category and reassign its projects, by moderators
+    async fn category_delete(
+        &self,
+        ctx: &Context<'_>,
+        category_id: ObjectId,
+        reassign_id: Option<ObjectId>,
+        token: String,
+    ) -> GqlResult<Category> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
+        categories::services::category_delete(
+            db,
+            cfg,
+            category_id,
+            reassign_id,
+            token,
+        )
+        .await
+    }
+
     // Add new category
     async fn category_user_new(
         &self,
@@ -180,13 +507,272 @@ impl MutationRoot {
         topics::services::topic_project_new(db, topic_project_new).await
     }
 
+    // Rename topic by moderators
+    async fn topic_rename(
+        &self,
+        ctx: &Context<'_>,
+        topic_id: ObjectId,
+        name: String,
+        token: String,
+    ) -> GqlResult<Topic> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
+        topics::services::topic_rename(db, cfg, topic_id, name, token).await
+    }
+
+    // Merge topic into the target one by moderators
+    async fn topic_merge(
+        &self,
+        ctx: &Context<'_>,
+        topic_id: ObjectId,
+        target_id: ObjectId,
+        token: String,
+    ) -> GqlResult<Topic> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
+        topics::services::topic_merge(db, cfg, topic_id, target_id, token).await
+    }
+
+    // Delete topic and its links by moderators
+    async fn topic_delete(
+        &self,
+        ctx: &Context<'_>,
+        topic_id: ObjectId,
+        token: String,
+    ) -> GqlResult<Topic> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
+        topics::services::topic_delete(db, cfg, topic_id, token).await
+    }
+
     // Add new wish
     async fn wish_new(
         &self,
         ctx: &Context<'_>,
         wish_new: WishNew,
+        idempotency_key: Option<String>,
     ) -> GqlResult<Wish> {
         let db = &ctx.data_unchecked::<DataSource>().db;
-        users::services::wish_new(db, wish_new).await
+        users::services::wish_new(db, wish_new, idempotency_key).await
+    }
+    // Add new comment, or reply to a comment
+    async fn comment_new(
+        &self,
+        ctx: &Context<'_>,
+        comment_new: CommentNew,
+        token: String,
+        idempotency_key: Option<String>,
+    ) -> GqlResult<Comment> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
+        comments::services::comment_new(
+            db,
+            cfg,
+            comment_new,
+            token,
+            idempotency_key,
+        )
+        .await
+    }
+
+    // update comment by its author
+    async fn comment_update(
+        &self,
+        ctx: &Context<'_>,
+        comment_id: ObjectId,
+        content: String,
+        token: String,
+    ) -> GqlResult<Comment> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
+        comments::services::comment_update(db, cfg, comment_id, content, token)
+            .await
+    }
+
+    // delete comment by its author
+    async fn comment_delete(
+        &self,
+        ctx: &Context<'_>,
+        comment_id: ObjectId,
+        token: String,
+    ) -> GqlResult<Comment> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
+        comments::services::comment_delete(db, cfg, comment_id, token).await
+    }
+
+    // hide or show comment by moderators
+    async fn comment_hide(
+        &self,
+        ctx: &Context<'_>,
+        comment_id: ObjectId,
+        hidden: bool,
+        token: String,
+    ) -> GqlResult<Comment> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
+        comments::services::comment_hide(db, cfg, comment_id, hidden, token)
+            .await
+    }
+
+    // mark one notification as read
+    async fn notification_mark_read(
+        &self,
+        ctx: &Context<'_>,
+        notification_id: ObjectId,
+        token: String,
+    ) -> GqlResult<Notification> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
+        notifications::services::notification_mark_read(
+            db,
+            cfg,
+            notification_id,
+            token,
+        )
+        .await
+    }
+
+    // ticket of the notifications subscription, which expires in a minute
+    async fn subscription_ticket(
+        &self,
+        ctx: &Context<'_>,
+        token: String,
+    ) -> GqlResult<String> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
+        users::services::user_subscription_ticket(db, cfg, token).await
+    }
+
+    // mark all notifications of the signed-in user as read
+    async fn notifications_mark_all_read(
+        &self,
+        ctx: &Context<'_>,
+        token: String,
+    ) -> GqlResult<u64> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
+        notifications::services::notifications_mark_all_read(db, cfg, token)
+            .await
+    }
+
+    // create new studio by the signed-in user
+    async fn studio_new(
+        &self,
+        ctx: &Context<'_>,
+        studio_new: StudioNew,
+        token: String,
+    ) -> GqlResult<Studio> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
+        studios::services::studio_new(db, cfg, studio_new, token).await
+    }
+
+    // update studio by its owner
+    async fn studio_update(
+        &self,
+        ctx: &Context<'_>,
+        studio_id: ObjectId,
+        studio_new: StudioNew,
+        token: String,
+    ) -> GqlResult<Studio> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
+        studios::services::studio_update(db, cfg, studio_id, studio_new, token)
+            .await
+    }
+
+    // invite user to curate studio, by its owner
+    async fn studio_curator_invite(
+        &self,
+        ctx: &Context<'_>,
+        studio_id: ObjectId,
+        username: String,
+        token: String,
+    ) -> GqlResult<Studio> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
+        studios::services::studio_curator_invite(
+            db, cfg, studio_id, username, token,
+        )
+        .await
+    }
+
+    // accept curator invitation by the signed-in user
+    async fn studio_curator_accept(
+        &self,
+        ctx: &Context<'_>,
+        studio_id: ObjectId,
+        token: String,
+    ) -> GqlResult<Studio> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
+        studios::services::studio_curator_accept(db, cfg, studio_id, token)
+            .await
+    }
+
+    // remove curator or decline invitation
+    async fn studio_curator_remove(
+        &self,
+        ctx: &Context<'_>,
+        studio_id: ObjectId,
+        user_id: ObjectId,
+        token: String,
+    ) -> GqlResult<Studio> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
+        studios::services::studio_curator_remove(
+            db, cfg, studio_id, user_id, token,
+        )
+        .await
+    }
+
+    // add project into studio, by its owner or curators
+    async fn studio_project_add(
+        &self,
+        ctx: &Context<'_>,
+        studio_id: ObjectId,
+        project_id: ObjectId,
+        token: String,
+    ) -> GqlResult<Studio> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
+        studios::services::studio_project_add(
+            db, cfg, studio_id, project_id, token,
+        )
+        .await
+    }
+
+    // remove project from studio, by its owner or curators
+    async fn studio_project_remove(
+        &self,
+        ctx: &Context<'_>,
+        studio_id: ObjectId,
+        project_id: ObjectId,
+        token: String,
+    ) -> GqlResult<Studio> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
+        studios::services::studio_project_remove(
+            db, cfg, studio_id, project_id, token,
+        )
+        .await
+    }
+
+    // move project to the position of studio, by its owner or curators
+    async fn studio_project_move(
+        &self,
+        ctx: &Context<'_>,
+        studio_id: ObjectId,
+        project_id: ObjectId,
+        position: u32,
+        token: String,
+    ) -> GqlResult<Studio> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
+        studios::services::studio_project_move(
+            db, cfg, studio_id, project_id, position, token,
+        )
+        .await
     }
 }
@@ -3,20 +3,32 @@ use mongodb::bson::oid::ObjectId;
 
 use crate::dbs::mongo::DataSource;
 use crate::util::{
+    config::Config,
     constant::GqlResult,
-    pagination::{UsersResult, ProjectsResult},
+    pagination::{
+        UsersResult, ProjectsResult, CommentsResult, NotificationsResult,
+        StudiosResult,
+    },
 };
 
 use crate::users::{
     self,
-    models::{User, SignInfo, Wish},
+    models::{User, UserStatusKind, SignInfo, Wish, FeedItem},
 };
 use crate::projects::{
     self,
-    models::{Project, File},
+    models::{Project, ProjectStatusKind, File, FileKind},
+};
+use crate::remixes::{self, models::RemixNode};
+use crate::trending::{self, models::TrendingWindow};
+use crate::categories::{
+    self,
+    models::{Category, CategoryNode},
 };
-use crate::categories::{self, models::Category};
 use crate::topics::{self, models::Topic};
+use crate::comments::{self, models::Comment};
+use crate::notifications;
+use crate::studios::{self, models::Studio};
 
 pub struct QueryRoot;
 
@@ -30,7 +42,8 @@ impl QueryRoot {
         password: String,
     ) -> GqlResult<SignInfo> {
         let db = &ctx.data_unchecked::<DataSource>().db;
-        users::services::user_sign_in(db, signature, password).await
+        let cfg = ctx.data_unchecked::<Config>();
+        users::services::user_sign_in(db, cfg, signature, password).await
     }
 
     // get user info by id
@@ -70,10 +83,31 @@ impl QueryRoot {
         from_page: u32,
         first_oid: String,
         last_oid: String,
-        status: i8,
+        status: UserStatusKind,
     ) -> GqlResult<UsersResult> {
         let db = &ctx.data_unchecked::<DataSource>().db;
-        users::services::users(db, from_page, first_oid, last_oid, status).await
+        let cfg = ctx.data_unchecked::<Config>();
+        users::services::users(
+            db,
+            cfg,
+            from_page,
+            first_oid,
+            last_oid,
+            status.into(),
+        )
+        .await
+    }
+
+    // Get activity feed of the users followed by the signed-in user
+    async fn feed(
+        &self,
+        ctx: &Context<'_>,
+        token: String,
+        from_page: u32,
+    ) -> GqlResult<Vec<FeedItem>> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
+        users::services::feed_by_token(db, cfg, token, from_page).await
     }
 
     // Get project by its id
@@ -86,6 +120,26 @@ impl QueryRoot {
         projects::services::project_by_id(db, project_id).await
     }
 
+    // Get project by its slug, or by an old slug before renaming
+    async fn project_by_slug(
+        &self,
+        ctx: &Context<'_>,
+        slug: String,
+    ) -> GqlResult<Project> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        projects::services::project_by_slug(db, slug).await
+    }
+
+    // get the remix tree which the project belongs to
+    async fn project_remix_tree(
+        &self,
+        ctx: &Context<'_>,
+        project_id: ObjectId,
+    ) -> GqlResult<Vec<RemixNode>> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        remixes::services::project_remix_tree(db, project_id).await
+    }
+
     // get random project
     async fn project_random_id(
         &self,
@@ -102,11 +156,36 @@ impl QueryRoot {
         from_page: u32,
         first_oid: String,
         last_oid: String,
-        status: i8,
+        status: Option<ProjectStatusKind>,
     ) -> GqlResult<ProjectsResult> {
         let db = &ctx.data_unchecked::<DataSource>().db;
-        projects::services::projects(db, from_page, first_oid, last_oid, status)
-            .await
+        let cfg = ctx.data_unchecked::<Config>();
+        projects::services::projects(
+            db,
+            cfg,
+            from_page,
+            first_oid,
+            last_oid,
+            status.map(Into::into),
+        )
+        .await
+    }
+
+    // Get all trashed projects of the signed-in user
+    async fn projects_trash(
+        &self,
+        ctx: &Context<'_>,
+        token: String,
+        from_page: u32,
+        first_oid: String,
+        last_oid: String,
+    ) -> GqlResult<ProjectsResult> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
+        projects::services::projects_trash(
+            db, cfg, token, from_page, first_oid, last_oid,
+        )
+        .await
     }
 
     async fn projects_in_position(
@@ -121,6 +200,18 @@ impl QueryRoot {
             .await
     }
 
+    // Get trending projects of the window, ranked by views, stars, comments
+    // and remixes
+    async fn projects_trending(
+        &self,
+        ctx: &Context<'_>,
+        window: TrendingWindow,
+        #[graphql(default = 20)] limit: i64,
+    ) -> GqlResult<Vec<Project>> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        trending::services::projects_trending(db, window, limit).await
+    }
+
     // Get all projects of one user by user_id
     async fn projects_by_user_id(
         &self,
@@ -129,11 +220,18 @@ impl QueryRoot {
         from_page: u32,
         first_oid: String,
         last_oid: String,
-        status: i8,
+        status: Option<ProjectStatusKind>,
     ) -> GqlResult<ProjectsResult> {
         let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
         projects::services::projects_by_user_id(
-            db, user_id, from_page, first_oid, last_oid, status,
+            db,
+            cfg,
+            user_id,
+            from_page,
+            first_oid,
+            last_oid,
+            status.map(Into::into),
         )
         .await
     }
@@ -146,11 +244,18 @@ impl QueryRoot {
         from_page: u32,
         first_oid: String,
         last_oid: String,
-        status: i8,
+        status: Option<ProjectStatusKind>,
     ) -> GqlResult<ProjectsResult> {
         let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
         projects::services::projects_by_username(
-            db, username, from_page, first_oid, last_oid, status,
+            db,
+            cfg,
+            username,
+            from_page,
+            first_oid,
+            last_oid,
+            status.map(Into::into),
         )
         .await
     }
@@ -163,16 +268,18 @@ impl QueryRoot {
         from_page: u32,
         first_oid: String,
         last_oid: String,
-        status: i8,
+        status: Option<ProjectStatusKind>,
     ) -> GqlResult<ProjectsResult> {
         let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
         projects::services::projects_by_category_id(
             db,
+            cfg,
             category_id,
             from_page,
             first_oid,
             last_oid,
-            status,
+            status.map(Into::into),
         )
         .await
     }
@@ -185,16 +292,18 @@ impl QueryRoot {
         from_page: u32,
         first_oid: String,
         last_oid: String,
-        status: i8,
+        status: Option<ProjectStatusKind>,
     ) -> GqlResult<ProjectsResult> {
         let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
         projects::services::projects_by_category_slug(
             db,
+            cfg,
             category_slug,
             from_page,
             first_oid,
             last_oid,
-            status,
+            status.map(Into::into),
         )
         .await
     }
@@ -207,11 +316,18 @@ impl QueryRoot {
         from_page: u32,
         first_oid: String,
         last_oid: String,
-        status: i8,
+        status: Option<ProjectStatusKind>,
     ) -> GqlResult<ProjectsResult> {
         let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
         projects::services::projects_by_topic_id(
-            db, topic_id, from_page, first_oid, last_oid, status,
+            db,
+            cfg,
+            topic_id,
+            from_page,
+            first_oid,
+            last_oid,
+            status.map(Into::into),
         )
         .await
     }
@@ -224,11 +340,18 @@ impl QueryRoot {
         from_page: u32,
         first_oid: String,
         last_oid: String,
-        status: i8,
+        status: Option<ProjectStatusKind>,
     ) -> GqlResult<ProjectsResult> {
         let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
         projects::services::projects_by_topic_slug(
-            db, topic_slug, from_page, first_oid, last_oid, status,
+            db,
+            cfg,
+            topic_slug,
+            from_page,
+            first_oid,
+            last_oid,
+            status.map(Into::into),
         )
         .await
     }
@@ -257,7 +380,7 @@ impl QueryRoot {
     async fn file_by_kind_project_id(
         &self,
         ctx: &Context<'_>,
-        file_kind: i8,
+        file_kind: FileKind,
         project_id: ObjectId,
     ) -> GqlResult<File> {
         let db = &ctx.data_unchecked::<DataSource>().db;
@@ -265,12 +388,58 @@ impl QueryRoot {
             .await
     }
 
+    // get comment by its id
+    async fn comment_by_id(
+        &self,
+        ctx: &Context<'_>,
+        id: ObjectId,
+    ) -> GqlResult<Comment> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        comments::services::comment_by_id(db, id).await
+    }
+
+    // get root comments of one project by project_id
+    async fn comments_by_project_id(
+        &self,
+        ctx: &Context<'_>,
+        project_id: ObjectId,
+        from_page: u32,
+        first_oid: String,
+        last_oid: String,
+    ) -> GqlResult<CommentsResult> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
+        comments::services::comments_by_project_id(
+            db, cfg, project_id, from_page, first_oid, last_oid,
+        )
+        .await
+    }
+
     // Get all categories
     async fn categories(&self, ctx: &Context<'_>) -> GqlResult<Vec<Category>> {
         let db = &ctx.data_unchecked::<DataSource>().db;
         categories::services::categories(db).await
     }
 
+    // Get the whole categories tree, listed depth-first
+    async fn categories_tree(
+        &self,
+        ctx: &Context<'_>,
+    ) -> GqlResult<Vec<CategoryNode>> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        categories::services::categories_tree(db).await
+    }
+
+    // Get ancestors of category from the root, ended with itself
+    async fn category_breadcrumb(
+        &self,
+        ctx: &Context<'_>,
+        category_id: ObjectId,
+    ) -> GqlResult<Vec<Category>> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        categories::services::category_breadcrumb(db, category_id).await
+    }
+
     // Get all categories by user_id
     async fn categories_by_user_id(
         &self,
@@ -337,6 +506,28 @@ impl QueryRoot {
         topics::services::topics(db).await
     }
 
+    // suggest topics by the prefix of name or slug
+    async fn topics_suggest(
+        &self,
+        ctx: &Context<'_>,
+        prefix: String,
+        #[graphql(default = 10)] limit: u32,
+    ) -> GqlResult<Vec<Topic>> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        topics::services::topics_suggest(db, prefix, limit).await
+    }
+
+    // suggest existing topics found in the subject or content
+    async fn topics_for_content(
+        &self,
+        ctx: &Context<'_>,
+        subject: String,
+        content: String,
+    ) -> GqlResult<Vec<Topic>> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        topics::services::topics_for_content(db, subject, content).await
+    }
+
     // get topics by project_id
     async fn topics_by_project_id(
         &self,
@@ -406,4 +597,75 @@ impl QueryRoot {
         let db = &ctx.data_unchecked::<DataSource>().db;
         users::services::wish_random(db, username).await
     }
+
+    // get notifications of the signed-in user
+    async fn notifications(
+        &self,
+        ctx: &Context<'_>,
+        token: String,
+        unread_only: bool,
+        from_page: u32,
+        first_oid: String,
+        last_oid: String,
+    ) -> GqlResult<NotificationsResult> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
+        notifications::services::notifications_by_token(
+            db,
+            cfg,
+            token,
+            unread_only,
+            from_page,
+            first_oid,
+            last_oid,
+        )
+        .await
+    }
+
+    // count unread notifications of the signed-in user
+    async fn notifications_unread_count(
+        &self,
+        ctx: &Context<'_>,
+        token: String,
+    ) -> GqlResult<u64> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
+        notifications::services::notifications_unread_count(db, cfg, token)
+            .await
+    }
+
+    // Get all studios
+    async fn studios(
+        &self,
+        ctx: &Context<'_>,
+        from_page: u32,
+        first_oid: String,
+        last_oid: String,
+    ) -> GqlResult<StudiosResult> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
+        studios::services::studios(db, cfg, from_page, first_oid, last_oid)
+            .await
+    }
+
+    // get studio info by slug
+    async fn studio_by_slug(
+        &self,
+        ctx: &Context<'_>,
+        slug: String,
+    ) -> GqlResult<Studio> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        studios::services::studio_by_slug(db, slug).await
+    }
+
+    // get studios which the signed-in user is invited to curate
+    async fn studio_invitations(
+        &self,
+        ctx: &Context<'_>,
+        token: String,
+    ) -> GqlResult<Vec<Studio>> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
+        studios::services::studio_invitations_by_token(db, cfg, token).await
+    }
 }
@@ -0,0 +1,50 @@
+use async_graphql::{Context, ErrorExtensions};
+use futures::{Stream, StreamExt, future};
+use mongodb::bson::oid::ObjectId;
+
+use crate::util::{
+    broker::SimpleBroker, config::Config, constant::GqlResult,
+    cred::ticket_data, error::AppError,
+};
+
+use crate::projects::models::Project;
+use crate::comments::models::Comment;
+use crate::notifications::models::Notification;
+
+pub struct SubscriptionRoot;
+
+#[async_graphql::Subscription]
+impl SubscriptionRoot {
+    // new notifications of the user, by the ticket of subscriptionTicket
+    async fn notifications(
+        &self,
+        ctx: &Context<'_>,
+        ticket: String,
+    ) -> GqlResult<impl Stream<Item = Notification>> {
+        let cfg = ctx.data_unchecked::<Config>();
+        let user_id = ticket_data(&ticket, &cfg.site_key)
+            .await
+            .ok()
+            .and_then(|data| ObjectId::parse_str(data.claims.sub).ok())
+            .ok_or_else(|| AppError::TokenInvalid.extend())?;
+
+        Ok(SimpleBroker::<Notification>::subscribe().filter(
+            move |notification| future::ready(notification.user_id == user_id),
+        ))
+    }
+
+    // new comments of one project
+    async fn comments(
+        &self,
+        project_id: ObjectId,
+    ) -> impl Stream<Item = Comment> {
+        SimpleBroker::<Comment>::subscribe().filter(move |comment| {
+            future::ready(comment.project_id == project_id)
+        })
+    }
+
+    // newly published projects
+    async fn projects_published(&self) -> impl Stream<Item = Project> {
+        SimpleBroker::<Project>::subscribe()
+    }
+}
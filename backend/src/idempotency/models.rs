@@ -0,0 +1,22 @@
+use serde::{Serialize, Deserialize};
+use mongodb::bson::{oid::ObjectId, DateTime};
+
+// a key sent by the client with a create mutation, retries with the same key
+// get the record created at first, instead of a duplicate
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct IdempotencyKey {
+    pub _id: ObjectId,
+    // collection of the target, "projects", "files", "wishes" or "comments"
+    pub kind: String,
+    // the caller, keys of different users never meet. Files have no owner
+    // when created, so their keys are shared.
+    #[serde(default)]
+    pub user_id: Option<ObjectId>,
+    pub key: String,
+    // digest of the first request, a retry must send the same one
+    #[serde(default)]
+    pub request_hash: String,
+    // None while the first request is still creating the target
+    pub target_id: Option<ObjectId>,
+    pub created_at: DateTime,
+}
@@ -0,0 +1,153 @@
+use mongodb::{
+    Database,
+    bson::{
+        oid::ObjectId, Document, doc, from_document, to_document, to_vec,
+        DateTime, Bson,
+    },
+    options::UpdateOptions,
+};
+use async_graphql::ErrorExtensions;
+use ring::digest;
+use base64::{Engine, engine::general_purpose::STANDARD};
+use serde::Serialize;
+
+use crate::util::{constant::GqlResult, error::AppError};
+use crate::dbs::mongo::is_duplicate_key;
+
+use super::models::IdempotencyKey;
+
+// the longest key accepted
+const KEY_LEN_MAX: usize = 128;
+// A reservation not settled in time was left by a request which crashed, and
+// a retry takes it over instead of waiting for the key to expire.
+const PENDING_SECS_MAX: i64 = 60;
+
+// Check the key of a create mutation of the kind, by the caller. Returns the
+// id of the target if a request with the key has created it, or None after
+// reserving the key for the caller, who must settle it when done. The key of
+// a different request is rejected. Requests without a key are never reserved.
+pub async fn idempotency_key_check<T: Serialize>(
+    db: &Database,
+    kind: &str,
+    user_id: Option<ObjectId>,
+    key: &Option<String>,
+    request: &T,
+) -> GqlResult<Option<ObjectId>> {
+    let key = match key {
+        Some(key) => key,
+        None => return Ok(None),
+    };
+    if key.trim().is_empty() || key.len() > KEY_LEN_MAX {
+        return Err(AppError::IdempotencyKeyInvalid.extend());
+    }
+    let request_hash = request_digest(request)?;
+
+    let coll = db.collection::<Document>("idempotency_keys");
+    let key_filter = doc! {"kind": kind, "user_id": user_id, "key": key};
+    let upsert_options = UpdateOptions::builder().upsert(true).build();
+    let reserve_res = coll
+        .update_one(
+            key_filter.clone(),
+            doc! {
+                "$setOnInsert": {
+                    "request_hash": &request_hash,
+                    "target_id": Bson::Null,
+                    "created_at": DateTime::now()
+                }
+            },
+            upsert_options,
+        )
+        .await;
+    match reserve_res {
+        Ok(update_res) if update_res.upserted_id.is_some() => return Ok(None),
+        // reserved before
+        Ok(_) => {}
+        // reserved by a concurrent request just now
+        Err(error) if is_duplicate_key(&error) => {}
+        Err(error) => return Err(error.into()),
+    }
+
+    let idempotency_key: IdempotencyKey =
+        match coll.find_one(key_filter, None).await? {
+            Some(document) => from_document(document)?,
+            None => return Err(AppError::RequestInProgress.extend()),
+        };
+    if idempotency_key.request_hash != request_hash {
+        return Err(AppError::IdempotencyKeyReused.extend());
+    }
+    if let Some(target_id) = idempotency_key.target_id {
+        return Ok(Some(target_id));
+    }
+
+    let now = DateTime::now();
+    let pending_millis =
+        now.timestamp_millis() - idempotency_key.created_at.timestamp_millis();
+    if pending_millis > PENDING_SECS_MAX * 1000 {
+        // only one of the concurrent retries takes it over
+        let takeover_res = coll
+            .update_one(
+                doc! {
+                    "_id": idempotency_key._id,
+                    "target_id": Bson::Null,
+                    "created_at": idempotency_key.created_at
+                },
+                doc! {"$set": {"created_at": now}},
+                None,
+            )
+            .await?;
+        if takeover_res.modified_count == 1 {
+            return Ok(None);
+        }
+    }
+
+    Err(AppError::RequestInProgress.extend())
+}
+
+// Settle the key reserved by idempotency_key_check: bind it to the created
+// target, or release it when the creation failed, so that a retry could
+// create again.
+pub async fn idempotency_key_settle(
+    db: &Database,
+    kind: &str,
+    user_id: Option<ObjectId>,
+    key: &Option<String>,
+    target_id: Option<ObjectId>,
+) -> GqlResult<()> {
+    let key = match key {
+        Some(key) => key,
+        None => return Ok(()),
+    };
+
+    let coll = db.collection::<Document>("idempotency_keys");
+    match target_id {
+        Some(target_id) => {
+            coll.update_one(
+                doc! {"kind": kind, "user_id": user_id, "key": key},
+                doc! {"$set": {"target_id": target_id}},
+                None,
+            )
+            .await?;
+        }
+        None => {
+            coll.delete_one(
+                doc! {
+                    "kind": kind,
+                    "user_id": user_id,
+                    "key": key,
+                    "target_id": Bson::Null
+                },
+                None,
+            )
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+// digest of the request, the same input always gives the same one
+fn request_digest<T: Serialize>(request: &T) -> GqlResult<String> {
+    let request_bytes = to_vec(&to_document(request)?)?;
+
+    Ok(STANDARD.encode(digest::digest(&digest::SHA256, &request_bytes)))
+}
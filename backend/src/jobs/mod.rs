@@ -0,0 +1,116 @@
+use std::time::Duration;
+use async_std::task;
+
+use mongodb::Database;
+
+use crate::util::config::Config;
+
+use crate::{categories, projects, studios, topics, trending};
+
+// interval of the trash purge job, in seconds
+const TRASH_PURGE_INTERVAL: u64 = 3600;
+// interval of the topic quotes repair job, in seconds
+const QUOTES_REPAIR_INTERVAL: u64 = 86400;
+// interval of the trending ranking job, in seconds
+const TRENDING_INTERVAL: u64 = 3600;
+
+// Run startup migrations, then spawn periodic background jobs. The database
+// shares the client of the schema, instead of opening another one.
+pub async fn init(cfg: &Config, db: Database) {
+    // one-off data migrations, done before the periodic jobs start
+    match categories::services::categories_names_migrate(&db).await {
+        Ok(migrated_count) if migrated_count > 0 => {
+            println!(
+                "\n\n\nMigrated names of {} categories\n\n\n",
+                migrated_count
+            )
+        }
+        Ok(_) => {}
+        Err(error) => {
+            println!("\n\n\nFailed to migrate categories: {:?}\n\n\n", error)
+        }
+    }
+
+    let slugs_migrated = vec![
+        ("topics", topics::services::topics_slugs_migrate(&db).await),
+        (
+            "categories",
+            categories::services::categories_slugs_migrate(&db).await,
+        ),
+        ("projects", projects::services::projects_slugs_migrate(&db).await),
+        ("studios", studios::services::studios_slugs_migrate(&db).await),
+    ];
+    for (kind, migrated) in slugs_migrated {
+        match migrated {
+            Ok(migrated_count) if migrated_count > 0 => {
+                println!(
+                    "\n\n\nCleaned slugs of {} {}\n\n\n",
+                    migrated_count, kind
+                )
+            }
+            Ok(_) => {}
+            Err(error) => {
+                println!(
+                    "\n\n\nFailed to clean slugs of {}: {:?}\n\n\n",
+                    kind, error
+                )
+            }
+        }
+    }
+
+    let quotes_db = db.clone();
+    task::spawn(async move {
+        loop {
+            match topics::services::topics_quotes_repair(&quotes_db).await {
+                Ok(repaired_count) if repaired_count > 0 => {
+                    println!(
+                        "\n\n\nRepaired quotes of {} topics\n\n\n",
+                        repaired_count
+                    )
+                }
+                Ok(_) => {}
+                Err(error) => {
+                    println!("\n\n\nFailed to repair quotes: {:?}\n\n\n", error)
+                }
+            }
+
+            task::sleep(Duration::from_secs(QUOTES_REPAIR_INTERVAL)).await;
+        }
+    });
+
+    let trending_db = db.clone();
+    task::spawn(async move {
+        loop {
+            if let Err(error) =
+                trending::services::projects_trending_compute(&trending_db)
+                    .await
+            {
+                println!("\n\n\nFailed to rank trending: {:?}\n\n\n", error)
+            }
+
+            task::sleep(Duration::from_secs(TRENDING_INTERVAL)).await;
+        }
+    });
+
+    let purge_cfg = cfg.clone();
+    task::spawn(async move {
+        loop {
+            match projects::services::projects_trash_purge(&db, &purge_cfg)
+                .await
+            {
+                Ok(purged_count) if purged_count > 0 => {
+                    println!(
+                        "\n\n\nPurged {} trashed projects\n\n\n",
+                        purged_count
+                    )
+                }
+                Ok(_) => {}
+                Err(error) => {
+                    println!("\n\n\nFailed to purge trash: {:?}\n\n\n", error)
+                }
+            }
+
+            task::sleep(Duration::from_secs(TRASH_PURGE_INTERVAL)).await;
+        }
+    });
+}
@@ -1,28 +1,54 @@
 mod util;
 mod dbs;
 mod gql;
+mod jobs;
 
 mod users;
 mod projects;
 mod categories;
 mod topics;
+mod comments;
+mod notifications;
+mod studios;
+mod slugs;
+mod idempotency;
+mod views;
+mod trending;
+mod remixes;
 
 use tide::http::headers::HeaderValue;
 use tide::security::{CorsMiddleware, Origin};
 
-use crate::util::constant::CFG;
+use async_graphql_tide::GraphQLSubscription;
+
+use crate::util::config::Config;
 use crate::gql::{build_schema, graphql, graphiql};
 
 #[async_std::main]
 async fn main() -> Result<(), std::io::Error> {
-    let schema = build_schema().await;
-    let app_state = State { schema };
+    // configuration from env & the optional TOML file, all problems of it
+    // are reported together before exiting
+    let cfg = match Config::load() {
+        Ok(cfg) => cfg,
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    };
+
+    let (schema, db) = build_schema(&cfg).await;
+    // startup migrations and periodic background jobs, such as trash
+    // purging, on the database of the schema
+    jobs::init(&cfg, db.clone()).await;
+
+    let app_state = State { schema: schema.clone(), db, config: cfg.clone() };
     let mut app = tide::with_state(app_state);
 
-    //environment variables defined in .env file
-    let mut gql = app.at(CFG.get("GQL_URI").unwrap());
-    gql.at(CFG.get("GQL_VER").unwrap()).post(graphql);
-    gql.at(CFG.get("GIQL_VER").unwrap()).get(graphiql);
+    let mut gql = app.at(&cfg.gql_uri);
+    gql.at(&cfg.gql_ver).post(graphql);
+    gql.at(&cfg.giql_ver).get(graphiql);
+    // subscriptions over websocket, graphql-ws protocols
+    gql.at(&cfg.gql_ws_ver).get(GraphQLSubscription::new(schema).build());
 
     let cors = CorsMiddleware::new()
         .allow_methods("GET, POST, OPTIONS".parse::<HeaderValue>().unwrap())
@@ -30,17 +56,14 @@ async fn main() -> Result<(), std::io::Error> {
         .allow_credentials(false);
     app.with(cors);
 
-    let log_level = CFG.get("LOG_LEVEL").unwrap();
     use std::str::FromStr;
-    femme::with_level(femme::LevelFilter::from_str(log_level).unwrap());
+    femme::with_level(
+        femme::LevelFilter::from_str(&cfg.log_level)
+            .unwrap_or(femme::LevelFilter::Info),
+    );
     app.with(tide::log::LogMiddleware::new());
 
-    app.listen(format!(
-        "{}:{}",
-        CFG.get("ADDR").unwrap(),
-        CFG.get("PORT").unwrap()
-    ))
-    .await?;
+    app.listen(format!("{}:{}", cfg.addr, cfg.port)).await?;
 
     Ok(())
 }
@@ -50,6 +73,8 @@ pub struct State {
     pub schema: async_graphql::Schema<
         gql::queries::QueryRoot,
         gql::mutations::MutationRoot,
-        async_graphql::EmptySubscription,
+        gql::subscriptions::SubscriptionRoot,
     >,
+    pub db: mongodb::Database,
+    pub config: Config,
 }
@@ -0,0 +1,111 @@
+use serde::{Serialize, Deserialize};
+use mongodb::bson::{oid::ObjectId, DateTime};
+
+use crate::dbs::mongo::DataSource;
+use crate::util::{
+    constant::GqlResult,
+    datetime::{DateStyle, date_formatted, date_nyrsq},
+};
+
+use crate::{
+    users::{self, models::User},
+    projects::{
+        self,
+        models::{Project, ProjectStatus, ProjectStatusKind},
+    },
+};
+
+#[derive(
+    async_graphql::Enum,
+    Serialize,
+    Deserialize,
+    Copy,
+    Clone,
+    Eq,
+    PartialEq,
+    Debug,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    // someone starred the project
+    ProjectStarred,
+    // someone commented on the project
+    ProjectCommented,
+    // someone replied to the comment
+    CommentReplied,
+    // someone remixed the project
+    ProjectRemixed,
+    // a moderator changed the status of the project
+    ProjectStatusChanged,
+}
+
+#[derive(async_graphql::SimpleObject, Serialize, Deserialize, Clone, Debug)]
+#[graphql(complex)]
+pub struct Notification {
+    pub _id: ObjectId,
+    pub user_id: ObjectId,
+    pub actor_id: Option<ObjectId>,
+    pub kind: NotificationKind,
+    pub project_id: ObjectId,
+    pub comment_id: Option<ObjectId>,
+    #[graphql(skip)]
+    pub project_status: Option<ProjectStatus>,
+    pub read: bool,
+    pub created_at: DateTime,
+}
+
+#[async_graphql::ComplexObject]
+impl Notification {
+    pub async fn actor(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+    ) -> GqlResult<Option<User>> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        match self.actor_id {
+            Some(actor_id) => {
+                Ok(Some(users::services::user_by_id(db, actor_id).await?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub async fn project(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+    ) -> GqlResult<Project> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        projects::services::project_by_id(db, self.project_id).await
+    }
+
+    // the status the project acts as, see ProjectStatusKind
+    pub async fn project_status(&self) -> Option<ProjectStatusKind> {
+        self.project_status.map(Into::into)
+    }
+
+    pub async fn created_at_nyrsq(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+    ) -> String {
+        date_nyrsq(ctx, self.created_at)
+    }
+
+    pub async fn created_at_formatted(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        tz: Option<String>,
+        locale: Option<String>,
+        #[graphql(default)] style: DateStyle,
+    ) -> GqlResult<String> {
+        date_formatted(ctx, self.created_at, tz, locale, style)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct NotificationNew {
+    pub user_id: ObjectId,
+    pub actor_id: Option<ObjectId>,
+    pub kind: NotificationKind,
+    pub project_id: ObjectId,
+    pub comment_id: Option<ObjectId>,
+    pub project_status: Option<ProjectStatus>,
+}
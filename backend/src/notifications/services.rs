@@ -0,0 +1,215 @@
+use futures::stream::StreamExt;
+use mongodb::{
+    Database,
+    bson::{
+        oid::ObjectId, DateTime, Document, doc, from_document, to_document,
+        from_bson,
+    },
+};
+use async_graphql::ErrorExtensions;
+
+use crate::util::{
+    broker::SimpleBroker,
+    config::Config,
+    constant::GqlResult,
+    error::AppError,
+    pagination::{
+        NotificationsResult, PageInfo, ResCount, count_pages_and_total,
+        calculate_current_filter_skip, find_options,
+    },
+};
+
+use crate::users;
+use super::models::{Notification, NotificationNew};
+
+const NOTIFICATIONS_STUFF: &str = "notifications";
+
+// Create new notification for the recipient, nobody is notified of the
+// actions done by oneself.
+pub async fn notification_new(
+    db: &Database,
+    notification_new: NotificationNew,
+) -> GqlResult<()> {
+    if notification_new.actor_id == Some(notification_new.user_id) {
+        return Ok(());
+    }
+
+    let mut new_document = to_document(&notification_new)?;
+    new_document.insert("read", false);
+    new_document.insert("created_at", DateTime::now());
+
+    let coll = db.collection::<Document>("notifications");
+    let notification_res = coll.insert_one(new_document, None).await?;
+    let notification_id = from_bson(notification_res.inserted_id)?;
+
+    let notification = notification_by_id(db, notification_id).await?;
+    SimpleBroker::publish(notification);
+
+    Ok(())
+}
+
+// Notify as a side effect of another write, which is done already. A failure
+// is only logged, so that clients do not retry the write and duplicate it.
+pub async fn notification_notify(
+    db: &Database,
+    notification_new: NotificationNew,
+) {
+    if let Err(error) = self::notification_new(db, notification_new).await {
+        println!("\n\n\n{}\n\n\n", error.message);
+    }
+}
+
+// get notification by its id
+pub async fn notification_by_id(
+    db: &Database,
+    id: ObjectId,
+) -> GqlResult<Notification> {
+    let coll = db.collection::<Document>("notifications");
+
+    let notification_document = coll.find_one(doc! {"_id": id}, None).await?;
+    if let Some(document) = notification_document {
+        let notification: Notification = from_document(document)?;
+        Ok(notification)
+    } else {
+        Err(AppError::NotificationNotFound.extend())
+    }
+}
+
+// Get notifications of the signed-in user
+pub async fn notifications_by_token(
+    db: &Database,
+    cfg: &Config,
+    token: String,
+    unread_only: bool,
+    from_page: u32,
+    first_oid: String,
+    last_oid: String,
+) -> GqlResult<NotificationsResult> {
+    let user = users::services::user_by_token(db, cfg, token).await?;
+
+    let coll = db.collection::<Document>("notifications");
+
+    let mut filter_doc = doc! {"user_id": user._id};
+    if unread_only {
+        filter_doc.insert("read", false);
+    }
+
+    let (pages_count, total_count) = count_pages_and_total(
+        &coll,
+        Some(filter_doc.clone()),
+        None,
+        cfg.page_size,
+    )
+    .await?;
+    let (current_page, skip_x) = calculate_current_filter_skip(
+        from_page,
+        first_oid,
+        last_oid,
+        &mut filter_doc,
+    )
+    .await?;
+
+    let sort_doc = doc! {"_id": -1};
+    let find_options =
+        find_options(Some(sort_doc), skip_x, cfg.page_size).await;
+
+    let mut cursor = coll.find(filter_doc, find_options).await?;
+
+    let mut notifications: Vec<Notification> = vec![];
+    while let Some(result) = cursor.next().await {
+        match result {
+            Ok(document) => {
+                let notification = from_document(document)?;
+                notifications.push(notification);
+            }
+            Err(error) => {
+                println!("\n\n\n{}\n\n\n", error);
+            }
+        }
+    }
+
+    let notifications_result = NotificationsResult {
+        page_info: PageInfo {
+            current_stuff: Some(String::from(NOTIFICATIONS_STUFF)),
+            current_page: Some(current_page),
+            first_cursor: match notifications.first() {
+                Some(notification) => Some(notification._id),
+                _ => None,
+            },
+            last_cursor: match notifications.last() {
+                Some(notification) => Some(notification._id),
+                _ => None,
+            },
+            has_previous_page: current_page > 1,
+            has_next_page: current_page < pages_count,
+        },
+        res_count: ResCount {
+            pages_count: Some(pages_count),
+            total_count: Some(total_count),
+        },
+        current_items: notifications,
+    };
+
+    Ok(notifications_result)
+}
+
+// count unread notifications of the signed-in user
+pub async fn notifications_unread_count(
+    db: &Database,
+    cfg: &Config,
+    token: String,
+) -> GqlResult<u64> {
+    let user = users::services::user_by_token(db, cfg, token).await?;
+
+    let coll = db.collection::<Document>("notifications");
+    let count = coll
+        .count_documents(doc! {"user_id": user._id, "read": false}, None)
+        .await?;
+
+    Ok(count)
+}
+
+// mark one notification as read by its recipient
+pub async fn notification_mark_read(
+    db: &Database,
+    cfg: &Config,
+    notification_id: ObjectId,
+    token: String,
+) -> GqlResult<Notification> {
+    let user = users::services::user_by_token(db, cfg, token).await?;
+    let notification = notification_by_id(db, notification_id).await?;
+
+    if notification.user_id != user._id {
+        return Err(AppError::NoPermission.extend());
+    }
+
+    let coll = db.collection::<Document>("notifications");
+    coll.update_one(
+        doc! {"_id": notification_id},
+        doc! {"$set": {"read": true}},
+        None,
+    )
+    .await?;
+
+    notification_by_id(db, notification_id).await
+}
+
+// mark all notifications of the signed-in user as read
+pub async fn notifications_mark_all_read(
+    db: &Database,
+    cfg: &Config,
+    token: String,
+) -> GqlResult<u64> {
+    let user = users::services::user_by_token(db, cfg, token).await?;
+
+    let coll = db.collection::<Document>("notifications");
+    let update_res = coll
+        .update_many(
+            doc! {"user_id": user._id, "read": false},
+            doc! {"$set": {"read": true}},
+            None,
+        )
+        .await?;
+
+    Ok(update_res.modified_count)
+}
@@ -1,14 +1,33 @@
+use std::cmp::Ordering;
+
 use serde::{Serialize, Deserialize};
-use mongodb::bson::{oid::ObjectId, DateTime};
-use chrono::FixedOffset;
+use mongodb::bson::{oid::ObjectId, Bson, DateTime};
+use chrono::Duration;
+use async_graphql::{dataloader::DataLoader, ErrorExtensions};
 
-use crate::util::constant::{GqlResult, DTF_YMDHMSZ};
+use crate::util::{
+    config::Config,
+    constant::GqlResult,
+    datetime::{DateStyle, date_formatted, date_nyrsq},
+    error::AppError,
+    common::md2html,
+    pagination::{CommentsResult, ProjectsResult},
+};
 use crate::dbs::mongo::DataSource;
 
 use crate::{
-    categories::{self, models::Category},
-    topics::{self, models::Topic},
-    users::{self, models::User},
+    comments::{self, models::Comment},
+    categories::models::Category,
+    topics::models::Topic,
+    users::models::User,
+    views::{
+        self,
+        models::{Stats, StatsRange},
+    },
+    remixes,
+};
+use crate::gql::loaders::{
+    UserLoader, CategoryLoader, ProjectFilesLoader, ProjectTopicsLoader,
 };
 
 #[derive(async_graphql::SimpleObject, Serialize, Deserialize, Clone, Debug)]
@@ -18,6 +37,8 @@ pub struct Project {
     pub user_id: ObjectId,
     pub category_id: ObjectId,
     pub subject: String,
+    #[serde(default)]
+    pub slug: String,
     pub content: String,
     pub contact_user: String,
     pub contact_phone: String,
@@ -29,84 +50,264 @@ pub struct Project {
     pub insides: u64,
     pub stars: u64,
     pub language: String,
-    pub status: i8,
+    #[graphql(skip)]
+    pub status: ProjectStatus,
+    #[serde(default)]
+    pub deleted_at: Option<DateTime>,
+    #[graphql(skip)]
+    #[serde(default)]
+    pub status_prior: Option<ProjectStatus>,
+    #[graphql(skip)]
+    #[serde(default)]
+    pub comments_disabled: bool,
+    #[serde(default)]
+    pub remix_of: Option<ObjectId>,
 }
 
 #[async_graphql::ComplexObject]
 impl Project {
+    // the status the project acts as, see ProjectStatusKind
+    pub async fn status(&self) -> ProjectStatusKind {
+        self.status.into()
+    }
+
     pub async fn cover_image(
         &self,
         ctx: &async_graphql::Context<'_>,
     ) -> GqlResult<File> {
-        let db = &ctx.data_unchecked::<DataSource>().db;
-        super::services::file_by_kind_project_id(db, 1, self._id).await
+        self.file_by_kind(ctx, FileKind::Cover).await
     }
 
     pub async fn source_file(
         &self,
         ctx: &async_graphql::Context<'_>,
     ) -> GqlResult<File> {
+        self.file_by_kind(ctx, FileKind::Source).await
+    }
+
+    // statistics by day, only for the owner
+    pub async fn stats(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        range: StatsRange,
+        token: String,
+    ) -> GqlResult<Stats> {
         let db = &ctx.data_unchecked::<DataSource>().db;
-        super::services::file_by_kind_project_id(db, 2, self._id).await
+        let cfg = ctx.data_unchecked::<Config>();
+        views::services::project_stats(db, cfg, self, range, token).await
     }
 
     pub async fn content_html(&self) -> String {
-        use pulldown_cmark::{Parser, Options, html};
-
-        let mut options = Options::empty();
-        options.insert(Options::ENABLE_HEADING_ATTRIBUTES);
-        // options.insert(Options::ENABLE_TABLES);
-        options.insert(Options::ENABLE_FOOTNOTES);
-        // options.insert(Options::ENABLE_STRIKETHROUGH);
-        options.insert(Options::ENABLE_TASKLISTS);
-        options.insert(Options::ENABLE_SMART_PUNCTUATION);
-
-        let parser = Parser::new_ext(&self.content, options);
-
-        let mut content_html = String::new();
-        html::push_html(&mut content_html, parser);
-
-        content_html
+        md2html(&self.content).await
     }
 
     pub async fn user(
         &self,
         ctx: &async_graphql::Context<'_>,
     ) -> GqlResult<User> {
-        let db = &ctx.data_unchecked::<DataSource>().db;
-        users::services::user_by_id(db, self.user_id).await
+        let loader = ctx.data_unchecked::<DataLoader<UserLoader>>();
+        match loader.load_one(self.user_id).await? {
+            Some(user) => Ok(user),
+            None => Err(AppError::UserNotFound.extend()),
+        }
     }
 
     pub async fn category(
         &self,
         ctx: &async_graphql::Context<'_>,
     ) -> GqlResult<Category> {
-        let db = &ctx.data_unchecked::<DataSource>().db;
-        categories::services::category_by_id(db, self.category_id).await
+        let loader = ctx.data_unchecked::<DataLoader<CategoryLoader>>();
+        match loader.load_one(self.category_id).await? {
+            Some(category) => Ok(category),
+            None => Err(AppError::CategoryNotFound.extend()),
+        }
     }
 
     pub async fn topics(
         &self,
         ctx: &async_graphql::Context<'_>,
     ) -> GqlResult<Vec<Topic>> {
+        let loader = ctx.data_unchecked::<DataLoader<ProjectTopicsLoader>>();
+        Ok(loader.load_one(self._id).await?.unwrap_or_default())
+    }
+
+    pub async fn created_at_nyrsq(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+    ) -> String {
+        date_nyrsq(ctx, self.created_at)
+    }
+
+    pub async fn created_at_formatted(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        tz: Option<String>,
+        locale: Option<String>,
+        #[graphql(default)] style: DateStyle,
+    ) -> GqlResult<String> {
+        date_formatted(ctx, self.created_at, tz, locale, style)
+    }
+
+    pub async fn updated_at_nyrsq(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+    ) -> String {
+        date_nyrsq(ctx, self.updated_at)
+    }
+
+    pub async fn updated_at_formatted(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        tz: Option<String>,
+        locale: Option<String>,
+        #[graphql(default)] style: DateStyle,
+    ) -> GqlResult<String> {
+        date_formatted(ctx, self.updated_at, tz, locale, style)
+    }
+
+    pub async fn comments_enabled(&self) -> bool {
+        !self.comments_disabled
+    }
+
+    pub async fn comments_count(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+    ) -> GqlResult<u64> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        comments::services::comments_count_by_project_id(db, self._id).await
+    }
+
+    pub async fn comments(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        from_page: u32,
+        first_oid: String,
+        last_oid: String,
+    ) -> GqlResult<CommentsResult> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
+        comments::services::comments_by_project_id(
+            db, cfg, self._id, from_page, first_oid, last_oid,
+        )
+        .await
+    }
+
+    pub async fn starred_by_me(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        token: String,
+    ) -> GqlResult<bool> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
+        super::services::project_starred_by_token(db, cfg, self._id, token)
+            .await
+    }
+
+    // the project this one is remixed from, if it's still public
+    pub async fn remix_parent(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+    ) -> GqlResult<Option<Project>> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        match self.remix_of {
+            Some(remix_of) => {
+                super::services::project_public_by_id(db, remix_of).await
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub async fn remixes_count(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+    ) -> GqlResult<u64> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        remixes::services::remixes_count_by_project_id(db, self._id).await
+    }
+
+    pub async fn remixes(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        from_page: u32,
+        first_oid: String,
+        last_oid: String,
+    ) -> GqlResult<ProjectsResult> {
         let db = &ctx.data_unchecked::<DataSource>().db;
-        topics::services::topics_by_project_id(db, self._id).await
+        let cfg = ctx.data_unchecked::<Config>();
+        remixes::services::remixes_by_project_id(
+            db, cfg, self._id, from_page, first_oid, last_oid,
+        )
+        .await
+    }
+
+    pub async fn deleted_at_nyrsq(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+    ) -> Option<String> {
+        self.deleted_at.map(|deleted_at| date_nyrsq(ctx, deleted_at))
+    }
+
+    pub async fn deleted_at_formatted(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        tz: Option<String>,
+        locale: Option<String>,
+        #[graphql(default)] style: DateStyle,
+    ) -> GqlResult<Option<String>> {
+        self.deleted_at
+            .map(|deleted_at| {
+                date_formatted(ctx, deleted_at, tz, locale, style)
+            })
+            .transpose()
+    }
+
+    // the date after which a trashed project would be purged
+    pub async fn purged_at_nyrsq(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+    ) -> Option<String> {
+        self.purged_at(ctx).map(|purged_at| date_nyrsq(ctx, purged_at))
+    }
+
+    pub async fn purged_at_formatted(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        tz: Option<String>,
+        locale: Option<String>,
+        #[graphql(default)] style: DateStyle,
+    ) -> GqlResult<Option<String>> {
+        self.purged_at(ctx)
+            .map(|purged_at| date_formatted(ctx, purged_at, tz, locale, style))
+            .transpose()
     }
+}
 
-    pub async fn created_at_nyrsq(&self) -> String {
-        self.created_at
-            .to_chrono()
-            .with_timezone(&FixedOffset::east_opt(8 * 3600).unwrap())
-            .format(DTF_YMDHMSZ)
-            .to_string()
+impl Project {
+    fn purged_at(&self, ctx: &async_graphql::Context<'_>) -> Option<DateTime> {
+        let cfg = ctx.data_unchecked::<Config>();
+
+        self.deleted_at.map(|deleted_at| {
+            DateTime::from_millis(
+                deleted_at.timestamp_millis()
+                    + Duration::days(cfg.trash_retention_days)
+                        .num_milliseconds(),
+            )
+        })
     }
 
-    pub async fn updated_at_nyrsq(&self) -> String {
-        self.updated_at
-            .to_chrono()
-            .with_timezone(&FixedOffset::east_opt(8 * 3600).unwrap())
-            .format(DTF_YMDHMSZ)
-            .to_string()
+    // the first file of the kind, by the batched files of projects
+    async fn file_by_kind(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        file_kind: FileKind,
+    ) -> GqlResult<File> {
+        let loader = ctx.data_unchecked::<DataLoader<ProjectFilesLoader>>();
+        let files = loader.load_one(self._id).await?.unwrap_or_default();
+
+        match files.into_iter().find(|file| file.kind == file_kind) {
+            Some(file) => Ok(file),
+            None => Err(AppError::FileNotFound.extend()),
+        }
     }
 }
 
@@ -115,6 +316,9 @@ pub struct ProjectNew {
     pub user_id: ObjectId,
     pub category_id: ObjectId,
     pub subject: String,
+    #[graphql(skip)]
+    #[serde(default)]
+    pub slug: String,
     pub content: String,
     pub contact_user: String,
     pub contact_phone: String,
@@ -128,21 +332,30 @@ pub struct ProjectNew {
     pub stars: u64,
     pub language: String,
     #[graphql(skip)]
-    pub status: i8,
+    pub status: ProjectStatus,
+}
+
+// a new project with its topics & files, published in one transaction
+#[derive(async_graphql::InputObject, Serialize, Deserialize)]
+pub struct ProjectPublish {
+    pub project_new: ProjectNew,
+    pub topic_names: String,
+    pub cover_image_id: ObjectId,
+    pub source_file_id: ObjectId,
 }
 
 #[derive(async_graphql::SimpleObject, Serialize, Deserialize, Clone, Debug)]
 pub struct File {
     pub _id: ObjectId,
     pub name: String,
-    pub kind: i8,
+    pub kind: FileKind,
     pub location: String,
 }
 
 #[derive(async_graphql::InputObject, Serialize, Deserialize)]
 pub struct FileNew {
     pub name: String,
-    pub kind: i8,
+    pub kind: FileKind,
     pub location: String,
 }
 
@@ -160,3 +373,211 @@ pub struct ProjectFileNew {
     pub project_id: ObjectId,
     pub file_id: ObjectId,
 }
+
+// Status of the project, stored as its integer. Legacy projects may have
+// any integer, kept as `Other` and compared as their integers, so the
+// projects of 1 and above are published as before.
+#[derive(
+    Serialize, Deserialize, Copy, Clone, Default, Eq, PartialEq, Debug,
+)]
+#[serde(from = "i8", into = "i8")]
+pub enum ProjectStatus {
+    Deleted,
+    Rejected,
+    Draft,
+    #[default]
+    Pending,
+    Published,
+    Recommended,
+    Broadcast,
+    Controlled,
+    Other(i8),
+}
+
+impl From<ProjectStatus> for i8 {
+    fn from(status: ProjectStatus) -> Self {
+        match status {
+            ProjectStatus::Deleted => -3,
+            ProjectStatus::Rejected => -2,
+            ProjectStatus::Draft => -1,
+            ProjectStatus::Pending => 0,
+            ProjectStatus::Published => 1,
+            ProjectStatus::Recommended => 2,
+            ProjectStatus::Broadcast => 3,
+            ProjectStatus::Controlled => 6,
+            ProjectStatus::Other(value) => value,
+        }
+    }
+}
+
+impl From<i8> for ProjectStatus {
+    fn from(value: i8) -> Self {
+        match value {
+            -3 => ProjectStatus::Deleted,
+            -2 => ProjectStatus::Rejected,
+            -1 => ProjectStatus::Draft,
+            0 => ProjectStatus::Pending,
+            1 => ProjectStatus::Published,
+            2 => ProjectStatus::Recommended,
+            3 => ProjectStatus::Broadcast,
+            6 => ProjectStatus::Controlled,
+            _ => ProjectStatus::Other(value),
+        }
+    }
+}
+
+impl PartialOrd for ProjectStatus {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ProjectStatus {
+    fn cmp(&self, other: &Self) -> Ordering {
+        i8::from(*self).cmp(&i8::from(*other))
+    }
+}
+
+// Status of the schema. The legacy statuses are shown as the status below
+// them they act as, and those below deleted are deleted.
+#[derive(async_graphql::Enum, Copy, Clone, Eq, PartialEq, Debug)]
+#[graphql(name = "ProjectStatus")]
+pub enum ProjectStatusKind {
+    Deleted,
+    Rejected,
+    Draft,
+    Pending,
+    Published,
+    Recommended,
+    Broadcast,
+    Controlled,
+}
+
+impl From<ProjectStatus> for ProjectStatusKind {
+    fn from(status: ProjectStatus) -> Self {
+        match i8::from(status) {
+            i8::MIN..=-3 => ProjectStatusKind::Deleted,
+            -2 => ProjectStatusKind::Rejected,
+            -1 => ProjectStatusKind::Draft,
+            0 => ProjectStatusKind::Pending,
+            1 => ProjectStatusKind::Published,
+            2 => ProjectStatusKind::Recommended,
+            3..=5 => ProjectStatusKind::Broadcast,
+            6..=i8::MAX => ProjectStatusKind::Controlled,
+        }
+    }
+}
+
+impl From<ProjectStatusKind> for ProjectStatus {
+    fn from(kind: ProjectStatusKind) -> Self {
+        match kind {
+            ProjectStatusKind::Deleted => ProjectStatus::Deleted,
+            ProjectStatusKind::Rejected => ProjectStatus::Rejected,
+            ProjectStatusKind::Draft => ProjectStatus::Draft,
+            ProjectStatusKind::Pending => ProjectStatus::Pending,
+            ProjectStatusKind::Published => ProjectStatus::Published,
+            ProjectStatusKind::Recommended => ProjectStatus::Recommended,
+            ProjectStatusKind::Broadcast => ProjectStatus::Broadcast,
+            ProjectStatusKind::Controlled => ProjectStatus::Controlled,
+        }
+    }
+}
+
+// so that the status can be used in the filters of queries
+impl From<ProjectStatus> for Bson {
+    fn from(status: ProjectStatus) -> Self {
+        Bson::Int32(i8::from(status).into())
+    }
+}
+
+// kind of the file, stored as its integer
+#[derive(
+    async_graphql::Enum,
+    Serialize,
+    Deserialize,
+    Copy,
+    Clone,
+    Eq,
+    PartialEq,
+    Debug,
+)]
+#[serde(try_from = "i8", into = "i8")]
+pub enum FileKind {
+    Cover,
+    Source,
+}
+
+impl From<FileKind> for i8 {
+    fn from(kind: FileKind) -> Self {
+        match kind {
+            FileKind::Cover => 1,
+            FileKind::Source => 2,
+        }
+    }
+}
+
+impl TryFrom<i8> for FileKind {
+    type Error = String;
+
+    fn try_from(value: i8) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(FileKind::Cover),
+            2 => Ok(FileKind::Source),
+            _ => Err(format!("invalid file kind: {}", value)),
+        }
+    }
+}
+
+impl From<FileKind> for Bson {
+    fn from(kind: FileKind) -> Self {
+        Bson::Int32(i8::from(kind).into())
+    }
+}
+
+#[derive(async_graphql::SimpleObject, Serialize, Deserialize, Clone, Debug)]
+pub struct ProjectStar {
+    pub _id: ObjectId,
+    pub user_id: ObjectId,
+    pub project_id: ObjectId,
+    pub created_at: DateTime,
+}
+
+#[cfg(test)]
+mod tests {
+    use mongodb::bson::{self, doc};
+
+    use super::*;
+
+    #[test]
+    fn project_with_unlisted_status_loads() {
+        let project_doc = doc! {
+            "_id": ObjectId::new(),
+            "user_id": ObjectId::new(),
+            "category_id": ObjectId::new(),
+            "subject": "Legacy",
+            "content": "",
+            "contact_user": "",
+            "contact_phone": "",
+            "contact_email": "",
+            "contact_im": "",
+            "created_at": DateTime::now(),
+            "updated_at": DateTime::now(),
+            "hits": 0_i64,
+            "insides": 0_i64,
+            "stars": 0_i64,
+            "language": "en-us",
+            "status": 5,
+        };
+
+        let project: Project = bson::from_document(project_doc).unwrap();
+        assert_eq!(project.status, ProjectStatus::Other(5));
+        assert!(project.status > ProjectStatus::Published);
+        assert_eq!(
+            ProjectStatusKind::from(project.status),
+            ProjectStatusKind::Broadcast
+        );
+
+        let stored = bson::to_document(&project).unwrap();
+        assert_eq!(stored.get_i32("status").unwrap(), 5);
+    }
+}
@@ -1,17 +1,20 @@
 use futures::stream::StreamExt;
 use mongodb::{
-    Database,
+    Database, Client, ClientSession,
     bson::{
         oid::ObjectId, DateTime, Document, doc, from_document, to_document,
         from_bson,
     },
-    options::FindOptions,
+    options::{FindOptions, UpdateOptions},
 };
-use async_graphql::Error;
+use async_graphql::ErrorExtensions;
 use chrono::Duration;
 
 use crate::util::{
+    broker::SimpleBroker,
+    config::Config,
     constant::GqlResult,
+    error::AppError,
     common::bson_dt_nyr,
     pagination::{
         ProjectsResult, PageInfo, ResCount, count_pages_and_total,
@@ -19,19 +22,68 @@ use crate::util::{
     },
 };
 
-use crate::users;
+use crate::dbs::mongo::commit_transaction;
+use crate::{users, users::models::UserStatus};
 use crate::categories;
+use crate::slugs::services::{
+    slug_unique, slug_unique_with_session, slug_change_with_session,
+    slug_history_target_id, slugs_history_delete,
+};
 use crate::{topics, topics::models::TopicProject};
+use crate::notifications::{
+    self,
+    models::{NotificationKind, NotificationNew},
+};
+use crate::idempotency::services::{idempotency_key_check, idempotency_key_settle};
 use super::models::{
-    Project, ProjectNew, File, FileNew, ProjectFileNew, ProjectFile,
+    Project, ProjectNew, ProjectPublish, File, FileNew, ProjectFileNew,
+    ProjectFile, ProjectStar, ProjectStatus, FileKind,
 };
 
-const PROJECTS_STUFF: &str = "projects";
+pub const PROJECTS_STUFF: &str = "projects";
 
-// create new project
+// Create new project. With an idempotency key, a retry gets the project
+// created at first; without one, the same subject from the same user within
+// two days is rejected as a double submission.
 pub async fn project_new(
     db: &Database,
+    cfg: &Config,
     project_new: ProjectNew,
+    idempotency_key: Option<String>,
+) -> GqlResult<Project> {
+    let user_id = Some(project_new.user_id);
+    if let Some(project_id) = idempotency_key_check(
+        db,
+        "projects",
+        user_id,
+        &idempotency_key,
+        &project_new,
+    )
+    .await?
+    {
+        return project_by_id(db, project_id).await;
+    }
+
+    let subject_check = idempotency_key.is_none();
+    let project_res = project_insert(db, cfg, project_new, subject_check).await;
+    let project_id = project_res.as_ref().ok().map(|project| project._id);
+    idempotency_key_settle(
+        db,
+        "projects",
+        user_id,
+        &idempotency_key,
+        project_id,
+    )
+    .await?;
+
+    project_res
+}
+
+async fn project_insert(
+    db: &Database,
+    cfg: &Config,
+    mut project_new: ProjectNew,
+    subject_check: bool,
 ) -> GqlResult<Project> {
     let coll = db.collection::<Document>("projects");
 
@@ -42,68 +94,1011 @@ pub async fn project_new(
         "subject": &project_new.subject,
         "created_at": {"$gte": now2ago} // "$lte": now
     };
-    let exist_document = coll.find_one(filter_doc, None).await?;
+    let exist_document = match subject_check {
+        true => coll.find_one(filter_doc, None).await?,
+        false => None,
+    };
 
     if exist_document.is_none() {
+        project_new.slug =
+            slug_unique(db, "projects", &project_new.subject, None).await?;
         let mut new_document = to_document(&project_new)?;
         new_document.insert("created_at", now);
         new_document.insert("updated_at", now);
 
-        let project_res =
-            coll.insert_one(new_document, None).await.expect("写入未成功");
+        let project_res = coll.insert_one(new_document, None).await?;
         let project_id = from_bson(project_res.inserted_id)?;
 
         project_by_id(db, project_id).await
     } else {
         let project: Project = from_document(exist_document.unwrap())?;
 
-        Err(Error::new(bson_dt_nyr(project.created_at).await))
+        Err(AppError::ProjectExists {
+            created_at: bson_dt_nyr(project.created_at, cfg.tz()).await,
+        }
+        .extend())
     }
 }
 
+// Publish new project with its topics & files in one transaction, so that
+// nothing is left when any step fails. Transactions need mongodb running
+// as a replica set. The owner is the signed-in user of the token, whatever
+// the input says, and idempotency keys work as in project_new.
+pub async fn project_publish(
+    db: &Database,
+    cfg: &Config,
+    client: Client,
+    mut project_publish: ProjectPublish,
+    idempotency_key: Option<String>,
+    token: String,
+) -> GqlResult<Project> {
+    let user = users::services::user_by_token(db, cfg, token).await?;
+    project_publish.project_new.user_id = user._id;
+
+    let user_id = Some(user._id);
+    if let Some(project_id) = idempotency_key_check(
+        db,
+        "projects",
+        user_id,
+        &idempotency_key,
+        &project_publish,
+    )
+    .await?
+    {
+        return project_by_id(db, project_id).await;
+    }
+
+    let subject_check = idempotency_key.is_none();
+    let project_res = project_publish_transaction(
+        db,
+        cfg,
+        client,
+        project_publish,
+        subject_check,
+    )
+    .await;
+    let project_id = project_res.as_ref().ok().map(|project| project._id);
+    idempotency_key_settle(
+        db,
+        "projects",
+        user_id,
+        &idempotency_key,
+        project_id,
+    )
+    .await?;
+
+    project_res
+}
+
+async fn project_publish_transaction(
+    db: &Database,
+    cfg: &Config,
+    client: Client,
+    project_publish: ProjectPublish,
+    subject_check: bool,
+) -> GqlResult<Project> {
+    let mut session = client.start_session(None).await?;
+    session.start_transaction(None).await?;
+
+    let project_id = match project_publish_in_session(
+        db,
+        cfg,
+        &mut session,
+        project_publish,
+        subject_check,
+    )
+    .await
+    {
+        Ok(project_id) => project_id,
+        Err(error) => {
+            session.abort_transaction().await?;
+            return Err(error);
+        }
+    };
+
+    commit_transaction(&mut session).await?;
+
+    project_by_id(db, project_id).await
+}
+
+async fn project_publish_in_session(
+    db: &Database,
+    cfg: &Config,
+    session: &mut ClientSession,
+    project_publish: ProjectPublish,
+    subject_check: bool,
+) -> GqlResult<ObjectId> {
+    let ProjectPublish {
+        mut project_new,
+        topic_names,
+        cover_image_id,
+        source_file_id,
+    } = project_publish;
+    let user_id = project_new.user_id;
+
+    let coll_files = db.collection::<Document>("files");
+    for (file_id, file_kind) in [
+        (cover_image_id, FileKind::Cover),
+        (source_file_id, FileKind::Source),
+    ] {
+        let file_document = coll_files
+            .find_one_with_session(
+                doc! {"_id": file_id, "kind": file_kind},
+                None,
+                session,
+            )
+            .await?;
+        if file_document.is_none() {
+            return Err(AppError::FileNotFound.extend());
+        }
+    }
+
+    let coll = db.collection::<Document>("projects");
+
+    let now = DateTime::now();
+    let now2ago = now.to_chrono() + Duration::days(-2);
+    let filter_doc = doc! {
+        "user_id": &project_new.user_id,
+        "subject": &project_new.subject,
+        "created_at": {"$gte": now2ago}
+    };
+    let exist_document = match subject_check {
+        true => coll.find_one_with_session(filter_doc, None, session).await?,
+        false => None,
+    };
+    if let Some(exist_document) = exist_document {
+        let project: Project = from_document(exist_document)?;
+        return Err(AppError::ProjectExists {
+            created_at: bson_dt_nyr(project.created_at, cfg.tz()).await,
+        }
+        .extend());
+    }
+
+    project_new.slug = slug_unique_with_session(
+        db,
+        session,
+        "projects",
+        &project_new.subject,
+        None,
+    )
+    .await?;
+    let mut new_document = to_document(&project_new)?;
+    new_document.insert("created_at", now);
+    new_document.insert("updated_at", now);
+
+    let project_res =
+        coll.insert_one_with_session(new_document, None, session).await?;
+    let project_id: ObjectId = from_bson(project_res.inserted_id)?;
+
+    topics::services::topics_project_link(
+        db,
+        session,
+        &topic_names,
+        user_id,
+        project_id,
+    )
+    .await?;
+
+    let coll_projects_files = db.collection::<Document>("projects_files");
+    for file_id in [cover_image_id, source_file_id] {
+        let project_file_new = ProjectFileNew { user_id, project_id, file_id };
+        coll_projects_files
+            .insert_one_with_session(
+                to_document(&project_file_new)?,
+                None,
+                session,
+            )
+            .await?;
+    }
+
+    Ok(project_id)
+}
+
 pub async fn project_by_id(
     db: &Database,
     project_id: ObjectId,
 ) -> GqlResult<Project> {
     let coll = db.collection::<Document>("projects");
 
-    let project_document = coll
-        .find_one(doc! {"_id": project_id}, None)
-        .await
-        .expect("查询未成功")
-        .unwrap();
+    let project_document =
+        coll.find_one(doc! {"_id": project_id}, None).await?;
 
-    let project: Project = from_document(project_document)?;
-    Ok(project)
+    match project_document {
+        Some(document) => Ok(from_document(document)?),
+        None => Err(AppError::ProjectNotFound.extend()),
+    }
+}
+
+// get project info by slug, or by an old slug before renaming
+pub async fn project_by_slug(
+    db: &Database,
+    slug: String,
+) -> GqlResult<Project> {
+    let coll = db.collection::<Document>("projects");
+
+    let project_document =
+        coll.find_one(doc! {"slug": slug.to_lowercase()}, None).await?;
+
+    match project_document {
+        Some(document) => Ok(from_document(document)?),
+        None => match slug_history_target_id(db, "projects", &slug).await? {
+            Some(project_id) => project_by_id(db, project_id).await,
+            None => Err(AppError::ProjectNotFound.extend()),
+        },
+    }
+}
+
+// Rename project by its owner, the old slug is kept for redirects. The
+// subject, the slug and its history are changed in one transaction, so that
+// no slug is lost when any step fails.
+pub async fn project_rename(
+    db: &Database,
+    cfg: &Config,
+    client: Client,
+    project_id: ObjectId,
+    subject: String,
+    token: String,
+) -> GqlResult<Project> {
+    let user = users::services::user_by_token(db, cfg, token).await?;
+    let project = project_by_id(db, project_id).await?;
+
+    if project.user_id != user._id {
+        return Err(AppError::NoPermission.extend());
+    }
+
+    let subject = subject.trim().to_string();
+    if subject.is_empty() {
+        return Err(AppError::NameInvalid.extend());
+    }
+
+    let mut session = client.start_session(None).await?;
+    session.start_transaction(None).await?;
+
+    if let Err(error) =
+        project_rename_in_session(db, &mut session, &project, &subject).await
+    {
+        session.abort_transaction().await?;
+        return Err(error);
+    }
+
+    commit_transaction(&mut session).await?;
+
+    project_by_id(db, project_id).await
+}
+
+async fn project_rename_in_session(
+    db: &Database,
+    session: &mut ClientSession,
+    project: &Project,
+    subject: &str,
+) -> GqlResult<()> {
+    let coll = db.collection::<Document>("projects");
+    coll.update_one_with_session(
+        doc! {"_id": project._id},
+        doc! {"$set": {"subject": subject, "updated_at": DateTime::now()}},
+        None,
+        session,
+    )
+    .await?;
+
+    let slug = slug_unique_with_session(
+        db,
+        session,
+        "projects",
+        subject,
+        Some(project._id),
+    )
+    .await?;
+    slug_change_with_session(
+        db,
+        session,
+        "projects",
+        project._id,
+        &project.slug,
+        &slug,
+    )
+    .await
 }
 
+// Set the status of the project, by moderators only.
+pub async fn project_set_status(
+    db: &Database,
+    cfg: &Config,
+    project_id: ObjectId,
+    status: ProjectStatus,
+    token: String,
+) -> GqlResult<Project> {
+    let user = users::services::user_by_token(db, cfg, token).await?;
+    if user.status < UserStatus::Moderator {
+        return Err(AppError::NoPermission.extend());
+    }
+
+    project_status_set(db, project_id, status).await
+}
+
+// Deprecated alias of project_set_status, only the "status" field is
+// accepted, and its value must be one of the stored status integers. It is
+// checked as project_set_status, only moderators could set it.
 pub async fn project_update_one_field_by_id(
     db: &Database,
+    cfg: &Config,
     project_id: ObjectId,
     field_name: String,
     field_val: String,
+    token: String,
+) -> GqlResult<Project> {
+    match field_name.as_str() {
+        "status" => {
+            let status = field_val
+                .parse::<i8>()
+                .ok()
+                .map(ProjectStatus::from)
+                .filter(|status| !matches!(status, ProjectStatus::Other(_)))
+                .ok_or_else(|| AppError::StatusInvalid.extend())?;
+            project_set_status(db, cfg, project_id, status, token).await
+        }
+        _ => Err(AppError::FieldInvalid.extend()),
+    }
+}
+
+// The trash is kept by project_delete & project_restore of the owner, with
+// the prior status and the time of deleting, so moderators could neither
+// move a project into it nor out of it.
+async fn project_status_set(
+    db: &Database,
+    project_id: ObjectId,
+    status: ProjectStatus,
 ) -> GqlResult<Project> {
+    if status == ProjectStatus::Deleted {
+        return Err(AppError::StatusInvalid.extend());
+    }
+
     let coll = db.collection::<Document>("projects");
 
-    let query_doc = doc! {"_id": project_id};
-    let update_doc = match field_name.as_str() {
-        "status" => {
+    // the owner is notified when a moderator changes the status
+    let status_prior = project_by_id(db, project_id).await?.status;
+
+    let query_doc = doc! {
+        "_id": project_id,
+        "status": {"$ne": ProjectStatus::Deleted},
+        "deleted_at": null
+    };
+    let update_doc = doc! {"$set": {
+        "status": status,
+        "updated_at": DateTime::now()
+    }};
+    let update_res = coll.update_one(query_doc, update_doc, None).await?;
+    if update_res.matched_count == 0 {
+        return Err(AppError::ProjectInTrash.extend());
+    }
+
+    let project = project_by_id(db, project_id).await?;
+    // newly published projects are pushed to the subscribers
+    if status_prior < ProjectStatus::Published
+        && project.status >= ProjectStatus::Published
+    {
+        SimpleBroker::publish(project.clone());
+    }
+
+    if status_prior != project.status {
+        let notification_new = NotificationNew {
+            user_id: project.user_id,
+            actor_id: None,
+            kind: NotificationKind::ProjectStatusChanged,
+            project_id,
+            comment_id: None,
+            project_status: Some(project.status),
+        };
+        notifications::services::notification_notify(db, notification_new)
+            .await;
+    }
+
+    Ok(project)
+}
+
+// move project into trash by its owner
+pub async fn project_delete(
+    db: &Database,
+    cfg: &Config,
+    project_id: ObjectId,
+    token: String,
+) -> GqlResult<Project> {
+    let user = users::services::user_by_token(db, cfg, token).await?;
+    let project = project_by_id(db, project_id).await?;
+
+    if project.user_id != user._id {
+        return Err(AppError::NoPermission.extend());
+    }
+
+    let coll = db.collection::<Document>("projects");
+    let update_res = coll
+        .update_one(
+            doc! {"_id": project_id, "deleted_at": null},
             doc! {"$set": {
-                field_name: field_val.parse::<i32>()?,
-                "updated_at": DateTime::now()
-            }}
-        }
-        "hits" | "applicants" => {
-            doc! {"$inc": {field_name: field_val.parse::<i64>()?}}
+                "status": ProjectStatus::Deleted,
+                "status_prior": project.status,
+                "deleted_at": DateTime::now()
+            }},
+            None,
+        )
+        .await?;
+
+    if update_res.matched_count > 0 {
+        project_by_id(db, project_id).await
+    } else {
+        Err(AppError::ProjectInTrash.extend())
+    }
+}
+
+// restore project from trash by its owner, within the retention window
+pub async fn project_restore(
+    db: &Database,
+    cfg: &Config,
+    project_id: ObjectId,
+    token: String,
+) -> GqlResult<Project> {
+    let user = users::services::user_by_token(db, cfg, token).await?;
+    let project = project_by_id(db, project_id).await?;
+
+    if project.user_id != user._id {
+        return Err(AppError::NoPermission.extend());
+    }
+
+    let retention_days = cfg.trash_retention_days;
+    let expired_at =
+        DateTime::now().to_chrono() + Duration::days(-retention_days);
+
+    let coll = db.collection::<Document>("projects");
+    let update_res = coll
+        .update_one(
+            doc! {"_id": project_id, "deleted_at": {"$gt": expired_at}},
+            doc! {
+                "$set": {
+                    "status": project.status_prior.unwrap_or_default(),
+                    "updated_at": DateTime::now()
+                },
+                "$unset": {"status_prior": "", "deleted_at": ""}
+            },
+            None,
+        )
+        .await?;
+
+    if update_res.matched_count > 0 {
+        project_by_id(db, project_id).await
+    } else {
+        Err(AppError::ProjectNotRestorable.extend())
+    }
+}
+
+// get all trashed projects of the owner
+pub async fn projects_trash(
+    db: &Database,
+    cfg: &Config,
+    token: String,
+    from_page: u32,
+    first_oid: String,
+    last_oid: String,
+) -> GqlResult<ProjectsResult> {
+    let user = users::services::user_by_token(db, cfg, token).await?;
+
+    let coll = db.collection::<Document>("projects");
+
+    let mut filter_doc =
+        doc! {"user_id": user._id, "deleted_at": {"$ne": null}};
+
+    let (pages_count, total_count) = count_pages_and_total(
+        &coll,
+        Some(filter_doc.clone()),
+        None,
+        cfg.page_size,
+    )
+    .await?;
+    let (current_page, skip_x) = calculate_current_filter_skip(
+        from_page,
+        first_oid,
+        last_oid,
+        &mut filter_doc,
+    )
+    .await?;
+
+    let sort_doc = doc! {"_id": -1};
+    let find_options =
+        find_options(Some(sort_doc), skip_x, cfg.page_size).await;
+
+    let mut cursor = coll.find(filter_doc, find_options).await?;
+
+    let mut projects: Vec<Project> = vec![];
+    while let Some(result) = cursor.next().await {
+        match result {
+            Ok(document) => {
+                let project = from_document(document)?;
+                projects.push(project);
+            }
+            Err(error) => {
+                println!("\n\n\n{}\n\n\n", error);
+            }
         }
-        _ => doc! {},
+    }
+
+    let projects_result = ProjectsResult {
+        page_info: PageInfo {
+            current_stuff: Some(String::from(PROJECTS_STUFF)),
+            current_page: Some(current_page),
+            first_cursor: match projects.first() {
+                Some(user) => Some(user._id),
+                _ => None,
+            },
+            last_cursor: match projects.last() {
+                Some(user) => Some(user._id),
+                _ => None,
+            },
+            has_previous_page: current_page > 1,
+            has_next_page: current_page < pages_count,
+        },
+        res_count: ResCount {
+            pages_count: Some(pages_count),
+            total_count: Some(total_count),
+        },
+        current_items: projects,
     };
 
-    coll.update_one(query_doc, update_doc, None).await?;
+    Ok(projects_result)
+}
+
+// hard-delete trashed projects whose retention window has expired
+pub async fn projects_trash_purge(
+    db: &Database,
+    cfg: &Config,
+) -> GqlResult<u64> {
+    let retention_days = cfg.trash_retention_days;
+    let expired_at =
+        DateTime::now().to_chrono() + Duration::days(-retention_days);
+
+    let coll = db.collection::<Document>("projects");
+    let mut cursor =
+        coll.find(doc! {"deleted_at": {"$lte": expired_at}}, None).await?;
+
+    let mut project_ids: Vec<ObjectId> = vec![];
+    while let Some(result) = cursor.next().await {
+        match result {
+            Ok(document) => {
+                let project: Project = from_document(document)?;
+                project_ids.push(project._id);
+            }
+            Err(error) => {
+                println!("\n\n\n{}\n\n\n", error);
+            }
+        }
+    }
+
+    let mut purged_count = 0;
+    for project_id in project_ids {
+        project_purge(db, project_id).await?;
+        purged_count += 1;
+    }
+
+    Ok(purged_count)
+}
+
+// remove project, its files & topics links, and files no longer referenced
+async fn project_purge(db: &Database, project_id: ObjectId) -> GqlResult<()> {
+    let projects_files = projects_files_by_project_id(db, project_id).await?;
+    let coll_projects_files = db.collection::<Document>("projects_files");
+    coll_projects_files
+        .delete_many(doc! {"project_id": project_id}, None)
+        .await?;
+
+    let coll_files = db.collection::<Document>("files");
+    for project_file in projects_files {
+        let file_quotes = coll_projects_files
+            .count_documents(doc! {"file_id": project_file.file_id}, None)
+            .await?;
+        if file_quotes == 0 {
+            coll_files
+                .delete_one(doc! {"_id": project_file.file_id}, None)
+                .await?;
+        }
+    }
+
+    let coll_topics_projects =
+        db.collection::<Document>("topics_users_projects");
+    let topics_projects =
+        topics::services::topics_projects_by_project_id(db, project_id).await?;
+    let coll_topics = db.collection::<Document>("topics");
+    for topic_project in topics_projects {
+        coll_topics
+            .update_one(
+                doc! {"_id": topic_project.topic_id},
+                doc! {"$inc": {"quotes": -1}},
+                None,
+            )
+            .await?;
+    }
+    coll_topics_projects
+        .delete_many(doc! {"project_id": project_id}, None)
+        .await?;
+
+    let coll_stars = db.collection::<Document>("project_stars");
+    coll_stars.delete_many(doc! {"project_id": project_id}, None).await?;
+
+    let coll_studios_projects = db.collection::<Document>("studios_projects");
+    coll_studios_projects
+        .delete_many(doc! {"project_id": project_id}, None)
+        .await?;
+
+    let coll_comments = db.collection::<Document>("comments");
+    coll_comments.delete_many(doc! {"project_id": project_id}, None).await?;
+
+    let coll_notifications = db.collection::<Document>("notifications");
+    coll_notifications
+        .delete_many(doc! {"project_id": project_id}, None)
+        .await?;
+
+    let coll_views = db.collection::<Document>("projects_views");
+    coll_views.delete_many(doc! {"project_id": project_id}, None).await?;
+
+    let coll_views_daily = db.collection::<Document>("projects_views_daily");
+    coll_views_daily.delete_many(doc! {"project_id": project_id}, None).await?;
+
+    let coll_downloads_daily =
+        db.collection::<Document>("projects_downloads_daily");
+    coll_downloads_daily
+        .delete_many(doc! {"project_id": project_id}, None)
+        .await?;
+
+    slugs_history_delete(db, "projects", project_id).await?;
+
+    let coll = db.collection::<Document>("projects");
+    coll.delete_one(doc! {"_id": project_id}, None).await?;
+
+    Ok(())
+}
+
+// give slugs to the projects created before slugs, returns the count of
+// migrated projects
+pub async fn projects_slugs_migrate(db: &Database) -> GqlResult<u64> {
+    let coll = db.collection::<Document>("projects");
+
+    let mut cursor = coll.find(doc! {"slug": {"$exists": false}}, None).await?;
+
+    let mut projects: Vec<Project> = vec![];
+    while let Some(result) = cursor.next().await {
+        match result {
+            Ok(document) => {
+                let project: Project = from_document(document)?;
+                projects.push(project);
+            }
+            Err(error) => {
+                println!("\n\n\n{}\n\n\n", error);
+            }
+        }
+    }
+
+    let mut migrated_count = 0;
+    for project in projects {
+        let slug =
+            slug_unique(db, "projects", &project.subject, Some(project._id))
+                .await?;
+        coll.update_one(
+            doc! {"_id": project._id},
+            doc! {"$set": {"slug": slug}},
+            None,
+        )
+        .await?;
+        migrated_count += 1;
+    }
+
+    Ok(migrated_count)
+}
+
+// enable or disable comments of project, by its owner or moderators
+pub async fn project_comments_enable(
+    db: &Database,
+    cfg: &Config,
+    project_id: ObjectId,
+    enabled: bool,
+    token: String,
+) -> GqlResult<Project> {
+    let user = users::services::user_by_token(db, cfg, token).await?;
+    let project = project_by_id(db, project_id).await?;
+
+    if project.user_id != user._id && user.status < UserStatus::Moderator {
+        return Err(AppError::NoPermission.extend());
+    }
+
+    let coll = db.collection::<Document>("projects");
+    coll.update_one(
+        doc! {"_id": project_id},
+        doc! {"$set": {"comments_disabled": !enabled}},
+        None,
+    )
+    .await?;
+
+    project_by_id(db, project_id).await
+}
+
+// star project by the signed-in user
+pub async fn project_star(
+    db: &Database,
+    cfg: &Config,
+    client: Client,
+    project_id: ObjectId,
+    token: String,
+) -> GqlResult<Project> {
+    let user = users::services::user_by_token(db, cfg, token).await?;
+    let project = project_by_id(db, project_id).await?;
+
+    if project.status < ProjectStatus::Published {
+        return Err(AppError::ProjectUnpublished.extend());
+    }
+
+    let starred =
+        project_star_transaction(db, client, user._id, project_id, true)
+            .await?;
+
+    if starred {
+        let notification_new = NotificationNew {
+            user_id: project.user_id,
+            actor_id: Some(user._id),
+            kind: NotificationKind::ProjectStarred,
+            project_id,
+            comment_id: None,
+            project_status: None,
+        };
+        notifications::services::notification_notify(db, notification_new)
+            .await;
+    }
 
     project_by_id(db, project_id).await
 }
 
+// unstar project by the signed-in user
+pub async fn project_unstar(
+    db: &Database,
+    cfg: &Config,
+    client: Client,
+    project_id: ObjectId,
+    token: String,
+) -> GqlResult<Project> {
+    let user = users::services::user_by_token(db, cfg, token).await?;
+
+    project_star_transaction(db, client, user._id, project_id, false).await?;
+
+    project_by_id(db, project_id).await
+}
+
+// Star or unstar the project, and keep the counter of the project in step, in
+// one transaction. The unique index on (user_id, project_id) keeps one star
+// per user, so the counter only changes when a star is really inserted or
+// deleted. Returns whether it changed.
+async fn project_star_transaction(
+    db: &Database,
+    client: Client,
+    user_id: ObjectId,
+    project_id: ObjectId,
+    star: bool,
+) -> GqlResult<bool> {
+    let mut session = client.start_session(None).await?;
+    session.start_transaction(None).await?;
+
+    let changed = match project_star_in_session(
+        db,
+        &mut session,
+        user_id,
+        project_id,
+        star,
+    )
+    .await
+    {
+        Ok(changed) => changed,
+        Err(error) => {
+            session.abort_transaction().await?;
+            return Err(error);
+        }
+    };
+
+    commit_transaction(&mut session).await?;
+
+    Ok(changed)
+}
+
+async fn project_star_in_session(
+    db: &Database,
+    session: &mut ClientSession,
+    user_id: ObjectId,
+    project_id: ObjectId,
+    star: bool,
+) -> GqlResult<bool> {
+    let coll_stars = db.collection::<Document>("project_stars");
+    let star_filter = doc! {"user_id": user_id, "project_id": project_id};
+
+    let (changed, stars_filter, stars_inc) = if star {
+        let star_res = coll_stars
+            .update_one_with_session(
+                star_filter,
+                doc! {"$setOnInsert": {"created_at": DateTime::now()}},
+                UpdateOptions::builder().upsert(true).build(),
+                session,
+            )
+            .await?;
+
+        (star_res.upserted_id.is_some(), doc! {"_id": project_id}, 1)
+    } else {
+        let unstar_res = coll_stars
+            .delete_one_with_session(star_filter, None, session)
+            .await?;
+
+        (
+            unstar_res.deleted_count > 0,
+            doc! {"_id": project_id, "stars": {"$gt": 0}},
+            -1,
+        )
+    };
+
+    if changed {
+        let coll = db.collection::<Document>("projects");
+        coll.update_one_with_session(
+            stars_filter,
+            doc! {"$inc": {"stars": stars_inc}},
+            None,
+            session,
+        )
+        .await?;
+    }
+
+    Ok(changed)
+}
+
+// check if the project is starred by the user of token
+pub async fn project_starred_by_token(
+    db: &Database,
+    cfg: &Config,
+    project_id: ObjectId,
+    token: String,
+) -> GqlResult<bool> {
+    if let Ok(user) = users::services::user_by_token(db, cfg, token).await {
+        let coll_stars = db.collection::<Document>("project_stars");
+        let star_document = coll_stars
+            .find_one(
+                doc! {"user_id": user._id, "project_id": project_id},
+                None,
+            )
+            .await?;
+
+        Ok(star_document.is_some())
+    } else {
+        Ok(false)
+    }
+}
+
+// Get all projects starred by user_id
+pub async fn projects_starred_by_user_id(
+    db: &Database,
+    cfg: &Config,
+    user_id: ObjectId,
+    from_page: u32,
+    first_oid: String,
+    last_oid: String,
+) -> GqlResult<ProjectsResult> {
+    let projects_stars = projects_stars_by_user_id(db, user_id).await?;
+
+    let mut project_ids = vec![];
+    for project_star in projects_stars {
+        project_ids.push(project_star.project_id);
+    }
+
+    let coll = db.collection::<Document>("projects");
+
+    let mut filter_doc = doc! {"_id": {"$in": project_ids}};
+    filter_status(Some(ProjectStatus::Published), &mut filter_doc).await;
+
+    let (pages_count, total_count) = count_pages_and_total(
+        &coll,
+        Some(filter_doc.clone()),
+        None,
+        cfg.page_size,
+    )
+    .await?;
+    let (current_page, skip_x) = calculate_current_filter_skip(
+        from_page,
+        first_oid,
+        last_oid,
+        &mut filter_doc,
+    )
+    .await?;
+
+    let sort_doc = doc! {"_id": -1};
+    let find_options =
+        find_options(Some(sort_doc), skip_x, cfg.page_size).await;
+
+    let mut cursor = coll.find(filter_doc, find_options).await?;
+
+    let mut projects: Vec<Project> = vec![];
+    while let Some(result) = cursor.next().await {
+        match result {
+            Ok(document) => {
+                let project = from_document(document)?;
+                projects.push(project);
+            }
+            Err(error) => {
+                println!("\n\n\n{}\n\n\n", error);
+            }
+        }
+    }
+
+    let projects_result = ProjectsResult {
+        page_info: PageInfo {
+            current_stuff: Some(String::from(PROJECTS_STUFF)),
+            current_page: Some(current_page),
+            first_cursor: match projects.first() {
+                Some(user) => Some(user._id),
+                _ => None,
+            },
+            last_cursor: match projects.last() {
+                Some(user) => Some(user._id),
+                _ => None,
+            },
+            has_previous_page: current_page > 1,
+            has_next_page: current_page < pages_count,
+        },
+        res_count: ResCount {
+            pages_count: Some(pages_count),
+            total_count: Some(total_count),
+        },
+        current_items: projects,
+    };
+
+    Ok(projects_result)
+}
+
+// get all ProjectStar list by user_id
+async fn projects_stars_by_user_id(
+    db: &Database,
+    user_id: ObjectId,
+) -> GqlResult<Vec<ProjectStar>> {
+    let coll_stars = db.collection::<Document>("project_stars");
+    let mut cursor_stars =
+        coll_stars.find(doc! {"user_id": user_id}, None).await?;
+
+    let mut projects_stars: Vec<ProjectStar> = vec![];
+    while let Some(result) = cursor_stars.next().await {
+        match result {
+            Ok(document) => {
+                let project_star: ProjectStar = from_document(document)?;
+                projects_stars.push(project_star);
+            }
+            Err(error) => {
+                println!("\n\n\n{}\n\n\n", error);
+            }
+        }
+    }
+
+    Ok(projects_stars)
+}
+
+// get project by id if it's published and not in trash
+pub async fn project_public_by_id(
+    db: &Database,
+    project_id: ObjectId,
+) -> GqlResult<Option<Project>> {
+    let coll = db.collection::<Document>("projects");
+
+    let project_document = coll
+        .find_one(
+            doc! {
+                "_id": project_id,
+                "status": {"$gte": ProjectStatus::Published}
+            },
+            None,
+        )
+        .await?;
+
+    match project_document {
+        Some(document) => Ok(Some(from_document(document)?)),
+        None => Ok(None),
+    }
+}
+
 // get random project
 pub async fn project_random_id(db: &Database) -> GqlResult<ObjectId> {
     let coll = db.collection::<Document>("projects");
@@ -111,7 +1106,7 @@ pub async fn project_random_id(db: &Database) -> GqlResult<ObjectId> {
     let now = DateTime::now();
     let days_before = now.to_chrono() + Duration::days(-7);
     let filter_doc = doc! {
-        "status": {"$gte": 1},
+        "status": {"$gte": ProjectStatus::Published},
         "updated_at": {"$gte": days_before}
     };
 
@@ -124,34 +1119,41 @@ pub async fn project_random_id(db: &Database) -> GqlResult<ObjectId> {
         let project: Project = from_document(document_res?)?;
         Ok(project._id)
     } else {
-        Err(Error::new("查询未成功"))
+        Err(AppError::ProjectNotFound.extend())
     }
 }
 
 pub async fn projects(
     db: &Database,
+    cfg: &Config,
     from_page: u32,
     first_oid: String,
     last_oid: String,
-    status: i8,
+    status: Option<ProjectStatus>,
 ) -> GqlResult<ProjectsResult> {
     let coll = db.collection::<Document>("projects");
 
     let mut filter_doc = doc! {};
     filter_status(status, &mut filter_doc).await;
 
-    let (pages_count, total_count) =
-        count_pages_and_total(&coll, Some(filter_doc.clone()), None).await;
+    let (pages_count, total_count) = count_pages_and_total(
+        &coll,
+        Some(filter_doc.clone()),
+        None,
+        cfg.page_size,
+    )
+    .await?;
     let (current_page, skip_x) = calculate_current_filter_skip(
         from_page,
         first_oid,
         last_oid,
         &mut filter_doc,
     )
-    .await;
+    .await?;
 
     let sort_doc = doc! {"_id": -1};
-    let find_options = find_options(Some(sort_doc), skip_x).await;
+    let find_options =
+        find_options(Some(sort_doc), skip_x, cfg.page_size).await;
 
     let mut cursor = coll.find(filter_doc, find_options).await?;
 
@@ -193,11 +1195,20 @@ pub async fn projects(
     Ok(projects_result)
 }
 
-async fn filter_status(status: i8, filter_doc: &mut Document) {
-    if status > 0 {
-        filter_doc.insert("status", doc! {"$gte": status as i32});
-    } else if status < 0 {
-        filter_doc.insert("status", status as i32);
+// Published and the statuses above it match the status or above, the others
+// match exactly, and no status matches all projects.
+pub async fn filter_status(
+    status: Option<ProjectStatus>,
+    filter_doc: &mut Document,
+) {
+    match status {
+        Some(status) if status >= ProjectStatus::Published => {
+            filter_doc.insert("status", doc! {"$gte": status});
+        }
+        Some(status) => {
+            filter_doc.insert("status", status);
+        }
+        None => {}
     }
 }
 
@@ -216,9 +1227,13 @@ pub async fn projects_in_position(
     }
 
     match position.trim() {
-        // "managed" => filter_doc.insert("status", doc! {"$gte": 6}),
-        "recommended" => filter_doc.insert("status", doc! {"$gte": 2}),
-        "published" => filter_doc.insert("status", doc! {"$gte": 1}),
+        // "managed" => filter_doc
+        //     .insert("status", doc! {"$gte": ProjectStatus::Controlled}),
+        "recommended" => filter_doc
+            .insert("status", doc! {"$gte": ProjectStatus::Recommended}),
+        "published" => {
+            filter_doc.insert("status", doc! {"$gte": ProjectStatus::Published})
+        }
         _ => None,
     };
 
@@ -245,29 +1260,36 @@ pub async fn projects_in_position(
 
 pub async fn projects_by_user_id(
     db: &Database,
+    cfg: &Config,
     user_id: ObjectId,
     from_page: u32,
     first_oid: String,
     last_oid: String,
-    status: i8,
+    status: Option<ProjectStatus>,
 ) -> GqlResult<ProjectsResult> {
     let coll = db.collection::<Document>("projects");
 
     let mut filter_doc = doc! {"user_id": user_id};
     filter_status(status, &mut filter_doc).await;
 
-    let (pages_count, total_count) =
-        count_pages_and_total(&coll, Some(filter_doc.clone()), None).await;
+    let (pages_count, total_count) = count_pages_and_total(
+        &coll,
+        Some(filter_doc.clone()),
+        None,
+        cfg.page_size,
+    )
+    .await?;
     let (current_page, skip_x) = calculate_current_filter_skip(
         from_page,
         first_oid,
         last_oid,
         &mut filter_doc,
     )
-    .await;
+    .await?;
 
     let sort_doc = doc! {"_id": -1};
-    let find_options = find_options(Some(sort_doc), skip_x).await;
+    let find_options =
+        find_options(Some(sort_doc), skip_x, cfg.page_size).await;
 
     let mut cursor = coll.find(filter_doc, find_options).await?;
 
@@ -311,43 +1333,56 @@ pub async fn projects_by_user_id(
 
 pub async fn projects_by_username(
     db: &Database,
+    cfg: &Config,
     username: String,
     from_page: u32,
     first_oid: String,
     last_oid: String,
-    status: i8,
+    status: Option<ProjectStatus>,
 ) -> GqlResult<ProjectsResult> {
     let user = users::services::user_by_username(db, username).await?;
-    projects_by_user_id(db, user._id, from_page, first_oid, last_oid, status)
-        .await
+    projects_by_user_id(
+        db, cfg, user._id, from_page, first_oid, last_oid, status,
+    )
+    .await
 }
 
 // Get all projects by category_id
 pub async fn projects_by_category_id(
     db: &Database,
+    cfg: &Config,
     category_id: ObjectId,
     from_page: u32,
     first_oid: String,
     last_oid: String,
-    status: i8,
+    status: Option<ProjectStatus>,
 ) -> GqlResult<ProjectsResult> {
     let coll = db.collection::<Document>("projects");
 
-    let mut filter_doc = doc! {"category_id": category_id};
+    // projects of the descendant categories are included too
+    let category_ids =
+        categories::services::category_descendant_ids(db, category_id).await?;
+    let mut filter_doc = doc! {"category_id": {"$in": category_ids}};
     filter_status(status, &mut filter_doc).await;
 
-    let (pages_count, total_count) =
-        count_pages_and_total(&coll, Some(filter_doc.clone()), None).await;
+    let (pages_count, total_count) = count_pages_and_total(
+        &coll,
+        Some(filter_doc.clone()),
+        None,
+        cfg.page_size,
+    )
+    .await?;
     let (current_page, skip_x) = calculate_current_filter_skip(
         from_page,
         first_oid,
         last_oid,
         &mut filter_doc,
     )
-    .await;
+    .await?;
 
     let sort_doc = doc! {"_id": -1};
-    let find_options = find_options(Some(sort_doc), skip_x).await;
+    let find_options =
+        find_options(Some(sort_doc), skip_x, cfg.page_size).await;
 
     let mut cursor = coll.find(filter_doc, find_options).await?;
 
@@ -392,16 +1427,18 @@ pub async fn projects_by_category_id(
 // Get all projects by category_slug
 pub async fn projects_by_category_slug(
     db: &Database,
+    cfg: &Config,
     category_slug: String,
     from_page: u32,
     first_oid: String,
     last_oid: String,
-    status: i8,
+    status: Option<ProjectStatus>,
 ) -> GqlResult<ProjectsResult> {
     let category =
         categories::services::category_by_slug(db, category_slug).await?;
     projects_by_category_id(
         db,
+        cfg,
         category._id,
         from_page,
         first_oid,
@@ -414,13 +1451,14 @@ pub async fn projects_by_category_slug(
 // Get all projects by topic_id
 pub async fn projects_by_topic_id(
     db: &Database,
+    cfg: &Config,
     topic_id: ObjectId,
     from_page: u32,
     first_oid: String,
     last_oid: String,
-    status: i8,
+    status: Option<ProjectStatus>,
 ) -> GqlResult<ProjectsResult> {
-    let topics_projects = topics_projects_by_topic_id(db, topic_id).await;
+    let topics_projects = topics_projects_by_topic_id(db, topic_id).await?;
 
     let mut project_ids = vec![];
     for topic_project in topics_projects {
@@ -434,18 +1472,24 @@ pub async fn projects_by_topic_id(
     let mut filter_doc = doc! {"_id": {"$in": project_ids}};
     filter_status(status, &mut filter_doc).await;
 
-    let (pages_count, total_count) =
-        count_pages_and_total(&coll, Some(filter_doc.clone()), None).await;
+    let (pages_count, total_count) = count_pages_and_total(
+        &coll,
+        Some(filter_doc.clone()),
+        None,
+        cfg.page_size,
+    )
+    .await?;
     let (current_page, skip_x) = calculate_current_filter_skip(
         from_page,
         first_oid,
         last_oid,
         &mut filter_doc,
     )
-    .await;
+    .await?;
 
     let sort_doc = doc! {"_id": -1};
-    let find_options = find_options(Some(sort_doc), skip_x).await;
+    let find_options =
+        find_options(Some(sort_doc), skip_x, cfg.page_size).await;
 
     let mut cursor = coll.find(filter_doc, find_options).await?;
 
@@ -490,22 +1534,25 @@ pub async fn projects_by_topic_id(
 // Get all projects by topic_slug
 pub async fn projects_by_topic_slug(
     db: &Database,
+    cfg: &Config,
     topic_slug: String,
     from_page: u32,
     first_oid: String,
     last_oid: String,
-    status: i8,
+    status: Option<ProjectStatus>,
 ) -> GqlResult<ProjectsResult> {
     let topic = topics::services::topic_by_slug(db, topic_slug).await?;
-    projects_by_topic_id(db, topic._id, from_page, first_oid, last_oid, status)
-        .await
+    projects_by_topic_id(
+        db, cfg, topic._id, from_page, first_oid, last_oid, status,
+    )
+    .await
 }
 
 // get all TopicProject list by topic_id
 async fn topics_projects_by_topic_id(
     db: &Database,
     topic_id: ObjectId,
-) -> Vec<TopicProject> {
+) -> GqlResult<Vec<TopicProject>> {
     let coll_topics_projects =
         db.collection::<Document>("topics_users_projects");
     let mut cursor_topics_projects = coll_topics_projects
@@ -516,15 +1563,13 @@ async fn topics_projects_by_topic_id(
             },
             None,
         )
-        .await
-        .unwrap();
+        .await?;
 
     let mut topics_projects: Vec<TopicProject> = vec![];
     while let Some(result) = cursor_topics_projects.next().await {
         match result {
             Ok(document) => {
-                let topic_project: TopicProject =
-                    from_document(document).unwrap();
+                let topic_project: TopicProject = from_document(document)?;
                 topics_projects.push(topic_project);
             }
             Err(error) => {
@@ -533,17 +1578,37 @@ async fn topics_projects_by_topic_id(
         }
     }
 
-    topics_projects
+    Ok(topics_projects)
 }
 
-// Create new file
-pub async fn file_new(db: &Database, file_new: FileNew) -> GqlResult<File> {
+// Create new file, a retry with the same idempotency key gets the file
+// created at first
+pub async fn file_new(
+    db: &Database,
+    file_new: FileNew,
+    idempotency_key: Option<String>,
+) -> GqlResult<File> {
+    if let Some(file_id) =
+        idempotency_key_check(db, "files", None, &idempotency_key, &file_new)
+            .await?
+    {
+        return file_by_id(db, file_id).await;
+    }
+
+    let file_res = file_insert(db, file_new).await;
+    let file_id = file_res.as_ref().ok().map(|file| file._id);
+    idempotency_key_settle(db, "files", None, &idempotency_key, file_id)
+        .await?;
+
+    file_res
+}
+
+async fn file_insert(db: &Database, file_new: FileNew) -> GqlResult<File> {
     let coll = db.collection::<Document>("files");
 
     let new_document = to_document(&file_new)?;
 
-    let file_res =
-        coll.insert_one(new_document, None).await.expect("写入未成功");
+    let file_res = coll.insert_one(new_document, None).await?;
     let file_id = from_bson(file_res.inserted_id)?;
 
     file_by_id(db, file_id).await
@@ -553,14 +1618,12 @@ pub async fn file_new(db: &Database, file_new: FileNew) -> GqlResult<File> {
 pub async fn file_by_id(db: &Database, id: ObjectId) -> GqlResult<File> {
     let coll = db.collection::<Document>("files");
 
-    let file_document = coll
-        .find_one(doc! {"_id": id}, None)
-        .await
-        .expect("查询未成功")
-        .unwrap();
+    let file_document = coll.find_one(doc! {"_id": id}, None).await?;
 
-    let file: File = from_document(file_document)?;
-    Ok(file)
+    match file_document {
+        Some(document) => Ok(from_document(document)?),
+        None => Err(AppError::FileNotFound.extend()),
+    }
 }
 
 // Create new project_file
@@ -581,13 +1644,12 @@ pub async fn project_file_new(
         .await?;
     if exist_document.is_none() {
         let new_document = to_document(&project_file_new)?;
-        let project_file_res =
-            coll.insert_one(new_document, None).await.expect("写入未成功");
+        let project_file_res = coll.insert_one(new_document, None).await?;
         let project_file_id = from_bson(project_file_res.inserted_id)?;
 
         project_file_by_id(db, project_file_id).await
     } else {
-        Err(Error::new("记录已存在"))
+        Err(AppError::RecordExists.extend())
     }
 }
 
@@ -598,14 +1660,12 @@ async fn project_file_by_id(
 ) -> GqlResult<ProjectFile> {
     let coll = db.collection::<Document>("projects_files");
 
-    let project_file_document = coll
-        .find_one(doc! {"_id": id}, None)
-        .await
-        .expect("查询未成功")
-        .unwrap();
+    let project_file_document = coll.find_one(doc! {"_id": id}, None).await?;
 
-    let project_file: ProjectFile = from_document(project_file_document)?;
-    Ok(project_file)
+    match project_file_document {
+        Some(document) => Ok(from_document(document)?),
+        None => Err(AppError::RecordNotFound.extend()),
+    }
 }
 
 // get all files of one project by project_id
@@ -613,7 +1673,7 @@ pub async fn files_by_project_id(
     db: &Database,
     project_id: ObjectId,
 ) -> GqlResult<Vec<File>> {
-    let projects_files = projects_files_by_project_id(db, project_id).await;
+    let projects_files = projects_files_by_project_id(db, project_id).await?;
 
     let mut file_ids = vec![];
     for project_file in projects_files {
@@ -644,42 +1704,40 @@ pub async fn files_by_project_id(
 // get file of one project by file's kind & project_id
 pub async fn file_by_kind_project_id(
     db: &Database,
-    file_kind: i8,
+    file_kind: FileKind,
     project_id: ObjectId,
 ) -> GqlResult<File> {
-    let projects_files = projects_files_by_project_id(db, project_id).await;
+    let projects_files = projects_files_by_project_id(db, project_id).await?;
 
     let mut file_ids = vec![];
     for project_file in projects_files {
         file_ids.push(project_file.file_id);
     }
-    let filter_doc = doc! {"_id": {"$in": file_ids}, "kind": file_kind as i32};
+    let filter_doc = doc! {"_id": {"$in": file_ids}, "kind": file_kind};
 
     let coll = db.collection::<Document>("files");
-    let file_document =
-        coll.find_one(filter_doc, None).await.expect("账户不存在").unwrap();
+    let file_document = coll.find_one(filter_doc, None).await?;
 
-    let file: File = from_document(file_document)?;
-    Ok(file)
+    match file_document {
+        Some(document) => Ok(from_document(document)?),
+        None => Err(AppError::FileNotFound.extend()),
+    }
 }
 
 // get all ProjectFile by project_id
-async fn projects_files_by_project_id(
+pub async fn projects_files_by_project_id(
     db: &Database,
     project_id: ObjectId,
-) -> Vec<ProjectFile> {
+) -> GqlResult<Vec<ProjectFile>> {
     let coll_projects_files = db.collection::<Document>("projects_files");
-    let mut cursor_projects_files = coll_projects_files
-        .find(doc! {"project_id": project_id}, None)
-        .await
-        .unwrap();
+    let mut cursor_projects_files =
+        coll_projects_files.find(doc! {"project_id": project_id}, None).await?;
 
     let mut projects_files: Vec<ProjectFile> = vec![];
     while let Some(result) = cursor_projects_files.next().await {
         match result {
             Ok(document) => {
-                let project_file: ProjectFile =
-                    from_document(document).unwrap();
+                let project_file: ProjectFile = from_document(document)?;
                 projects_files.push(project_file);
             }
             Err(error) => {
@@ -688,5 +1746,5 @@ async fn projects_files_by_project_id(
         }
     }
 
-    projects_files
+    Ok(projects_files)
 }
@@ -0,0 +1,8 @@
+use crate::projects::models::Project;
+
+// one project of a remix tree, depth 0 is the original work
+#[derive(async_graphql::SimpleObject, Clone, Debug)]
+pub struct RemixNode {
+    pub depth: u32,
+    pub project: Project,
+}
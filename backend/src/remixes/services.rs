@@ -0,0 +1,278 @@
+use futures::stream::StreamExt;
+use mongodb::{
+    Database,
+    bson::{
+        oid::ObjectId, DateTime, Document, doc, from_document, to_document,
+        from_bson,
+    },
+    options::FindOptions,
+};
+use async_graphql::ErrorExtensions;
+
+use crate::util::{
+    config::Config,
+    constant::GqlResult,
+    error::AppError,
+    common::bson_dt_nyr,
+    pagination::{
+        ProjectsResult, PageInfo, ResCount, count_pages_and_total,
+        calculate_current_filter_skip, find_options,
+    },
+};
+
+use crate::users;
+use crate::slugs::services::slug_unique;
+use crate::notifications::{
+    self,
+    models::{NotificationKind, NotificationNew},
+};
+use crate::projects::{
+    models::{Project, ProjectNew, ProjectFileNew, ProjectStatus},
+    services::{
+        PROJECTS_STUFF, project_by_id, project_public_by_id, project_file_new,
+        projects_files_by_project_id, filter_status,
+    },
+};
+use super::models::RemixNode;
+
+// remix a published project by the signed-in user: the remix shares the
+// cover & source files of the original, and credits it by remix_of.
+pub async fn project_remix(
+    db: &Database,
+    cfg: &Config,
+    project_id: ObjectId,
+    token: String,
+) -> GqlResult<Project> {
+    let user = users::services::user_by_token(db, cfg, token).await?;
+    let project = project_by_id(db, project_id).await?;
+
+    if project.status < ProjectStatus::Published {
+        return Err(AppError::ProjectUnpublished.extend());
+    }
+    if project.user_id == user._id {
+        return Err(AppError::RemixOwnProject.extend());
+    }
+
+    let coll = db.collection::<Document>("projects");
+    let exist_document = coll
+        .find_one(
+            doc! {
+                "user_id": user._id,
+                "remix_of": project_id,
+                "deleted_at": null,
+            },
+            None,
+        )
+        .await?;
+    if let Some(exist_document) = exist_document {
+        let remix: Project = from_document(exist_document)?;
+        return Err(AppError::RemixExists {
+            created_at: bson_dt_nyr(remix.created_at, cfg.tz()).await,
+        }
+        .extend());
+    }
+
+    let mut project_new = ProjectNew {
+        user_id: user._id,
+        category_id: project.category_id,
+        subject: project.subject.clone(),
+        slug: String::from(""),
+        content: project.content.clone(),
+        contact_user: user.nickname.clone(),
+        contact_phone: user.phone_number.clone(),
+        contact_email: user.email.clone(),
+        contact_im: user.im_account.clone(),
+        hits: 0,
+        insides: 0,
+        stars: 0,
+        language: project.language.clone(),
+        status: ProjectStatus::Pending,
+    };
+    project_new.slug =
+        slug_unique(db, "projects", &project_new.subject, None).await?;
+
+    let now = DateTime::now();
+    let mut new_document = to_document(&project_new)?;
+    new_document.insert("remix_of", project_id);
+    new_document.insert("created_at", now);
+    new_document.insert("updated_at", now);
+
+    let remix_res = coll.insert_one(new_document, None).await?;
+    let remix_id: ObjectId = from_bson(remix_res.inserted_id)?;
+
+    for project_file in projects_files_by_project_id(db, project_id).await? {
+        let project_file_new = ProjectFileNew {
+            user_id: user._id,
+            project_id: remix_id,
+            file_id: project_file.file_id,
+        };
+        project_file_new(db, project_file_new).await?;
+    }
+
+    let notification_new = NotificationNew {
+        user_id: project.user_id,
+        actor_id: Some(user._id),
+        kind: NotificationKind::ProjectRemixed,
+        project_id,
+        comment_id: None,
+        project_status: None,
+    };
+    notifications::services::notification_notify(db, notification_new).await;
+
+    project_by_id(db, remix_id).await
+}
+
+// count published remixes of one project
+pub async fn remixes_count_by_project_id(
+    db: &Database,
+    project_id: ObjectId,
+) -> GqlResult<u64> {
+    let coll = db.collection::<Document>("projects");
+
+    let remixes_count = coll
+        .count_documents(
+            doc! {
+                "remix_of": project_id,
+                "status": {"$gte": ProjectStatus::Published}
+            },
+            None,
+        )
+        .await?;
+
+    Ok(remixes_count)
+}
+
+// Get published remixes of one project
+pub async fn remixes_by_project_id(
+    db: &Database,
+    cfg: &Config,
+    project_id: ObjectId,
+    from_page: u32,
+    first_oid: String,
+    last_oid: String,
+) -> GqlResult<ProjectsResult> {
+    let coll = db.collection::<Document>("projects");
+
+    let mut filter_doc = doc! {"remix_of": project_id};
+    filter_status(Some(ProjectStatus::Published), &mut filter_doc).await;
+
+    let (pages_count, total_count) = count_pages_and_total(
+        &coll,
+        Some(filter_doc.clone()),
+        None,
+        cfg.page_size,
+    )
+    .await?;
+    let (current_page, skip_x) = calculate_current_filter_skip(
+        from_page,
+        first_oid,
+        last_oid,
+        &mut filter_doc,
+    )
+    .await?;
+
+    let sort_doc = doc! {"_id": -1};
+    let find_options =
+        find_options(Some(sort_doc), skip_x, cfg.page_size).await;
+
+    let mut cursor = coll.find(filter_doc, find_options).await?;
+
+    let mut projects: Vec<Project> = vec![];
+    while let Some(result) = cursor.next().await {
+        match result {
+            Ok(document) => {
+                let project = from_document(document)?;
+                projects.push(project);
+            }
+            Err(error) => {
+                println!("\n\n\n{}\n\n\n", error);
+            }
+        }
+    }
+
+    let projects_result = ProjectsResult {
+        page_info: PageInfo {
+            current_stuff: Some(String::from(PROJECTS_STUFF)),
+            current_page: Some(current_page),
+            first_cursor: match projects.first() {
+                Some(project) => Some(project._id),
+                _ => None,
+            },
+            last_cursor: match projects.last() {
+                Some(project) => Some(project._id),
+                _ => None,
+            },
+            has_previous_page: current_page > 1,
+            has_next_page: current_page < pages_count,
+        },
+        res_count: ResCount {
+            pages_count: Some(pages_count),
+            total_count: Some(total_count),
+        },
+        current_items: projects,
+    };
+
+    Ok(projects_result)
+}
+
+// get the whole remix tree of one published project: walk up to the original
+// work, then list its published descendants depth-first. An unpublished
+// project is not found, as its tree is not public.
+pub async fn project_remix_tree(
+    db: &Database,
+    project_id: ObjectId,
+) -> GqlResult<Vec<RemixNode>> {
+    let mut root = match project_public_by_id(db, project_id).await? {
+        Some(project) => project,
+        None => return Err(AppError::ProjectNotFound.extend()),
+    };
+    let mut ancestor_ids = vec![root._id];
+    while let Some(remix_of) = root.remix_of {
+        // stop at unpublished ancestors, and never loop on broken lineage
+        if ancestor_ids.contains(&remix_of) {
+            break;
+        }
+        match project_public_by_id(db, remix_of).await? {
+            Some(parent) => {
+                ancestor_ids.push(parent._id);
+                root = parent;
+            }
+            None => break,
+        }
+    }
+
+    let coll = db.collection::<Document>("projects");
+
+    let mut remix_tree: Vec<RemixNode> = vec![];
+    let mut stack = vec![RemixNode { depth: 0, project: root }];
+    while let Some(node) = stack.pop() {
+        let filter_doc = doc! {
+            "remix_of": node.project._id,
+            "status": {"$gte": ProjectStatus::Published},
+        };
+        let find_options =
+            FindOptions::builder().sort(doc! {"_id": -1}).build();
+        let mut cursor = coll.find(filter_doc, find_options).await?;
+
+        while let Some(result) = cursor.next().await {
+            match result {
+                Ok(document) => {
+                    let project: Project = from_document(document)?;
+                    let visited =
+                        remix_tree.iter().any(|n| n.project._id == project._id);
+                    if !visited {
+                        stack
+                            .push(RemixNode { depth: node.depth + 1, project });
+                    }
+                }
+                Err(error) => {
+                    println!("\n\n\n{}\n\n\n", error);
+                }
+            }
+        }
+
+        remix_tree.push(node);
+    }
+
+    Ok(remix_tree)
+}
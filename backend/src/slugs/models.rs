@@ -0,0 +1,21 @@
+use serde::{Serialize, Deserialize};
+use mongodb::bson::{oid::ObjectId, DateTime};
+
+// an old slug of a topic or category, kept for redirects after renaming
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SlugHistory {
+    pub _id: ObjectId,
+    // collection of the target, "topics" or "categories"
+    pub kind: String,
+    pub slug: String,
+    pub target_id: ObjectId,
+    pub created_at: DateTime,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SlugHistoryNew {
+    pub kind: String,
+    pub slug: String,
+    pub target_id: ObjectId,
+    pub created_at: DateTime,
+}
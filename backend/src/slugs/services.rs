@@ -0,0 +1,282 @@
+use futures::stream::StreamExt;
+use mongodb::{
+    Database, ClientSession,
+    bson::{
+        oid::ObjectId, Document, doc, from_document, to_document, DateTime,
+    },
+};
+
+use crate::util::{constant::GqlResult, common::slugify};
+
+use super::models::{SlugHistory, SlugHistoryNew};
+
+// Clean slug of the name, suffixed with -2, -3 ... only when it is taken by
+// another target of the kind, currently or in the history.
+pub async fn slug_unique(
+    db: &Database,
+    kind: &str,
+    name: &str,
+    target_id: Option<ObjectId>,
+) -> GqlResult<String> {
+    slug_unique_in(db, None, kind, name, target_id).await
+}
+
+// Same as `slug_unique`, but reads inside the transaction of the session, so
+// that the slugs inserted earlier in the same transaction are seen as taken.
+pub async fn slug_unique_with_session(
+    db: &Database,
+    session: &mut ClientSession,
+    kind: &str,
+    name: &str,
+    target_id: Option<ObjectId>,
+) -> GqlResult<String> {
+    slug_unique_in(db, Some(session), kind, name, target_id).await
+}
+
+async fn slug_unique_in(
+    db: &Database,
+    mut session: Option<&mut ClientSession>,
+    kind: &str,
+    name: &str,
+    target_id: Option<ObjectId>,
+) -> GqlResult<String> {
+    let mut slug_base = slugify(name).await.trim_matches('-').to_string();
+    if slug_base.is_empty() {
+        slug_base = String::from("n");
+    }
+
+    let mut slug = slug_base.clone();
+    let mut suffix = 1;
+    while slug_taken(db, &mut session, kind, &slug, target_id).await? {
+        suffix += 1;
+        slug = format!("{}-{}", slug_base, suffix);
+    }
+
+    Ok(slug)
+}
+
+async fn slug_taken(
+    db: &Database,
+    session: &mut Option<&mut ClientSession>,
+    kind: &str,
+    slug: &str,
+    target_id: Option<ObjectId>,
+) -> GqlResult<bool> {
+    let mut current_filter = doc! {"slug": slug};
+    let mut history_filter = doc! {"kind": kind, "slug": slug};
+    if let Some(target_id) = target_id {
+        current_filter.insert("_id", doc! {"$ne": target_id});
+        history_filter.insert("target_id", doc! {"$ne": target_id});
+    }
+
+    let coll = db.collection::<Document>(kind);
+    let coll_history = db.collection::<Document>("slugs_history");
+    match session {
+        Some(session) => {
+            if coll
+                .find_one_with_session(current_filter, None, session)
+                .await?
+                .is_some()
+            {
+                return Ok(true);
+            }
+
+            Ok(coll_history
+                .find_one_with_session(history_filter, None, session)
+                .await?
+                .is_some())
+        }
+        None => {
+            if coll.find_one(current_filter, None).await?.is_some() {
+                return Ok(true);
+            }
+
+            Ok(coll_history.find_one(history_filter, None).await?.is_some())
+        }
+    }
+}
+
+// Change slug of the target, and keep the old one in history
+pub async fn slug_change(
+    db: &Database,
+    kind: &str,
+    target_id: ObjectId,
+    old_slug: &str,
+    new_slug: &str,
+) -> GqlResult<()> {
+    slug_change_in(db, None, kind, target_id, old_slug, new_slug).await
+}
+
+// Same as `slug_change`, but writes inside the transaction of the session, so
+// that the history and the slug are changed together with the target.
+pub async fn slug_change_with_session(
+    db: &Database,
+    session: &mut ClientSession,
+    kind: &str,
+    target_id: ObjectId,
+    old_slug: &str,
+    new_slug: &str,
+) -> GqlResult<()> {
+    slug_change_in(db, Some(session), kind, target_id, old_slug, new_slug).await
+}
+
+async fn slug_change_in(
+    db: &Database,
+    session: Option<&mut ClientSession>,
+    kind: &str,
+    target_id: ObjectId,
+    old_slug: &str,
+    new_slug: &str,
+) -> GqlResult<()> {
+    if old_slug == new_slug {
+        return Ok(());
+    }
+
+    // renamed back to an old slug, which is current again
+    let coll_history = db.collection::<Document>("slugs_history");
+    let history_filter = doc! {"kind": kind, "slug": new_slug};
+    let slug_history_new = SlugHistoryNew {
+        kind: kind.to_string(),
+        slug: old_slug.to_string(),
+        target_id,
+        created_at: DateTime::now(),
+    };
+    let history_document = to_document(&slug_history_new)?;
+
+    let coll = db.collection::<Document>(kind);
+    let query_doc = doc! {"_id": target_id};
+    let update_doc = doc! {"$set": {"slug": new_slug}};
+    match session {
+        Some(session) => {
+            coll_history
+                .delete_many_with_session(history_filter, None, session)
+                .await?;
+            coll_history
+                .insert_one_with_session(history_document, None, session)
+                .await?;
+            coll.update_one_with_session(query_doc, update_doc, None, session)
+                .await?;
+        }
+        None => {
+            coll_history.delete_many(history_filter, None).await?;
+            coll_history.insert_one(history_document, None).await?;
+            coll.update_one(query_doc, update_doc, None).await?;
+        }
+    }
+
+    Ok(())
+}
+
+// Keep the slug in history of the target
+pub async fn slug_history_new(
+    db: &Database,
+    kind: &str,
+    slug: &str,
+    target_id: ObjectId,
+) -> GqlResult<()> {
+    let coll_history = db.collection::<Document>("slugs_history");
+
+    let slug_history_new = SlugHistoryNew {
+        kind: kind.to_string(),
+        slug: slug.to_string(),
+        target_id,
+        created_at: DateTime::now(),
+    };
+    coll_history.insert_one(to_document(&slug_history_new)?, None).await?;
+
+    Ok(())
+}
+
+// get id of the target whose old slug it is
+pub async fn slug_history_target_id(
+    db: &Database,
+    kind: &str,
+    slug: &str,
+) -> GqlResult<Option<ObjectId>> {
+    let coll_history = db.collection::<Document>("slugs_history");
+
+    let history_document = coll_history
+        .find_one(doc! {"kind": kind, "slug": slug.to_lowercase()}, None)
+        .await?;
+    match history_document {
+        Some(document) => {
+            let slug_history: SlugHistory = from_document(document)?;
+            Ok(Some(slug_history.target_id))
+        }
+        None => Ok(None),
+    }
+}
+
+// Point all old slugs of the target to another one, such as after merging
+pub async fn slugs_history_retarget(
+    db: &Database,
+    kind: &str,
+    target_id: ObjectId,
+    new_target_id: ObjectId,
+) -> GqlResult<u64> {
+    let coll_history = db.collection::<Document>("slugs_history");
+
+    let update_res = coll_history
+        .update_many(
+            doc! {"kind": kind, "target_id": target_id},
+            doc! {"$set": {"target_id": new_target_id}},
+            None,
+        )
+        .await?;
+
+    Ok(update_res.modified_count)
+}
+
+// Delete all old slugs of the target, such as after deleting
+pub async fn slugs_history_delete(
+    db: &Database,
+    kind: &str,
+    target_id: ObjectId,
+) -> GqlResult<u64> {
+    let coll_history = db.collection::<Document>("slugs_history");
+
+    let delete_res = coll_history
+        .delete_many(doc! {"kind": kind, "target_id": target_id}, None)
+        .await?;
+
+    Ok(delete_res.deleted_count)
+}
+
+// Replace the timestamped slugs of the kind with clean ones, old slugs are
+// kept in history. The name of a target is picked by `name_of`.
+pub async fn slugs_clean_migrate(
+    db: &Database,
+    kind: &str,
+    name_of: fn(&Document) -> Option<String>,
+) -> GqlResult<u64> {
+    let coll = db.collection::<Document>(kind);
+
+    let mut cursor =
+        coll.find(doc! {"slug": {"$regex": r"-[0-9]{13}$"}}, None).await?;
+
+    let mut targets: Vec<Document> = vec![];
+    while let Some(result) = cursor.next().await {
+        match result {
+            Ok(document) => targets.push(document),
+            Err(error) => {
+                println!("\n\n\n{}\n\n\n", error);
+            }
+        }
+    }
+
+    let mut migrated_count = 0;
+    for target in targets {
+        let target_id = target.get_object_id("_id")?;
+        let old_slug = target.get_str("slug")?;
+        let name = match name_of(&target) {
+            Some(name) => name,
+            None => continue,
+        };
+
+        let new_slug = slug_unique(db, kind, &name, Some(target_id)).await?;
+        slug_change(db, kind, target_id, old_slug, &new_slug).await?;
+        migrated_count += 1;
+    }
+
+    Ok(migrated_count)
+}
@@ -0,0 +1,155 @@
+use serde::{Serialize, Deserialize};
+use mongodb::bson::{oid::ObjectId, DateTime};
+
+use crate::util::{
+    config::Config,
+    constant::GqlResult,
+    datetime::{DateStyle, date_formatted, date_nyrsq},
+    common::md2html_safe,
+    pagination::ProjectsResult,
+};
+use crate::dbs::mongo::DataSource;
+
+use crate::{
+    projects::{self, models::File},
+    users::{self, models::User},
+};
+
+#[derive(async_graphql::SimpleObject, Serialize, Deserialize, Clone, Debug)]
+#[graphql(complex)]
+pub struct Studio {
+    pub _id: ObjectId,
+    pub user_id: ObjectId,
+    pub name: String,
+    pub slug: String,
+    pub description: String,
+    #[serde(default)]
+    pub cover_image_id: Option<ObjectId>,
+    pub created_at: DateTime,
+    pub updated_at: DateTime,
+}
+
+#[async_graphql::ComplexObject]
+impl Studio {
+    pub async fn owner(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+    ) -> GqlResult<User> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        users::services::user_by_id(db, self.user_id).await
+    }
+
+    pub async fn cover_image(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+    ) -> GqlResult<Option<File>> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        match self.cover_image_id {
+            Some(cover_image_id) => Ok(Some(
+                projects::services::file_by_id(db, cover_image_id).await?,
+            )),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn description_html(&self) -> String {
+        md2html_safe(&self.description).await
+    }
+
+    pub async fn curators(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+    ) -> GqlResult<Vec<User>> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        super::services::curators_by_studio_id(db, self._id).await
+    }
+
+    pub async fn projects_count(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+    ) -> GqlResult<u64> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        super::services::projects_count_by_studio_id(db, self._id).await
+    }
+
+    pub async fn projects(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        from_page: u32,
+        first_oid: String,
+        last_oid: String,
+    ) -> GqlResult<ProjectsResult> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
+        super::services::projects_by_studio_id(
+            db, cfg, self._id, from_page, first_oid, last_oid,
+        )
+        .await
+    }
+
+    // the owner or an accepted curator could manage projects of studio
+    pub async fn curated_by_me(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        token: String,
+    ) -> GqlResult<bool> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
+        super::services::studio_curated_by_token(db, cfg, self, token).await
+    }
+
+    // a curator invitation of the user is pending
+    pub async fn invited_me(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        token: String,
+    ) -> GqlResult<bool> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
+        super::services::studio_invited_by_token(db, cfg, self._id, token).await
+    }
+
+    pub async fn created_at_nyrsq(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+    ) -> String {
+        date_nyrsq(ctx, self.created_at)
+    }
+
+    pub async fn created_at_formatted(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        tz: Option<String>,
+        locale: Option<String>,
+        #[graphql(default)] style: DateStyle,
+    ) -> GqlResult<String> {
+        date_formatted(ctx, self.created_at, tz, locale, style)
+    }
+}
+
+#[derive(async_graphql::InputObject, Serialize, Deserialize)]
+pub struct StudioNew {
+    pub name: String,
+    pub description: String,
+    pub cover_image_id: Option<ObjectId>,
+}
+
+#[derive(async_graphql::SimpleObject, Serialize, Deserialize, Clone, Debug)]
+pub struct StudioCurator {
+    pub _id: ObjectId,
+    pub studio_id: ObjectId,
+    pub user_id: ObjectId,
+    pub inviter_id: ObjectId,
+    pub accepted: bool,
+    pub created_at: DateTime,
+}
+
+#[derive(async_graphql::SimpleObject, Serialize, Deserialize, Clone, Debug)]
+pub struct StudioProject {
+    pub _id: ObjectId,
+    pub studio_id: ObjectId,
+    pub project_id: ObjectId,
+    pub user_id: ObjectId,
+    pub position: i64,
+    pub created_at: DateTime,
+}
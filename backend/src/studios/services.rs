@@ -0,0 +1,678 @@
+use futures::stream::StreamExt;
+use mongodb::{
+    Database,
+    bson::{
+        oid::ObjectId, DateTime, Document, doc, from_document, to_document,
+        from_bson,
+    },
+    options::{FindOptions, UpdateOptions},
+};
+use async_graphql::ErrorExtensions;
+
+use crate::util::{
+    config::Config,
+    constant::{GqlResult},
+    error::AppError,
+    pagination::{
+        StudiosResult, ProjectsResult, PageInfo, ResCount,
+        count_pages_and_total, calculate_current_filter_skip, find_options,
+    },
+};
+
+use crate::users::{self, models::User};
+use crate::slugs::services::{
+    slug_unique, slug_history_target_id, slugs_clean_migrate,
+};
+use crate::projects::{
+    self,
+    models::{Project, ProjectStatus},
+};
+use super::models::{Studio, StudioNew, StudioCurator, StudioProject};
+
+const STUDIOS_STUFF: &str = "studios";
+const PROJECTS_STUFF: &str = "projects";
+
+// Create new studio, owned by the signed-in user
+pub async fn studio_new(
+    db: &Database,
+    cfg: &Config,
+    mut studio_new: StudioNew,
+    token: String,
+) -> GqlResult<Studio> {
+    let user = users::services::user_by_token(db, cfg, token).await?;
+
+    studio_new.name = String::from(studio_new.name.trim());
+    if "".eq(&studio_new.name) || "-".eq(&studio_new.name) {
+        return Err(AppError::NameInvalid.extend());
+    }
+
+    let coll = db.collection::<Document>("studios");
+
+    let exist_document = coll
+        .find_one(doc! {"user_id": user._id, "name": &studio_new.name}, None)
+        .await?;
+    if exist_document.is_some() {
+        return Err(AppError::StudioExists.extend());
+    }
+
+    let now = DateTime::now();
+    let slug = slug_unique(db, "studios", &studio_new.name, None).await?;
+
+    let mut new_document = to_document(&studio_new)?;
+    new_document.insert("user_id", user._id);
+    new_document.insert("slug", slug);
+    new_document.insert("created_at", now);
+    new_document.insert("updated_at", now);
+
+    let studio_res = coll.insert_one(new_document, None).await?;
+    let studio_id = from_bson(studio_res.inserted_id)?;
+
+    studio_by_id(db, studio_id).await
+}
+
+// update name, description & cover of studio, by its owner
+pub async fn studio_update(
+    db: &Database,
+    cfg: &Config,
+    studio_id: ObjectId,
+    mut studio_new: StudioNew,
+    token: String,
+) -> GqlResult<Studio> {
+    let user = users::services::user_by_token(db, cfg, token).await?;
+    let studio = studio_by_id(db, studio_id).await?;
+
+    if studio.user_id != user._id {
+        return Err(AppError::NoPermission.extend());
+    }
+
+    studio_new.name = String::from(studio_new.name.trim());
+    if "".eq(&studio_new.name) || "-".eq(&studio_new.name) {
+        return Err(AppError::NameInvalid.extend());
+    }
+
+    let mut set_doc = to_document(&studio_new)?;
+    if studio_new.cover_image_id.is_none() {
+        // keep the current cover if no new one is uploaded
+        set_doc.remove("cover_image_id");
+    }
+    set_doc.insert("updated_at", DateTime::now());
+
+    let coll = db.collection::<Document>("studios");
+    coll.update_one(doc! {"_id": studio_id}, doc! {"$set": set_doc}, None)
+        .await?;
+
+    studio_by_id(db, studio_id).await
+}
+
+// get studio info by id
+pub async fn studio_by_id(db: &Database, id: ObjectId) -> GqlResult<Studio> {
+    let coll = db.collection::<Document>("studios");
+
+    let studio_document = coll.find_one(doc! {"_id": id}, None).await?;
+
+    match studio_document {
+        Some(document) => Ok(from_document(document)?),
+        None => Err(AppError::StudioNotFound.extend()),
+    }
+}
+
+// get studio info by slug, or by an old timestamped slug
+pub async fn studio_by_slug(db: &Database, slug: String) -> GqlResult<Studio> {
+    let coll = db.collection::<Document>("studios");
+
+    let studio_document =
+        coll.find_one(doc! {"slug": slug.to_lowercase()}, None).await?;
+
+    match studio_document {
+        Some(document) => Ok(from_document(document)?),
+        None => match slug_history_target_id(db, "studios", &slug).await? {
+            Some(studio_id) => studio_by_id(db, studio_id).await,
+            None => Err(AppError::StudioNotFound.extend()),
+        },
+    }
+}
+
+// replace the timestamped slugs of studios with clean ones
+pub async fn studios_slugs_migrate(db: &Database) -> GqlResult<u64> {
+    slugs_clean_migrate(db, "studios", |studio| {
+        studio.get_str("name").ok().map(String::from)
+    })
+    .await
+}
+
+// Get all studios
+pub async fn studios(
+    db: &Database,
+    cfg: &Config,
+    from_page: u32,
+    first_oid: String,
+    last_oid: String,
+) -> GqlResult<StudiosResult> {
+    let coll = db.collection::<Document>("studios");
+
+    let mut filter_doc = doc! {};
+
+    let (pages_count, total_count) = count_pages_and_total(
+        &coll,
+        Some(filter_doc.clone()),
+        None,
+        cfg.page_size,
+    )
+    .await?;
+    let (current_page, skip_x) = calculate_current_filter_skip(
+        from_page,
+        first_oid,
+        last_oid,
+        &mut filter_doc,
+    )
+    .await?;
+
+    let sort_doc = doc! {"_id": -1};
+    let find_options =
+        find_options(Some(sort_doc), skip_x, cfg.page_size).await;
+
+    let mut cursor = coll.find(filter_doc, find_options).await?;
+
+    let mut studios: Vec<Studio> = vec![];
+    while let Some(result) = cursor.next().await {
+        match result {
+            Ok(document) => {
+                let studio = from_document(document)?;
+                studios.push(studio);
+            }
+            Err(error) => {
+                println!("\n\n\n{}\n\n\n", error);
+            }
+        }
+    }
+
+    let studios_result = StudiosResult {
+        page_info: PageInfo {
+            current_stuff: Some(String::from(STUDIOS_STUFF)),
+            current_page: Some(current_page),
+            first_cursor: match studios.first() {
+                Some(studio) => Some(studio._id),
+                _ => None,
+            },
+            last_cursor: match studios.last() {
+                Some(studio) => Some(studio._id),
+                _ => None,
+            },
+            has_previous_page: current_page > 1,
+            has_next_page: current_page < pages_count,
+        },
+        res_count: ResCount {
+            pages_count: Some(pages_count),
+            total_count: Some(total_count),
+        },
+        current_items: studios,
+    };
+
+    Ok(studios_result)
+}
+
+// get studios which the user of token is invited to curate
+pub async fn studio_invitations_by_token(
+    db: &Database,
+    cfg: &Config,
+    token: String,
+) -> GqlResult<Vec<Studio>> {
+    let user = users::services::user_by_token(db, cfg, token).await?;
+
+    let studios_curators = studios_curators_by_filter(
+        db,
+        doc! {"user_id": user._id, "accepted": false},
+    )
+    .await?;
+
+    let mut studios: Vec<Studio> = vec![];
+    for studio_curator in studios_curators {
+        if let Ok(studio) = studio_by_id(db, studio_curator.studio_id).await {
+            studios.push(studio);
+        }
+    }
+
+    Ok(studios)
+}
+
+// invite user to curate the studio, by its owner
+pub async fn studio_curator_invite(
+    db: &Database,
+    cfg: &Config,
+    studio_id: ObjectId,
+    username: String,
+    token: String,
+) -> GqlResult<Studio> {
+    let owner = users::services::user_by_token(db, cfg, token).await?;
+    let studio = studio_by_id(db, studio_id).await?;
+
+    if studio.user_id != owner._id {
+        return Err(AppError::NoPermission.extend());
+    }
+
+    let user = users::services::user_by_username(db, username).await?;
+    if user._id == owner._id {
+        return Err(AppError::StudioInviteSelf.extend());
+    }
+
+    // the unique index on (studio_id, user_id) keeps one invitation per user
+    let coll = db.collection::<Document>("studios_curators");
+    coll.update_one(
+        doc! {"studio_id": studio_id, "user_id": user._id},
+        doc! {"$setOnInsert": {
+            "inviter_id": owner._id,
+            "accepted": false,
+            "created_at": DateTime::now(),
+        }},
+        UpdateOptions::builder().upsert(true).build(),
+    )
+    .await?;
+
+    Ok(studio)
+}
+
+// accept the curator invitation by the invited user
+pub async fn studio_curator_accept(
+    db: &Database,
+    cfg: &Config,
+    studio_id: ObjectId,
+    token: String,
+) -> GqlResult<Studio> {
+    let user = users::services::user_by_token(db, cfg, token).await?;
+
+    let coll = db.collection::<Document>("studios_curators");
+    let accept_res = coll
+        .update_one(
+            doc! {"studio_id": studio_id, "user_id": user._id},
+            doc! {"$set": {"accepted": true}},
+            None,
+        )
+        .await?;
+
+    if accept_res.matched_count == 0 {
+        return Err(AppError::StudioInviteNotFound.extend());
+    }
+
+    studio_by_id(db, studio_id).await
+}
+
+// remove curator or invitation from studio: the owner could remove anyone,
+// and the curator could decline an invitation or leave.
+pub async fn studio_curator_remove(
+    db: &Database,
+    cfg: &Config,
+    studio_id: ObjectId,
+    user_id: ObjectId,
+    token: String,
+) -> GqlResult<Studio> {
+    let user = users::services::user_by_token(db, cfg, token).await?;
+    let studio = studio_by_id(db, studio_id).await?;
+
+    if studio.user_id != user._id && user_id != user._id {
+        return Err(AppError::NoPermission.extend());
+    }
+
+    let coll = db.collection::<Document>("studios_curators");
+    coll.delete_one(doc! {"studio_id": studio_id, "user_id": user_id}, None)
+        .await?;
+
+    Ok(studio)
+}
+
+// get accepted curators of studio
+pub async fn curators_by_studio_id(
+    db: &Database,
+    studio_id: ObjectId,
+) -> GqlResult<Vec<User>> {
+    let studios_curators = studios_curators_by_filter(
+        db,
+        doc! {"studio_id": studio_id, "accepted": true},
+    )
+    .await?;
+
+    let mut curators: Vec<User> = vec![];
+    for studio_curator in studios_curators {
+        let curator =
+            users::services::user_by_id(db, studio_curator.user_id).await?;
+        curators.push(curator);
+    }
+
+    Ok(curators)
+}
+
+// check if the user of token is the owner or an accepted curator of studio
+pub async fn studio_curated_by_token(
+    db: &Database,
+    cfg: &Config,
+    studio: &Studio,
+    token: String,
+) -> GqlResult<bool> {
+    if let Ok(user) = users::services::user_by_token(db, cfg, token).await {
+        studio_curated_by_user_id(db, studio, user._id).await
+    } else {
+        Ok(false)
+    }
+}
+
+// check if the user of token has a pending curator invitation of studio
+pub async fn studio_invited_by_token(
+    db: &Database,
+    cfg: &Config,
+    studio_id: ObjectId,
+    token: String,
+) -> GqlResult<bool> {
+    if let Ok(user) = users::services::user_by_token(db, cfg, token).await {
+        let coll = db.collection::<Document>("studios_curators");
+        let invitation_document = coll
+            .find_one(
+                doc! {
+                    "studio_id": studio_id,
+                    "user_id": user._id,
+                    "accepted": false,
+                },
+                None,
+            )
+            .await?;
+
+        Ok(invitation_document.is_some())
+    } else {
+        Ok(false)
+    }
+}
+
+async fn studio_curated_by_user_id(
+    db: &Database,
+    studio: &Studio,
+    user_id: ObjectId,
+) -> GqlResult<bool> {
+    if studio.user_id == user_id {
+        return Ok(true);
+    }
+
+    let coll = db.collection::<Document>("studios_curators");
+    let curator_document = coll
+        .find_one(
+            doc! {
+                "studio_id": studio._id,
+                "user_id": user_id,
+                "accepted": true,
+            },
+            None,
+        )
+        .await?;
+
+    Ok(curator_document.is_some())
+}
+
+async fn studios_curators_by_filter(
+    db: &Database,
+    filter_doc: Document,
+) -> GqlResult<Vec<StudioCurator>> {
+    let coll = db.collection::<Document>("studios_curators");
+    let find_options = FindOptions::builder().sort(doc! {"_id": 1}).build();
+    let mut cursor = coll.find(filter_doc, find_options).await?;
+
+    let mut studios_curators: Vec<StudioCurator> = vec![];
+    while let Some(result) = cursor.next().await {
+        match result {
+            Ok(document) => {
+                let studio_curator = from_document(document)?;
+                studios_curators.push(studio_curator);
+            }
+            Err(error) => {
+                println!("\n\n\n{}\n\n\n", error);
+            }
+        }
+    }
+
+    Ok(studios_curators)
+}
+
+// add published project to the end of studio, by its owner or curators
+pub async fn studio_project_add(
+    db: &Database,
+    cfg: &Config,
+    studio_id: ObjectId,
+    project_id: ObjectId,
+    token: String,
+) -> GqlResult<Studio> {
+    let user = users::services::user_by_token(db, cfg, token).await?;
+    let studio = studio_by_id(db, studio_id).await?;
+
+    if !studio_curated_by_user_id(db, &studio, user._id).await? {
+        return Err(AppError::NoPermission.extend());
+    }
+
+    let project = projects::services::project_by_id(db, project_id).await?;
+    if project.status < ProjectStatus::Published {
+        return Err(AppError::ProjectUnpublished.extend());
+    }
+
+    let studios_projects = studios_projects_by_studio_id(db, studio_id).await?;
+    let position = match studios_projects.last() {
+        Some(studio_project) => studio_project.position + 1,
+        None => 1,
+    };
+
+    // the unique index on (studio_id, project_id) keeps one membership
+    let coll = db.collection::<Document>("studios_projects");
+    coll.update_one(
+        doc! {"studio_id": studio_id, "project_id": project_id},
+        doc! {"$setOnInsert": {
+            "user_id": user._id,
+            "position": position,
+            "created_at": DateTime::now(),
+        }},
+        UpdateOptions::builder().upsert(true).build(),
+    )
+    .await?;
+
+    Ok(studio)
+}
+
+// remove project from studio, by its owner or curators
+pub async fn studio_project_remove(
+    db: &Database,
+    cfg: &Config,
+    studio_id: ObjectId,
+    project_id: ObjectId,
+    token: String,
+) -> GqlResult<Studio> {
+    let user = users::services::user_by_token(db, cfg, token).await?;
+    let studio = studio_by_id(db, studio_id).await?;
+
+    if !studio_curated_by_user_id(db, &studio, user._id).await? {
+        return Err(AppError::NoPermission.extend());
+    }
+
+    let coll = db.collection::<Document>("studios_projects");
+    coll.delete_one(
+        doc! {"studio_id": studio_id, "project_id": project_id},
+        None,
+    )
+    .await?;
+
+    Ok(studio)
+}
+
+// move project to the position (from 1) of studio, by its owner or curators
+pub async fn studio_project_move(
+    db: &Database,
+    cfg: &Config,
+    studio_id: ObjectId,
+    project_id: ObjectId,
+    position: u32,
+    token: String,
+) -> GqlResult<Studio> {
+    let user = users::services::user_by_token(db, cfg, token).await?;
+    let studio = studio_by_id(db, studio_id).await?;
+
+    if !studio_curated_by_user_id(db, &studio, user._id).await? {
+        return Err(AppError::NoPermission.extend());
+    }
+
+    let mut project_ids: Vec<ObjectId> =
+        studios_projects_by_studio_id(db, studio_id)
+            .await?
+            .into_iter()
+            .map(|studio_project| studio_project.project_id)
+            .collect();
+
+    let index = project_ids.iter().position(|id| *id == project_id);
+    if let Some(index) = index {
+        project_ids.remove(index);
+    } else {
+        return Err(AppError::StudioProjectNotFound.extend());
+    }
+
+    let position = (position.max(1) as usize).min(project_ids.len() + 1);
+    project_ids.insert(position - 1, project_id);
+
+    // renumber all memberships, so the positions keep continuous
+    let coll = db.collection::<Document>("studios_projects");
+    for (index, id) in project_ids.iter().enumerate() {
+        coll.update_one(
+            doc! {"studio_id": studio_id, "project_id": id},
+            doc! {"$set": {"position": index as i64 + 1}},
+            None,
+        )
+        .await?;
+    }
+
+    Ok(studio)
+}
+
+// count published projects of studio
+pub async fn projects_count_by_studio_id(
+    db: &Database,
+    studio_id: ObjectId,
+) -> GqlResult<u64> {
+    let project_ids: Vec<ObjectId> =
+        studios_projects_by_studio_id(db, studio_id)
+            .await?
+            .into_iter()
+            .map(|studio_project| studio_project.project_id)
+            .collect();
+
+    let coll = db.collection::<Document>("projects");
+    let projects_count = coll
+        .count_documents(
+            doc! {
+                "_id": {"$in": project_ids},
+                "status": {"$gte": ProjectStatus::Published}
+            },
+            None,
+        )
+        .await?;
+
+    Ok(projects_count)
+}
+
+// Get published projects of studio, in the order curated by position
+pub async fn projects_by_studio_id(
+    db: &Database,
+    cfg: &Config,
+    studio_id: ObjectId,
+    from_page: u32,
+    first_oid: String,
+    last_oid: String,
+) -> GqlResult<ProjectsResult> {
+    let studios_projects = studios_projects_by_studio_id(db, studio_id).await?;
+    let project_ids: Vec<ObjectId> = studios_projects
+        .iter()
+        .map(|studio_project| studio_project.project_id)
+        .collect();
+
+    let coll = db.collection::<Document>("projects");
+    let filter_doc = doc! {
+        "_id": {"$in": &project_ids},
+        "status": {"$gte": ProjectStatus::Published}
+    };
+    let mut cursor = coll.find(filter_doc, None).await?;
+
+    let mut projects_all: Vec<Project> = vec![];
+    while let Some(result) = cursor.next().await {
+        match result {
+            Ok(document) => {
+                let project = from_document(document)?;
+                projects_all.push(project);
+            }
+            Err(error) => {
+                println!("\n\n\n{}\n\n\n", error);
+            }
+        }
+    }
+    projects_all.sort_by_key(|project| {
+        project_ids.iter().position(|id| *id == project._id)
+    });
+
+    // the curated order isn't the order of ids, so paging is done by the
+    // page number only, and the cursors just show where the page starts.
+    let (current_page, _) = calculate_current_filter_skip(
+        from_page,
+        first_oid,
+        last_oid,
+        &mut doc! {},
+    )
+    .await?;
+    let page_size = cfg.page_size as usize;
+    let total_count = projects_all.len() as u64;
+    let pages_count = ((projects_all.len() + page_size - 1) / page_size) as u32;
+
+    let projects: Vec<Project> = projects_all
+        .into_iter()
+        .skip((current_page.max(1) as usize - 1) * page_size)
+        .take(page_size)
+        .collect();
+
+    let projects_result = ProjectsResult {
+        page_info: PageInfo {
+            current_stuff: Some(String::from(PROJECTS_STUFF)),
+            current_page: Some(current_page),
+            first_cursor: match projects.first() {
+                Some(project) => Some(project._id),
+                _ => None,
+            },
+            last_cursor: match projects.last() {
+                Some(project) => Some(project._id),
+                _ => None,
+            },
+            has_previous_page: current_page > 1,
+            has_next_page: current_page < pages_count,
+        },
+        res_count: ResCount {
+            pages_count: Some(pages_count),
+            total_count: Some(total_count),
+        },
+        current_items: projects,
+    };
+
+    Ok(projects_result)
+}
+
+// get all StudioProject of studio, ordered by position
+async fn studios_projects_by_studio_id(
+    db: &Database,
+    studio_id: ObjectId,
+) -> GqlResult<Vec<StudioProject>> {
+    let coll = db.collection::<Document>("studios_projects");
+    let find_options =
+        FindOptions::builder().sort(doc! {"position": 1, "_id": 1}).build();
+    let mut cursor =
+        coll.find(doc! {"studio_id": studio_id}, find_options).await?;
+
+    let mut studios_projects: Vec<StudioProject> = vec![];
+    while let Some(result) = cursor.next().await {
+        match result {
+            Ok(document) => {
+                let studio_project = from_document(document)?;
+                studios_projects.push(studio_project);
+            }
+            Err(error) => {
+                println!("\n\n\n{}\n\n\n", error);
+            }
+        }
+    }
+
+    Ok(studios_projects)
+}
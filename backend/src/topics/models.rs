@@ -2,7 +2,7 @@ use serde::{Serialize, Deserialize};
 use mongodb::bson::oid::ObjectId;
 
 use crate::dbs::mongo::DataSource;
-use crate::util::{constant::GqlResult, pagination::ProjectsResult};
+use crate::util::{config::Config, constant::GqlResult, pagination::ProjectsResult};
 
 use crate::projects::services::projects_by_topic_id;
 
@@ -22,8 +22,10 @@ impl Topic {
         ctx: &async_graphql::Context<'_>,
     ) -> GqlResult<ProjectsResult> {
         let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
         projects_by_topic_id(
             db,
+            cfg,
             self._id,
             1,
             String::from("-"),
@@ -37,7 +39,8 @@ impl Topic {
 #[derive(async_graphql::InputObject, Serialize, Deserialize)]
 pub struct TopicNew {
     pub name: String,
-    #[graphql(default = 1)]
+    // counted by the links to users & projects, not by creating
+    #[graphql(skip)]
     pub quotes: i64,
     #[graphql(skip)]
     pub slug: String,
@@ -1,21 +1,33 @@
+use std::collections::HashMap;
 use futures::stream::StreamExt;
 use mongodb::{
-    Database,
+    Database, ClientSession,
     bson::{
         oid::ObjectId, Document, doc, from_document, to_document, from_bson,
-        DateTime,
+        Bson,
     },
     options::FindOptions,
 };
-use async_graphql::Error;
+use async_graphql::ErrorExtensions;
 
-use crate::util::{constant::GqlResult, common::slugify};
+use crate::util::{config::Config, constant::GqlResult, error::AppError};
 
-use crate::users;
+use crate::{users, users::models::UserStatus};
+use crate::slugs::services::{
+    slug_unique, slug_unique_with_session, slug_change, slug_history_new,
+    slug_history_target_id, slugs_history_retarget, slugs_history_delete,
+    slugs_clean_migrate,
+};
 use super::models::{
     Topic, TopicNew, TopicUser, TopicUserNew, TopicProject, TopicProjectNew,
 };
 
+// the most topics returned by suggestions
+const SUGGEST_LIMIT_MAX: u32 = 20;
+// the longest topic names found in the contents, in words & in letters
+const PHRASE_WORDS_MAX: usize = 3;
+const PHRASE_CHARS_MAX: usize = 8;
+
 // Create new topic
 pub async fn topic_new(
     db: &Database,
@@ -32,33 +44,22 @@ pub async fn topic_new(
 
             let topic_id;
             if exist_document.is_none() {
-                let slug = slugify(&topic_new.name).await;
                 topic_new.slug =
-                    format!("{}-{}", slug, DateTime::now().timestamp_millis());
+                    slug_unique(db, "topics", &topic_new.name, None).await?;
+                topic_new.quotes = 0;
 
                 let new_document = to_document(&topic_new)?;
-                let topic_res = coll
-                    .insert_one(new_document, None)
-                    .await
-                    .expect("写入未成功");
+                let topic_res = coll.insert_one(new_document, None).await?;
 
                 topic_id = from_bson(topic_res.inserted_id)?;
             } else {
                 let topic: Topic = from_document(exist_document.unwrap())?;
-                coll.update_one(
-                    doc! {"_id": &topic._id},
-                    doc! {"$inc": {"quotes": 1}},
-                    None,
-                )
-                .await
-                .expect("更新未成功");
-
                 topic_id = topic._id;
             }
 
             topic_by_id(db, topic_id).await
         }
-        _ => Err(Error::new("名称不合法")),
+        _ => Err(AppError::NameInvalid.extend()),
     }
 }
 
@@ -66,28 +67,28 @@ pub async fn topic_new(
 pub async fn topic_by_id(db: &Database, id: ObjectId) -> GqlResult<Topic> {
     let coll = db.collection::<Document>("topics");
 
-    let topic_document = coll
-        .find_one(doc! {"_id": id}, None)
-        .await
-        .expect("查询未成功")
-        .unwrap();
+    let topic_document = coll.find_one(doc! {"_id": id}, None).await?;
 
-    let topic: Topic = from_document(topic_document)?;
-    Ok(topic)
+    match topic_document {
+        Some(document) => Ok(from_document(document)?),
+        None => Err(AppError::TopicNotFound.extend()),
+    }
 }
 
-// get topic info by slug
+// get topic info by slug, or by an old slug before renaming
 pub async fn topic_by_slug(db: &Database, slug: String) -> GqlResult<Topic> {
     let coll = db.collection::<Document>("topics");
 
-    let topic_document = coll
-        .find_one(doc! {"slug": slug.to_lowercase()}, None)
-        .await
-        .expect("查询未成功")
-        .unwrap();
+    let topic_document =
+        coll.find_one(doc! {"slug": slug.to_lowercase()}, None).await?;
 
-    let topic: Topic = from_document(topic_document)?;
-    Ok(topic)
+    match topic_document {
+        Some(document) => Ok(from_document(document)?),
+        None => match slug_history_target_id(db, "topics", &slug).await? {
+            Some(topic_id) => topic_by_id(db, topic_id).await,
+            None => Err(AppError::TopicNotFound.extend()),
+        },
+    }
 }
 
 // Create new topics
@@ -101,7 +102,7 @@ pub async fn topics_new(
     for name in names {
         let topic_init = TopicNew {
             name: String::from(name.trim()),
-            quotes: 1,
+            quotes: 0,
             slug: String::from(""),
         };
 
@@ -112,6 +113,77 @@ pub async fn topics_new(
     Ok(topics)
 }
 
+// Link topics to the project in the session of a transaction, topics not
+// existing are created. Blank & repeated names are skipped.
+pub async fn topics_project_link(
+    db: &Database,
+    session: &mut ClientSession,
+    topic_names: &str,
+    user_id: ObjectId,
+    project_id: ObjectId,
+) -> GqlResult<()> {
+    let coll_topics = db.collection::<Document>("topics");
+    let coll_topics_projects =
+        db.collection::<Document>("topics_users_projects");
+
+    let mut names: Vec<String> = vec![];
+    for name in topic_names.split(",") {
+        let name = name.trim().to_lowercase();
+        if "".ne(&name) && "-".ne(&name) && !names.contains(&name) {
+            names.push(name);
+        }
+    }
+
+    for name in names {
+        let exist_document = coll_topics
+            .find_one_with_session(doc! {"name": &name}, None, session)
+            .await?;
+
+        let topic_id: ObjectId = match exist_document {
+            Some(document) => document.get_object_id("_id")?,
+            None => {
+                let topic_new = TopicNew {
+                    slug: slug_unique_with_session(
+                        db, session, "topics", &name, None,
+                    )
+                    .await?,
+                    name,
+                    quotes: 0,
+                };
+                let topic_res = coll_topics
+                    .insert_one_with_session(
+                        to_document(&topic_new)?,
+                        None,
+                        session,
+                    )
+                    .await?;
+
+                from_bson(topic_res.inserted_id)?
+            }
+        };
+
+        let topic_project_new =
+            TopicProjectNew { user_id, project_id, topic_id };
+        coll_topics_projects
+            .insert_one_with_session(
+                to_document(&topic_project_new)?,
+                None,
+                session,
+            )
+            .await?;
+        coll_topics
+            .update_one_with_session(
+                doc! {"_id": topic_id},
+                doc! {"$inc": {"quotes": 1}},
+                None,
+                session,
+            )
+            .await?;
+    }
+
+    Ok(())
+}
+
 // Create new topic_user
 pub async fn topic_user_new(
     db: &Database,
@@ -131,13 +203,13 @@ pub async fn topic_user_new(
         .await?;
     if exist_document.is_none() {
         let new_document = to_document(&topic_user_new)?;
-        let topic_user_res =
-            coll.insert_one(new_document, None).await.expect("写入未成功");
+        let topic_user_res = coll.insert_one(new_document, None).await?;
         let topic_user_id = from_bson(topic_user_res.inserted_id)?;
+        topic_quotes_inc(db, topic_user_new.topic_id).await?;
 
         topic_user_by_id(db, topic_user_id).await
     } else {
-        Err(Error::new("记录已存在"))
+        Err(AppError::RecordExists.extend())
     }
 }
 
@@ -145,14 +217,12 @@ pub async fn topic_user_new(
 async fn topic_user_by_id(db: &Database, id: ObjectId) -> GqlResult<TopicUser> {
     let coll = db.collection::<Document>("topics_users_projects");
 
-    let topic_user_document = coll
-        .find_one(doc! {"_id": id}, None)
-        .await
-        .expect("查询未成功")
-        .unwrap();
+    let topic_user_document = coll.find_one(doc! {"_id": id}, None).await?;
 
-    let topic_user: TopicUser = from_document(topic_user_document)?;
-    Ok(topic_user)
+    match topic_user_document {
+        Some(document) => Ok(from_document(document)?),
+        None => Err(AppError::RecordNotFound.extend()),
+    }
 }
 
 // Create new topic_project
@@ -174,13 +244,13 @@ pub async fn topic_project_new(
         .await?;
     if exist_document.is_none() {
         let new_document = to_document(&topic_project_new)?;
-        let topic_project_res =
-            coll.insert_one(new_document, None).await.expect("写入未成功");
+        let topic_project_res = coll.insert_one(new_document, None).await?;
         let topic_project_id = from_bson(topic_project_res.inserted_id)?;
+        topic_quotes_inc(db, topic_project_new.topic_id).await?;
 
         topic_project_by_id(db, topic_project_id).await
     } else {
-        Err(Error::new("记录已存在"))
+        Err(AppError::RecordExists.extend())
     }
 }
 
@@ -191,14 +261,214 @@ async fn topic_project_by_id(
 ) -> GqlResult<TopicProject> {
     let coll = db.collection::<Document>("topics_users_projects");
 
-    let topic_project_document = coll
-        .find_one(doc! {"_id": id}, None)
-        .await
-        .expect("查询未成功")
-        .unwrap();
+    let topic_project_document = coll.find_one(doc! {"_id": id}, None).await?;
+
+    match topic_project_document {
+        Some(document) => Ok(from_document(document)?),
+        None => Err(AppError::RecordNotFound.extend()),
+    }
+}
+
+// count one more link of topic, only after the link is written
+async fn topic_quotes_inc(db: &Database, topic_id: ObjectId) -> GqlResult<()> {
+    let coll = db.collection::<Document>("topics");
+    coll.update_one(doc! {"_id": topic_id}, doc! {"$inc": {"quotes": 1}}, None)
+        .await?;
+
+    Ok(())
+}
+
+// rename topic by moderators, the old slug is kept for redirects
+pub async fn topic_rename(
+    db: &Database,
+    cfg: &Config,
+    topic_id: ObjectId,
+    name: String,
+    token: String,
+) -> GqlResult<Topic> {
+    moderator_check(db, cfg, token).await?;
+
+    let topic = topic_by_id(db, topic_id).await?;
+    let name = name.trim().to_lowercase();
+    if "".eq(&name) || "-".eq(&name) || name.contains(',') {
+        return Err(AppError::NameInvalid.extend());
+    }
+
+    let coll = db.collection::<Document>("topics");
+    let exist_document = coll
+        .find_one(doc! {"_id": {"$ne": topic_id}, "name": &name}, None)
+        .await?;
+    if exist_document.is_some() {
+        return Err(AppError::TopicExists { name }.extend());
+    }
+
+    coll.update_one(
+        doc! {"_id": topic_id},
+        doc! {"$set": {"name": &name}},
+        None,
+    )
+    .await?;
+
+    let slug = slug_unique(db, "topics", &name, Some(topic_id)).await?;
+    slug_change(db, "topics", topic_id, &topic.slug, &slug).await?;
+
+    topic_by_id(db, topic_id).await
+}
+
+// merge topic into the target one by moderators: links are re-pointed to
+// the target, duplicates are dropped, and the merged topic is deleted.
+pub async fn topic_merge(
+    db: &Database,
+    cfg: &Config,
+    topic_id: ObjectId,
+    target_id: ObjectId,
+    token: String,
+) -> GqlResult<Topic> {
+    moderator_check(db, cfg, token).await?;
+
+    if topic_id == target_id {
+        return Err(AppError::MergeSelf.extend());
+    }
+    let topic = topic_by_id(db, topic_id).await?;
+    topic_by_id(db, target_id).await?;
+
+    let coll_links = db.collection::<Document>("topics_users_projects");
+    let mut cursor = coll_links.find(doc! {"topic_id": topic_id}, None).await?;
+
+    let mut links: Vec<Document> = vec![];
+    while let Some(result) = cursor.next().await {
+        match result {
+            Ok(document) => links.push(document),
+            Err(error) => {
+                println!("\n\n\n{}\n\n\n", error);
+            }
+        }
+    }
+
+    for link in links {
+        let link_id = link.get_object_id("_id")?;
+        // keywords of users are the links without project
+        let project_filter = match link.get_object_id("project_id") {
+            Ok(project_id) => Bson::ObjectId(project_id),
+            Err(_) => Bson::Document(doc! {"$exists": false}),
+        };
+        let target_filter = doc! {
+            "topic_id": target_id,
+            "user_id": link.get_object_id("user_id")?,
+            "project_id": project_filter,
+        };
+
+        if coll_links.find_one(target_filter, None).await?.is_some() {
+            coll_links.delete_one(doc! {"_id": link_id}, None).await?;
+        } else {
+            coll_links
+                .update_one(
+                    doc! {"_id": link_id},
+                    doc! {"$set": {"topic_id": target_id}},
+                    None,
+                )
+                .await?;
+        }
+    }
+
+    let coll = db.collection::<Document>("topics");
+    coll.delete_one(doc! {"_id": topic_id}, None).await?;
+    topic_quotes_recount(db, target_id).await?;
+
+    // old urls of the merged topic lead to the target
+    slugs_history_retarget(db, "topics", topic_id, target_id).await?;
+    slug_history_new(db, "topics", &topic.slug, target_id).await?;
+
+    topic_by_id(db, target_id).await
+}
+
+// delete topic and all its links by moderators
+pub async fn topic_delete(
+    db: &Database,
+    cfg: &Config,
+    topic_id: ObjectId,
+    token: String,
+) -> GqlResult<Topic> {
+    moderator_check(db, cfg, token).await?;
+
+    let topic = topic_by_id(db, topic_id).await?;
+
+    let coll_links = db.collection::<Document>("topics_users_projects");
+    coll_links.delete_many(doc! {"topic_id": topic_id}, None).await?;
+
+    let coll = db.collection::<Document>("topics");
+    coll.delete_one(doc! {"_id": topic_id}, None).await?;
+    slugs_history_delete(db, "topics", topic_id).await?;
+
+    Ok(topic)
+}
+
+// set quotes of topic to the count of its links
+async fn topic_quotes_recount(
+    db: &Database,
+    topic_id: ObjectId,
+) -> GqlResult<i64> {
+    let coll_links = db.collection::<Document>("topics_users_projects");
+    let quotes = coll_links
+        .count_documents(doc! {"topic_id": topic_id}, None)
+        .await? as i64;
+
+    let coll = db.collection::<Document>("topics");
+    coll.update_one(
+        doc! {"_id": topic_id},
+        doc! {"$set": {"quotes": quotes}},
+        None,
+    )
+    .await?;
+
+    Ok(quotes)
+}
+
+// replace the timestamped slugs of topics with clean ones
+pub async fn topics_slugs_migrate(db: &Database) -> GqlResult<u64> {
+    slugs_clean_migrate(db, "topics", |topic| {
+        topic.get_str("name").ok().map(String::from)
+    })
+    .await
+}
+
+// recompute quotes of all topics from the actual links, returns the count
+// of repaired topics
+pub async fn topics_quotes_repair(db: &Database) -> GqlResult<u64> {
+    let topics = topics(db).await?;
+
+    let coll = db.collection::<Document>("topics");
+    let coll_links = db.collection::<Document>("topics_users_projects");
+    let mut repaired_count = 0;
+    for topic in topics {
+        let quotes = coll_links
+            .count_documents(doc! {"topic_id": topic._id}, None)
+            .await? as i64;
+        if quotes != topic.quotes {
+            coll.update_one(
+                doc! {"_id": topic._id},
+                doc! {"$set": {"quotes": quotes}},
+                None,
+            )
+            .await?;
+            repaired_count += 1;
+        }
+    }
+
+    Ok(repaired_count)
+}
 
-    let topic_project: TopicProject = from_document(topic_project_document)?;
-    Ok(topic_project)
+async fn moderator_check(
+    db: &Database,
+    cfg: &Config,
+    token: String,
+) -> GqlResult<()> {
+    let user = users::services::user_by_token(db, cfg, token).await?;
+    if user.status < UserStatus::Moderator {
+        return Err(AppError::NoPermission.extend());
+    }
+
+    Ok(())
 }
 
 // get all topics
@@ -224,12 +494,145 @@ pub async fn topics(db: &Database) -> GqlResult<Vec<Topic>> {
     Ok(topics)
 }
 
+// suggest topics whose name or slug starts with the prefix, the most quoted
+// first, and an exact name on the top
+pub async fn topics_suggest(
+    db: &Database,
+    prefix: String,
+    limit: u32,
+) -> GqlResult<Vec<Topic>> {
+    let prefix = prefix.trim().to_lowercase();
+    if "".eq(&prefix) {
+        return Ok(vec![]);
+    }
+
+    let coll = db.collection::<Document>("topics");
+
+    let prefix_regex = format!("^{}", regex::escape(&prefix));
+    let filter_doc = doc! {"$or": [
+        {"name": {"$regex": &prefix_regex}},
+        {"slug": {"$regex": &prefix_regex}}
+    ]};
+    let find_options = FindOptions::builder()
+        .sort(doc! {"quotes": -1, "name": 1})
+        .limit(limit.clamp(1, SUGGEST_LIMIT_MAX) as i64)
+        .build();
+    let mut cursor = coll.find(filter_doc, find_options).await?;
+
+    let mut topics: Vec<Topic> = vec![];
+    while let Some(result) = cursor.next().await {
+        match result {
+            Ok(document) => {
+                let topic: Topic = from_document(document)?;
+                topics.push(topic);
+            }
+            Err(error) => {
+                println!("\n\n\n{}\n\n\n", error);
+            }
+        }
+    }
+    if let Some(index) = topics.iter().position(|topic| topic.name == prefix) {
+        let topic = topics.remove(index);
+        topics.insert(0, topic);
+    }
+
+    Ok(topics)
+}
+
+// suggest existing topics whose names are found in the subject or content,
+// ranked by the occurrences, then by quotes. The text is split into the
+// phrases a topic name could be, which are looked up at once.
+pub async fn topics_for_content(
+    db: &Database,
+    subject: String,
+    content: String,
+) -> GqlResult<Vec<Topic>> {
+    let text = format!("{}\n{}", subject, content).to_lowercase();
+    let phrases = content_phrases(&text);
+    if phrases.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let coll = db.collection::<Document>("topics");
+    let names: Vec<&String> = phrases.keys().collect();
+    let mut cursor = coll
+        .find(doc! {"name": {"$in": names}, "quotes": {"$gte": 1}}, None)
+        .await?;
+
+    let mut topics_found: Vec<(usize, Topic)> = vec![];
+    while let Some(result) = cursor.next().await {
+        match result {
+            Ok(document) => {
+                let topic: Topic = from_document(document)?;
+                let occurrences =
+                    phrases.get(&topic.name).copied().unwrap_or_default();
+                topics_found.push((occurrences, topic));
+            }
+            Err(error) => {
+                println!("\n\n\n{}\n\n\n", error);
+            }
+        }
+    }
+    topics_found.sort_by(|a, b| {
+        b.0.cmp(&a.0).then_with(|| b.1.quotes.cmp(&a.1.quotes))
+    });
+
+    Ok(topics_found
+        .into_iter()
+        .take(SUGGEST_LIMIT_MAX as usize)
+        .map(|(_, topic)| topic)
+        .collect())
+}
+
+// Phrases of the text with their occurrences. Ascii names are whole words,
+// so the phrases are runs of up to `PHRASE_WORDS_MAX` words, with the
+// separators between them as in the text, such as "node.js". Other names are
+// found inside text without spaces, so the phrases are all pieces of up to
+// `PHRASE_CHARS_MAX` letters of it. One letter phrases match almost
+// everything, and are left out.
+fn content_phrases(text: &str) -> HashMap<String, usize> {
+    let mut phrases: HashMap<String, usize> = HashMap::new();
+
+    let words: Vec<(usize, usize)> = text.char_indices().fold(
+        vec![],
+        |mut words: Vec<(usize, usize)>, (i, c)| {
+            if c.is_ascii_alphanumeric() || c == '_' {
+                match words.last_mut() {
+                    Some(word) if word.1 == i => word.1 = i + 1,
+                    _ => words.push((i, i + 1)),
+                }
+            }
+            words
+        },
+    );
+    for (i, first) in words.iter().enumerate() {
+        for last in &words[i..words.len().min(i + PHRASE_WORDS_MAX)] {
+            let phrase = &text[first.0..last.1];
+            if phrase.len() > 1 && phrase.is_ascii() {
+                *phrases.entry(phrase.to_string()).or_default() += 1;
+            }
+        }
+    }
+
+    for run in text.split(|c: char| c.is_ascii() || !c.is_alphanumeric()) {
+        let chars: Vec<char> = run.chars().collect();
+        for i in 0..chars.len() {
+            for j in (i + 2)..=chars.len().min(i + PHRASE_CHARS_MAX) {
+                let phrase: String = chars[i..j].iter().collect();
+                *phrases.entry(phrase).or_default() += 1;
+            }
+        }
+    }
+
+    phrases
+}
+
 // get topics by project_id
 pub async fn topics_by_project_id(
     db: &Database,
     project_id: ObjectId,
 ) -> GqlResult<Vec<Topic>> {
-    let topics_projects = topics_projects_by_project_id(db, project_id).await;
+    let topics_projects = topics_projects_by_project_id(db, project_id).await?;
 
     let mut topic_ids = vec![];
     for topic_project in topics_projects {
@@ -257,23 +660,21 @@ pub async fn topics_by_project_id(
 }
 
 // get all TopicProject list by project_id
-async fn topics_projects_by_project_id(
+pub async fn topics_projects_by_project_id(
     db: &Database,
     project_id: ObjectId,
-) -> Vec<TopicProject> {
+) -> GqlResult<Vec<TopicProject>> {
     let coll_topics_projects =
         db.collection::<Document>("topics_users_projects");
     let mut cursor_topics_projects = coll_topics_projects
         .find(doc! {"project_id": project_id}, None)
-        .await
-        .unwrap();
+        .await?;
 
     let mut topics_projects: Vec<TopicProject> = vec![];
     while let Some(result) = cursor_topics_projects.next().await {
         match result {
             Ok(document) => {
-                let topic_project: TopicProject =
-                    from_document(document).unwrap();
+                let topic_project: TopicProject = from_document(document)?;
                 topics_projects.push(topic_project);
             }
             Err(error) => {
@@ -282,7 +683,7 @@ async fn topics_projects_by_project_id(
         }
     }
 
-    topics_projects
+    Ok(topics_projects)
 }
 
 // get users' keywords by user_id
@@ -290,7 +691,7 @@ pub async fn keywords_by_user_id(
     db: &Database,
     user_id: ObjectId,
 ) -> GqlResult<Vec<Topic>> {
-    let topics_users = topics_users_by_user_id(db, user_id, false).await;
+    let topics_users = topics_users_by_user_id(db, user_id, false).await?;
 
     let mut topic_ids = vec![];
     for topic_user in topics_users {
@@ -322,7 +723,7 @@ async fn topics_users_by_user_id(
     db: &Database,
     user_id: ObjectId,
     contain_project: bool,
-) -> Vec<TopicUser> {
+) -> GqlResult<Vec<TopicUser>> {
     let coll_topics_users = db.collection::<Document>("topics_users_projects");
 
     let mut filter_doc = doc! {"user_id": user_id};
@@ -330,13 +731,13 @@ async fn topics_users_by_user_id(
         filter_doc.insert("project_id", doc! { "$exists": contain_project });
     }
     let mut cursor_topics_users =
-        coll_topics_users.find(filter_doc, None).await.unwrap();
+        coll_topics_users.find(filter_doc, None).await?;
 
     let mut topics_users: Vec<TopicUser> = vec![];
     while let Some(result) = cursor_topics_users.next().await {
         match result {
             Ok(document) => {
-                let topic_user: TopicUser = from_document(document).unwrap();
+                let topic_user: TopicUser = from_document(document)?;
                 topics_users.push(topic_user);
             }
             Err(error) => {
@@ -345,7 +746,7 @@ async fn topics_users_by_user_id(
         }
     }
 
-    topics_users
+    Ok(topics_users)
 }
 
 // get users' keywords by username
@@ -362,7 +763,7 @@ pub async fn topics_by_user_id(
     db: &Database,
     user_id: ObjectId,
 ) -> GqlResult<Vec<Topic>> {
-    let topics_projects = topics_users_by_user_id(db, user_id, true).await;
+    let topics_projects = topics_users_by_user_id(db, user_id, true).await?;
 
     let mut topic_ids_dup = vec![];
     for topic_project in topics_projects {
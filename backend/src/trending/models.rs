@@ -0,0 +1,56 @@
+use serde::{Serialize, Deserialize};
+use mongodb::bson::{oid::ObjectId, DateTime};
+
+// time window of the trending ranking
+#[derive(
+    async_graphql::Enum,
+    Serialize,
+    Deserialize,
+    Copy,
+    Clone,
+    Eq,
+    PartialEq,
+    Debug,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum TrendingWindow {
+    Day,
+    Week,
+    Month,
+}
+
+impl TrendingWindow {
+    pub const ALL: [TrendingWindow; 3] = [
+        TrendingWindow::Day,
+        TrendingWindow::Week,
+        TrendingWindow::Month,
+    ];
+
+    pub fn days(&self) -> i64 {
+        match self {
+            TrendingWindow::Day => 1,
+            TrendingWindow::Week => 7,
+            TrendingWindow::Month => 30,
+        }
+    }
+}
+
+// one project of the trending ranking, recomputed periodically
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ProjectTrending {
+    pub _id: ObjectId,
+    pub window: TrendingWindow,
+    pub project_id: ObjectId,
+    pub score: f64,
+    pub rank: u32,
+    pub computed_at: DateTime,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ProjectTrendingNew {
+    pub window: TrendingWindow,
+    pub project_id: ObjectId,
+    pub score: f64,
+    pub rank: u32,
+    pub computed_at: DateTime,
+}
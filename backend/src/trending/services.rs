@@ -0,0 +1,289 @@
+use std::collections::HashMap;
+use futures::stream::StreamExt;
+use mongodb::{
+    Database,
+    bson::{
+        oid::ObjectId, DateTime, Document, doc, from_document, to_document,
+        to_bson,
+    },
+    options::{FindOptions, FindOneOptions},
+};
+use chrono::Duration;
+
+use crate::util::constant::{GqlResult, DAY_MILLIS};
+
+use crate::comments::services::COMMENT_STATUS_VISIBLE;
+use crate::projects::models::{Project, ProjectStar, ProjectStatus};
+use crate::views::models::ProjectViewsDaily;
+use super::models::{TrendingWindow, ProjectTrending, ProjectTrendingNew};
+
+// weights of views, stars, comments and remixes in the trending score
+const TRENDING_VIEW_WEIGHT: f64 = 1.0;
+const TRENDING_STAR_WEIGHT: f64 = 5.0;
+const TRENDING_COMMENT_WEIGHT: f64 = 8.0;
+const TRENDING_REMIX_WEIGHT: f64 = 12.0;
+// count of projects kept in the ranking of each window
+const TRENDING_SIZE: usize = 100;
+
+// get trending projects of the window, by the latest computed ranking
+pub async fn projects_trending(
+    db: &Database,
+    window: TrendingWindow,
+    limit: i64,
+) -> GqlResult<Vec<Project>> {
+    let coll_trending = db.collection::<Document>("projects_trending");
+
+    let window_bson = to_bson(&window)?;
+    let latest_options =
+        FindOneOptions::builder().sort(doc! {"computed_at": -1}).build();
+    let latest_document = coll_trending
+        .find_one(doc! {"window": &window_bson}, latest_options)
+        .await?;
+    let computed_at = match latest_document {
+        Some(document) => {
+            let latest: ProjectTrending = from_document(document)?;
+            latest.computed_at
+        }
+        None => return Ok(vec![]),
+    };
+
+    let find_options = FindOptions::builder()
+        .sort(doc! {"rank": 1})
+        .limit(limit.clamp(1, TRENDING_SIZE as i64))
+        .build();
+    let mut cursor = coll_trending
+        .find(
+            doc! {"window": &window_bson, "computed_at": computed_at},
+            find_options,
+        )
+        .await?;
+
+    let mut project_ids: Vec<ObjectId> = vec![];
+    while let Some(result) = cursor.next().await {
+        match result {
+            Ok(document) => {
+                let trending: ProjectTrending = from_document(document)?;
+                project_ids.push(trending.project_id);
+            }
+            Err(error) => {
+                println!("\n\n\n{}\n\n\n", error);
+            }
+        }
+    }
+
+    // projects unpublished or trashed since the computing are left out
+    let coll = db.collection::<Document>("projects");
+    let mut cursor = coll
+        .find(
+            doc! {
+                "_id": {"$in": &project_ids},
+                "status": {"$gte": ProjectStatus::Published}
+            },
+            None,
+        )
+        .await?;
+
+    let mut projects_map: HashMap<ObjectId, Project> = HashMap::new();
+    while let Some(result) = cursor.next().await {
+        match result {
+            Ok(document) => {
+                let project: Project = from_document(document)?;
+                projects_map.insert(project._id, project);
+            }
+            Err(error) => {
+                println!("\n\n\n{}\n\n\n", error);
+            }
+        }
+    }
+
+    let projects = project_ids
+        .iter()
+        .filter_map(|project_id| projects_map.remove(project_id))
+        .collect();
+
+    Ok(projects)
+}
+
+// Recompute the trending rankings of all windows, returns the count of
+// ranked projects. Daily views, stars, comments and remixes are weighted,
+// and decay by half every half window.
+pub async fn projects_trending_compute(db: &Database) -> GqlResult<u64> {
+    let coll_trending = db.collection::<Document>("projects_trending");
+
+    let mut ranked_count = 0;
+    for window in TrendingWindow::ALL {
+        let computed_at = DateTime::now();
+        let scores = trending_scores(db, window, computed_at).await?;
+
+        let mut ranking: Vec<(ObjectId, f64)> = scores.into_iter().collect();
+        ranking.sort_by(|a, b| b.1.total_cmp(&a.1));
+        ranking.truncate(TRENDING_SIZE);
+
+        let mut trending_documents: Vec<Document> = vec![];
+        for (index, (project_id, score)) in ranking.into_iter().enumerate() {
+            let project_trending_new = ProjectTrendingNew {
+                window,
+                project_id,
+                score,
+                rank: index as u32 + 1,
+                computed_at,
+            };
+            trending_documents.push(to_document(&project_trending_new)?);
+        }
+
+        ranked_count += trending_documents.len() as u64;
+        if !trending_documents.is_empty() {
+            coll_trending.insert_many(trending_documents, None).await?;
+        }
+
+        // the new ranking is in place, drop the former ones
+        coll_trending
+            .delete_many(
+                doc! {
+                    "window": to_bson(&window)?,
+                    "computed_at": {"$lt": computed_at}
+                },
+                None,
+            )
+            .await?;
+    }
+
+    Ok(ranked_count)
+}
+
+// trending scores of the published projects active in the window
+async fn trending_scores(
+    db: &Database,
+    window: TrendingWindow,
+    now: DateTime,
+) -> GqlResult<HashMap<ObjectId, f64>> {
+    let half_life_hours = (window.days() * 24) as f64 / 2.0;
+    let decay = |created_at: DateTime| {
+        let age_hours = (now.timestamp_millis() - created_at.timestamp_millis())
+            as f64
+            / 3_600_000.0;
+        0.5_f64.powf(age_hours.max(0.0) / half_life_hours)
+    };
+    let since = now.to_chrono() + Duration::days(-window.days());
+
+    let mut scores: HashMap<ObjectId, f64> = HashMap::new();
+
+    let coll_stars = db.collection::<Document>("project_stars");
+    let mut cursor =
+        coll_stars.find(doc! {"created_at": {"$gte": since}}, None).await?;
+    while let Some(result) = cursor.next().await {
+        match result {
+            Ok(document) => {
+                let star: ProjectStar = from_document(document)?;
+                *scores.entry(star.project_id).or_insert(0.0) +=
+                    TRENDING_STAR_WEIGHT * decay(star.created_at);
+            }
+            Err(error) => {
+                println!("\n\n\n{}\n\n\n", error);
+            }
+        }
+    }
+
+    let coll_comments = db.collection::<Document>("comments");
+    let mut cursor = coll_comments
+        .find(
+            doc! {
+                "created_at": {"$gte": since},
+                "status": {"$gte": COMMENT_STATUS_VISIBLE}
+            },
+            None,
+        )
+        .await?;
+    while let Some(result) = cursor.next().await {
+        match result {
+            Ok(document) => {
+                let project_id = document.get_object_id("project_id")?;
+                let created_at = *document.get_datetime("created_at")?;
+                *scores.entry(project_id).or_insert(0.0) +=
+                    TRENDING_COMMENT_WEIGHT * decay(created_at);
+            }
+            Err(error) => {
+                println!("\n\n\n{}\n\n\n", error);
+            }
+        }
+    }
+
+    let coll = db.collection::<Document>("projects");
+    let mut cursor = coll
+        .find(
+            doc! {
+                "remix_of": {"$exists": true},
+                "created_at": {"$gte": since}
+            },
+            None,
+        )
+        .await?;
+    while let Some(result) = cursor.next().await {
+        match result {
+            Ok(document) => {
+                let remix: Project = from_document(document)?;
+                if let Some(remix_of) = remix.remix_of {
+                    *scores.entry(remix_of).or_insert(0.0) +=
+                        TRENDING_REMIX_WEIGHT * decay(remix.created_at);
+                }
+            }
+            Err(error) => {
+                println!("\n\n\n{}\n\n\n", error);
+            }
+        }
+    }
+
+    // daily views overlapping the window, decayed from the middle of the day
+    let coll_daily = db.collection::<Document>("projects_views_daily");
+    let views_since = DateTime::from_millis(
+        now.timestamp_millis() - (window.days() + 1) * DAY_MILLIS,
+    );
+    let mut cursor =
+        coll_daily.find(doc! {"day": {"$gt": views_since}}, None).await?;
+    while let Some(result) = cursor.next().await {
+        match result {
+            Ok(document) => {
+                let views_daily: ProjectViewsDaily = from_document(document)?;
+                let viewed_at = DateTime::from_millis(
+                    views_daily.day.timestamp_millis() + DAY_MILLIS / 2,
+                );
+                *scores.entry(views_daily.project_id).or_insert(0.0) +=
+                    TRENDING_VIEW_WEIGHT
+                        * views_daily.views as f64
+                        * decay(viewed_at);
+            }
+            Err(error) => {
+                println!("\n\n\n{}\n\n\n", error);
+            }
+        }
+    }
+
+    // only the published projects are ranked
+    let active_ids: Vec<ObjectId> = scores.keys().cloned().collect();
+    let mut cursor = coll
+        .find(
+            doc! {
+                "_id": {"$in": active_ids},
+                "status": {"$gte": ProjectStatus::Published}
+            },
+            None,
+        )
+        .await?;
+
+    let mut published_scores: HashMap<ObjectId, f64> = HashMap::new();
+    while let Some(result) = cursor.next().await {
+        match result {
+            Ok(document) => {
+                let project_id = document.get_object_id("_id")?;
+                if let Some(score) = scores.get(&project_id) {
+                    published_scores.insert(project_id, *score);
+                }
+            }
+            Err(error) => {
+                println!("\n\n\n{}\n\n\n", error);
+            }
+        }
+    }
+
+    Ok(published_scores)
+}
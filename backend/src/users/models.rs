@@ -1,18 +1,34 @@
+use std::cmp::Ordering;
 use serde::{Serialize, Deserialize};
-use mongodb::bson::{oid::ObjectId, DateTime};
-use chrono::FixedOffset;
+use mongodb::bson::{oid::ObjectId, Bson, DateTime};
 
 use crate::dbs::mongo::DataSource;
 use crate::util::{
-    constant::{GqlResult, DTF_YMDHMSZ},
-    pagination::ProjectsResult,
+    config::Config,
+    constant::GqlResult,
+    datetime::{DateStyle, date_formatted, date_nyrsq},
+    pagination::{ProjectsResult, UsersResult},
 };
 
 use crate::{
     topics::{self, models::Topic},
-    projects::services::projects_by_user_id,
+    projects::{
+        self,
+        models::{Project, ProjectStatusKind},
+        services::{projects_by_user_id, projects_starred_by_user_id},
+    },
+    comments::{self, models::Comment},
+    views::{
+        self,
+        models::{Stats, StatsRange},
+    },
 };
 
+// kinds of the activities in feed
+pub const FEED_KIND_PROJECT: &str = "project";
+pub const FEED_KIND_STAR: &str = "star";
+pub const FEED_KIND_COMMENT: &str = "comment";
+
 #[derive(async_graphql::SimpleObject, Serialize, Deserialize, Clone, Debug)]
 #[graphql(complex)]
 pub struct User {
@@ -27,14 +43,23 @@ pub struct User {
     pub im_public: bool,
     pub website: String,
     pub introduction: String,
+    // IANA timezone of the dates, such as "Europe/Paris"
+    #[serde(default)]
+    pub timezone: Option<String>,
     pub created_at: DateTime,
     pub updated_at: DateTime,
     pub hits: u64,
-    pub status: i8,
+    #[graphql(skip)]
+    pub status: UserStatus,
 }
 
 #[async_graphql::ComplexObject]
 impl User {
+    // the status the account acts as, see UserStatusKind
+    pub async fn status(&self) -> UserStatusKind {
+        self.status.into()
+    }
+
     pub async fn introduction_html(&self) -> String {
         use pulldown_cmark::{Parser, Options, html};
 
@@ -53,20 +78,38 @@ impl User {
         introduction_html
     }
 
-    pub async fn created_at_nyrsq(&self) -> String {
-        self.created_at
-            .to_chrono()
-            .with_timezone(&FixedOffset::east_opt(8 * 3600).unwrap())
-            .format(DTF_YMDHMSZ)
-            .to_string()
+    pub async fn created_at_nyrsq(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+    ) -> String {
+        date_nyrsq(ctx, self.created_at)
+    }
+
+    pub async fn created_at_formatted(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        tz: Option<String>,
+        locale: Option<String>,
+        #[graphql(default)] style: DateStyle,
+    ) -> GqlResult<String> {
+        date_formatted(ctx, self.created_at, tz, locale, style)
+    }
+
+    pub async fn updated_at_nyrsq(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+    ) -> String {
+        date_nyrsq(ctx, self.updated_at)
     }
 
-    pub async fn updated_at_nyrsq(&self) -> String {
-        self.updated_at
-            .to_chrono()
-            .with_timezone(&FixedOffset::east_opt(8 * 3600).unwrap())
-            .format(DTF_YMDHMSZ)
-            .to_string()
+    pub async fn updated_at_formatted(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        tz: Option<String>,
+        locale: Option<String>,
+        #[graphql(default)] style: DateStyle,
+    ) -> GqlResult<String> {
+        date_formatted(ctx, self.updated_at, tz, locale, style)
     }
 
     pub async fn keywords(
@@ -88,19 +131,104 @@ impl User {
     pub async fn projects(
         &self,
         ctx: &async_graphql::Context<'_>,
-        status: i8,
+        status: Option<ProjectStatusKind>,
     ) -> GqlResult<ProjectsResult> {
         let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
         projects_by_user_id(
             db,
+            cfg,
             self._id,
             1,
             String::from("-"),
             String::from("-"),
-            status,
+            status.map(Into::into),
         )
         .await
     }
+
+    pub async fn starred_projects(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        from_page: u32,
+        first_oid: String,
+        last_oid: String,
+    ) -> GqlResult<ProjectsResult> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
+        projects_starred_by_user_id(
+            db, cfg, self._id, from_page, first_oid, last_oid,
+        )
+        .await
+    }
+
+    // statistics by day of all projects, only for the user self
+    pub async fn stats(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        range: StatsRange,
+        token: String,
+    ) -> GqlResult<Stats> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
+        views::services::user_stats(db, cfg, self._id, range, token).await
+    }
+
+    pub async fn followers_count(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+    ) -> GqlResult<u64> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        super::services::followers_count_by_user_id(db, self._id).await
+    }
+
+    pub async fn following_count(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+    ) -> GqlResult<u64> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        super::services::following_count_by_user_id(db, self._id).await
+    }
+
+    pub async fn followers(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        from_page: u32,
+        first_oid: String,
+        last_oid: String,
+    ) -> GqlResult<UsersResult> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
+        super::services::followers_by_user_id(
+            db, cfg, self._id, from_page, first_oid, last_oid,
+        )
+        .await
+    }
+
+    pub async fn following(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        from_page: u32,
+        first_oid: String,
+        last_oid: String,
+    ) -> GqlResult<UsersResult> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
+        super::services::following_by_user_id(
+            db, cfg, self._id, from_page, first_oid, last_oid,
+        )
+        .await
+    }
+
+    pub async fn followed_by_me(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        token: String,
+    ) -> GqlResult<bool> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        let cfg = ctx.data_unchecked::<Config>();
+        super::services::user_followed_by_token(db, cfg, self._id, token).await
+    }
 }
 
 #[derive(async_graphql::InputObject, Serialize, Deserialize)]
@@ -115,10 +243,111 @@ pub struct UserNew {
     pub im_public: bool,
     pub website: String,
     pub introduction: String,
+    pub timezone: Option<String>,
     #[graphql(skip)]
     pub hits: u64,
     #[graphql(skip)]
-    pub status: i8,
+    pub status: UserStatus,
+}
+
+// Status of the account, stored as its integer. Legacy accounts may have
+// any integer, kept as `Other` and compared as their integers, so the
+// accounts of 10 and above are moderators as before.
+#[derive(
+    Serialize, Deserialize, Copy, Clone, Default, Eq, PartialEq, Debug,
+)]
+#[serde(from = "i8", into = "i8")]
+pub enum UserStatus {
+    Banned,
+    #[default]
+    Unactivated,
+    Active,
+    Moderator,
+    Other(i8),
+}
+
+impl UserStatus {
+    // accounts of 1 to 10 could sign in, the others have security problems
+    // unless banned or unactivated
+    pub fn sign_in_allowed(&self) -> bool {
+        (1..=10).contains(&i8::from(*self))
+    }
+}
+
+impl From<UserStatus> for i8 {
+    fn from(status: UserStatus) -> Self {
+        match status {
+            UserStatus::Banned => -1,
+            UserStatus::Unactivated => 0,
+            UserStatus::Active => 1,
+            UserStatus::Moderator => 10,
+            UserStatus::Other(value) => value,
+        }
+    }
+}
+
+impl From<i8> for UserStatus {
+    fn from(value: i8) -> Self {
+        match value {
+            -1 => UserStatus::Banned,
+            0 => UserStatus::Unactivated,
+            1 => UserStatus::Active,
+            10 => UserStatus::Moderator,
+            _ => UserStatus::Other(value),
+        }
+    }
+}
+
+impl PartialOrd for UserStatus {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for UserStatus {
+    fn cmp(&self, other: &Self) -> Ordering {
+        i8::from(*self).cmp(&i8::from(*other))
+    }
+}
+
+// Status of the schema. The legacy statuses are shown as the one they act
+// as: below banned are banned, between active & moderator are active, and
+// above moderator are moderators.
+#[derive(async_graphql::Enum, Copy, Clone, Eq, PartialEq, Debug)]
+#[graphql(name = "UserStatus")]
+pub enum UserStatusKind {
+    Banned,
+    Unactivated,
+    Active,
+    Moderator,
+}
+
+impl From<UserStatus> for UserStatusKind {
+    fn from(status: UserStatus) -> Self {
+        match i8::from(status) {
+            i8::MIN..=-1 => UserStatusKind::Banned,
+            0 => UserStatusKind::Unactivated,
+            1..=9 => UserStatusKind::Active,
+            10..=i8::MAX => UserStatusKind::Moderator,
+        }
+    }
+}
+
+impl From<UserStatusKind> for UserStatus {
+    fn from(kind: UserStatusKind) -> Self {
+        match kind {
+            UserStatusKind::Banned => UserStatus::Banned,
+            UserStatusKind::Unactivated => UserStatus::Unactivated,
+            UserStatusKind::Active => UserStatus::Active,
+            UserStatusKind::Moderator => UserStatus::Moderator,
+        }
+    }
+}
+
+impl From<UserStatus> for Bson {
+    fn from(status: UserStatus) -> Self {
+        Bson::Int32(i8::from(status).into())
+    }
 }
 
 #[derive(async_graphql::SimpleObject, Serialize, Deserialize, Clone, Debug)]
@@ -141,20 +370,38 @@ pub struct Wish {
 
 #[async_graphql::ComplexObject]
 impl Wish {
-    pub async fn created_at_nyrsq(&self) -> String {
-        self.created_at
-            .to_chrono()
-            .with_timezone(&FixedOffset::east_opt(8 * 3600).unwrap())
-            .format(DTF_YMDHMSZ)
-            .to_string()
+    pub async fn created_at_nyrsq(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+    ) -> String {
+        date_nyrsq(ctx, self.created_at)
     }
 
-    pub async fn updated_at_nyrsq(&self) -> String {
-        self.updated_at
-            .to_chrono()
-            .with_timezone(&FixedOffset::east_opt(8 * 3600).unwrap())
-            .format(DTF_YMDHMSZ)
-            .to_string()
+    pub async fn created_at_formatted(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        tz: Option<String>,
+        locale: Option<String>,
+        #[graphql(default)] style: DateStyle,
+    ) -> GqlResult<String> {
+        date_formatted(ctx, self.created_at, tz, locale, style)
+    }
+
+    pub async fn updated_at_nyrsq(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+    ) -> String {
+        date_nyrsq(ctx, self.updated_at)
+    }
+
+    pub async fn updated_at_formatted(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        tz: Option<String>,
+        locale: Option<String>,
+        #[graphql(default)] style: DateStyle,
+    ) -> GqlResult<String> {
+        date_formatted(ctx, self.updated_at, tz, locale, style)
     }
 
     pub async fn user(
@@ -174,3 +421,70 @@ pub struct WishNew {
     #[graphql(skip)]
     pub published: bool,
 }
+
+#[derive(async_graphql::SimpleObject, Serialize, Deserialize, Clone, Debug)]
+pub struct UserFollow {
+    pub _id: ObjectId,
+    pub follower_id: ObjectId,
+    pub user_id: ObjectId,
+    pub created_at: DateTime,
+}
+
+#[derive(async_graphql::SimpleObject, Serialize, Deserialize, Clone, Debug)]
+#[graphql(complex)]
+pub struct FeedItem {
+    pub kind: String,
+    pub user_id: ObjectId,
+    pub project_id: ObjectId,
+    pub comment_id: Option<ObjectId>,
+    pub created_at: DateTime,
+}
+
+#[async_graphql::ComplexObject]
+impl FeedItem {
+    pub async fn created_at_nyrsq(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+    ) -> String {
+        date_nyrsq(ctx, self.created_at)
+    }
+
+    pub async fn created_at_formatted(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        tz: Option<String>,
+        locale: Option<String>,
+        #[graphql(default)] style: DateStyle,
+    ) -> GqlResult<String> {
+        date_formatted(ctx, self.created_at, tz, locale, style)
+    }
+
+    pub async fn user(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+    ) -> GqlResult<User> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        super::services::user_by_id(db, self.user_id).await
+    }
+
+    pub async fn project(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+    ) -> GqlResult<Project> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        projects::services::project_by_id(db, self.project_id).await
+    }
+
+    pub async fn comment(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+    ) -> GqlResult<Option<Comment>> {
+        let db = &ctx.data_unchecked::<DataSource>().db;
+        match self.comment_id {
+            Some(comment_id) => Ok(Some(
+                comments::services::comment_by_id(db, comment_id).await?,
+            )),
+            None => Ok(None),
+        }
+    }
+}
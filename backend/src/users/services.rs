@@ -1,25 +1,37 @@
 use futures::stream::StreamExt;
 use mongodb::{
     Database,
+    options::UpdateOptions,
     bson::{
         oid::ObjectId, DateTime, Document, doc, from_document, to_document,
         from_bson,
     },
 };
-use async_graphql::{Error, ErrorExtensions};
+use async_graphql::ErrorExtensions;
 use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
 use regex::Regex;
 
 use crate::util::{
-    constant::{CFG, GqlResult},
-    cred::{cred_encode, cred_verify, Claims, token_data},
+    config::Config,
+    constant::{GqlResult},
+    error::AppError,
+    cred::{
+        cred_encode, cred_verify, Claims, token_data, ticket_encode,
+        activation_verify,
+    },
     pagination::{
         UsersResult, PageInfo, ResCount, count_pages_and_total,
         calculate_current_filter_skip, find_options,
     },
 };
 
-use super::models::{User, UserNew, SignInfo, Wish, WishNew};
+use crate::projects::models::ProjectStatus;
+use crate::comments::services::COMMENT_STATUS_VISIBLE;
+use crate::idempotency::services::{idempotency_key_check, idempotency_key_settle};
+use super::models::{
+    User, UserNew, UserStatus, SignInfo, Wish, WishNew, UserFollow, FeedItem,
+    FEED_KIND_PROJECT, FEED_KIND_STAR, FEED_KIND_COMMENT,
+};
 
 const USERS_STUFF: &str = "users";
 
@@ -31,6 +43,7 @@ pub async fn user_register(
 
     user_new.email = user_new.email.trim().to_lowercase();
     user_new.username = user_new.username.trim().to_lowercase();
+    user_new.timezone = timezone_check(user_new.timezone)?;
 
     if user_by_email(db, user_new.email.to_owned()).await.is_err()
         && user_by_username(db, user_new.username.to_owned()).await.is_err()
@@ -42,18 +55,18 @@ pub async fn user_register(
         new_document.insert("created_at", now);
         new_document.insert("updated_at", now);
 
-        let user_res =
-            coll.insert_one(new_document, None).await.expect("写入未成功");
+        let user_res = coll.insert_one(new_document, None).await?;
         let user_id = from_bson(user_res.inserted_id)?;
 
         user_by_id(db, user_id).await
     } else {
-        Err(Error::new("register-failed-username-email-exists"))
+        Err(AppError::RegisterExists.extend())
     }
 }
 
 pub async fn user_sign_in(
     db: &Database,
+    cfg: &Config,
     signature: String,
     password: String,
 ) -> GqlResult<SignInfo> {
@@ -69,14 +82,13 @@ pub async fn user_sign_in(
 
     if let Ok(user) = user_res {
         match user.status {
-            1..=10 => {
+            status if status.sign_in_allowed() => {
                 let is_verified =
                     cred_verify(&user.username, &password, &user.cred).await;
                 if is_verified {
-                    let site_kid = CFG.get("SITE_KID").unwrap();
-                    let site_key = CFG.get("SITE_KEY").unwrap().as_bytes();
-                    let claim_exp =
-                        CFG.get("CLAIM_EXP").unwrap().parse::<usize>()?;
+                    let site_kid = &cfg.site_kid;
+                    let site_key = cfg.site_key.as_bytes();
+                    let claim_exp = cfg.claim_exp;
 
                     let mut header = Header::default();
                     header.kid = Some(String::from(site_kid));
@@ -100,16 +112,18 @@ pub async fn user_sign_in(
 
                     Ok(sign_info)
                 } else {
-                    Err(Error::new("sign-in-incorrect"))
+                    Err(AppError::SignInIncorrect.extend())
                 }
             }
-            0 => Err(Error::new("sign-in-not-activation")
-                .extend_with(|_, e| e.set("user_id", user._id.to_string()))),
-            -1 => Err(Error::new("sign-in-banned")),
-            _ => Err(Error::new("sign-in-security-problem")),
+            UserStatus::Unactivated => Err(AppError::SignInNotActivation {
+                user_id: user._id.to_string(),
+            }
+            .extend()),
+            UserStatus::Banned => Err(AppError::SignInBanned.extend()),
+            _ => Err(AppError::SignInSecurityProblem.extend()),
         }
     } else {
-        Err(Error::new("sign-in-not-registration"))
+        Err(AppError::SignInNotRegistration.extend())
     }
 }
 
@@ -117,32 +131,88 @@ pub async fn user_sign_in(
 pub async fn user_by_id(db: &Database, id: ObjectId) -> GqlResult<User> {
     let coll = db.collection::<Document>("users");
 
-    let user_document = coll
-        .find_one(doc! {"_id": id}, None)
-        .await
-        .expect("账户不存在")
-        .unwrap();
+    let user_document = coll.find_one(doc! {"_id": id}, None).await?;
 
-    let user: User = from_document(user_document)?;
-    Ok(user)
+    match user_document {
+        Some(document) => Ok(from_document(document)?),
+        None => Err(AppError::UserNotFound.extend()),
+    }
+}
+
+// Set the status of the user, by moderators only, and never their own.
+pub async fn user_set_status(
+    db: &Database,
+    cfg: &Config,
+    user_id: ObjectId,
+    status: UserStatus,
+    token: String,
+) -> GqlResult<User> {
+    let moderator = user_by_token(db, cfg, token).await?;
+    if moderator.status < UserStatus::Moderator || moderator._id == user_id {
+        return Err(AppError::NoPermission.extend());
+    }
+
+    user_status_set(db, user_id, status).await
 }
 
+// Activate the account from the link of the activation email, whose code is
+// signed for the account. Activating an active account again is harmless,
+// but a banned one stays banned.
+pub async fn user_activate(
+    db: &Database,
+    cfg: &Config,
+    user_id: ObjectId,
+    code: String,
+) -> GqlResult<User> {
+    if !activation_verify(&user_id.to_hex(), &code, &cfg.activation_key).await {
+        return Err(AppError::ActivationCodeInvalid.extend());
+    }
+
+    let user = user_by_id(db, user_id).await?;
+
+    match user.status {
+        UserStatus::Unactivated => {
+            user_status_set(db, user_id, UserStatus::Active).await
+        }
+        status if status.sign_in_allowed() => Ok(user),
+        UserStatus::Banned => Err(AppError::SignInBanned.extend()),
+        _ => Err(AppError::SignInSecurityProblem.extend()),
+    }
+}
+
+// Deprecated alias of user_activate, only the "status" field is accepted,
+// and only to activate the account with the code of the activation email.
 pub async fn user_update_one_field_by_id(
     db: &Database,
+    cfg: &Config,
     user_id: ObjectId,
     field_name: String,
     field_val: String,
+    code: String,
+) -> GqlResult<User> {
+    match field_name.as_str() {
+        "status" => match field_val.parse::<i8>().map(UserStatus::from) {
+            Ok(UserStatus::Active) => {
+                user_activate(db, cfg, user_id, code).await
+            }
+            _ => Err(AppError::StatusInvalid.extend()),
+        },
+        _ => Err(AppError::FieldInvalid.extend()),
+    }
+}
+
+async fn user_status_set(
+    db: &Database,
+    user_id: ObjectId,
+    status: UserStatus,
 ) -> GqlResult<User> {
     let coll = db.collection::<Document>("users");
 
     let query_doc = doc! {"_id": user_id};
-    let update_doc = match field_name.as_str() {
-        "status" => {
-            doc! {"$set": {field_name: field_val.parse::<i32>()?}}
-        }
-        _ => doc! {},
-    };
-
+    let update_doc = doc! {"$set": {
+        "status": status,
+        "updated_at": DateTime::now()
+    }};
     coll.update_one(query_doc, update_doc, None).await?;
 
     user_by_id(db, user_id).await
@@ -157,7 +227,7 @@ pub async fn user_by_email(db: &Database, email: String) -> GqlResult<User> {
         let user: User = from_document(user_document.unwrap())?;
         Ok(user)
     } else {
-        Err(Error::new(format!("{} - 未注册", email)))
+        Err(AppError::UserNotFound.extend())
     }
 }
 
@@ -174,18 +244,44 @@ pub async fn user_by_username(
         let user: User = from_document(user_document.unwrap())?;
         Ok(user)
     } else {
-        Err(Error::new(format!("{} - 未注册", username)))
+        Err(AppError::UserNotFound.extend())
     }
 }
 
+// get user info by token
+pub async fn user_by_token(
+    db: &Database,
+    cfg: &Config,
+    token: String,
+) -> GqlResult<User> {
+    let token_data = token_data(&token, &cfg.site_key).await;
+    if let Ok(data) = token_data {
+        user_by_email(db, data.claims.email).await
+    } else {
+        Err(AppError::TokenInvalid.extend())
+    }
+}
+
+// ticket of the notifications subscription of the signed-in user
+pub async fn user_subscription_ticket(
+    db: &Database,
+    cfg: &Config,
+    token: String,
+) -> GqlResult<String> {
+    let user = user_by_token(db, cfg, token).await?;
+
+    Ok(ticket_encode(&user._id.to_hex(), &cfg.site_key).await?)
+}
+
 // Change user password
 pub async fn user_change_password(
     db: &Database,
+    cfg: &Config,
     pwd_cur: String,
     pwd_new: String,
     token: String,
 ) -> GqlResult<User> {
-    let token_data = token_data(&token).await;
+    let token_data = token_data(&token, &cfg.site_key).await;
     if let Ok(data) = token_data {
         let email = data.claims.email;
         let user_res = user_by_email(db, email).await;
@@ -199,28 +295,28 @@ pub async fn user_change_password(
                     doc! {"$set": {"cred": &user.cred}},
                     None,
                 )
-                .await
-                .expect("更新未成功");
+                .await?;
 
                 Ok(user)
             } else {
-                Err(Error::new("密码验证失败"))
+                Err(AppError::PasswordIncorrect.extend())
             }
         } else {
-            Err(Error::new("账户未注册"))
+            Err(AppError::UserNotFound.extend())
         }
     } else {
-        Err(Error::new("令牌验证失败"))
+        Err(AppError::TokenInvalid.extend())
     }
 }
 
 // update user profile
 pub async fn user_update_profile(
     db: &Database,
+    cfg: &Config,
     user_new: UserNew,
     token: String,
 ) -> GqlResult<User> {
-    let token_data = token_data(&token).await;
+    let token_data = token_data(&token, &cfg.site_key).await;
     if let Ok(data) = token_data {
         let email = data.claims.email;
         let user_res = user_by_email(db, email).await;
@@ -229,6 +325,7 @@ pub async fn user_update_profile(
 
             user.email = user_new.email.to_lowercase();
             user.username = user_new.username.to_lowercase();
+            user.timezone = timezone_check(user_new.timezone)?;
 
             let user_document = to_document(&user)?;
 
@@ -237,47 +334,266 @@ pub async fn user_update_profile(
                 user_document,
                 None,
             )
-            .await
-            .expect("更新未成功");
+            .await?;
 
             Ok(user)
         } else {
-            Err(Error::new("账户未注册"))
+            Err(AppError::UserNotFound.extend())
         }
     } else {
-        Err(Error::new("令牌验证失败"))
+        Err(AppError::TokenInvalid.extend())
     }
 }
 
 // Get all Users
 pub async fn users(
     db: &Database,
+    cfg: &Config,
     from_page: u32,
     first_oid: String,
     last_oid: String,
-    status: i8,
+    status: UserStatus,
 ) -> GqlResult<UsersResult> {
     let coll = db.collection::<Document>("users");
 
-    let mut filter_doc = doc! {
-        "status": {
-            "$gte": status as i32,
-            "$lte": 12
+    let mut filter_doc = doc! {"status": {"$gte": status}};
+
+    let (pages_count, total_count) = count_pages_and_total(
+        &coll,
+        Some(filter_doc.clone()),
+        None,
+        cfg.page_size,
+    )
+    .await?;
+    let (current_page, skip_x) = calculate_current_filter_skip(
+        from_page,
+        first_oid,
+        last_oid,
+        &mut filter_doc,
+    )
+    .await?;
+
+    let sort_doc = doc! {"_id": -1};
+    let find_options =
+        find_options(Some(sort_doc), skip_x, cfg.page_size).await;
+
+    let mut cursor = coll.find(filter_doc, find_options).await?;
+
+    let mut users: Vec<User> = vec![];
+    while let Some(result) = cursor.next().await {
+        match result {
+            Ok(document) => {
+                let user = from_document(document)?;
+                users.push(user);
+            }
+            Err(error) => {
+                println!("\n\n\n{}\n\n\n", error);
+            }
+        }
+    }
+
+    let users_result = UsersResult {
+        page_info: PageInfo {
+            current_stuff: Some(String::from(USERS_STUFF)),
+            current_page: Some(current_page),
+            first_cursor: match users.first() {
+                Some(user) => Some(user._id),
+                _ => None,
+            },
+            last_cursor: match users.last() {
+                Some(user) => Some(user._id),
+                _ => None,
+            },
+            has_previous_page: current_page > 1,
+            has_next_page: current_page < pages_count,
+        },
+        res_count: ResCount {
+            pages_count: Some(pages_count),
+            total_count: Some(total_count),
+        },
+        current_items: users,
+    };
+
+    Ok(users_result)
+}
+
+// follow user by the signed-in user
+pub async fn user_follow(
+    db: &Database,
+    cfg: &Config,
+    user_id: ObjectId,
+    token: String,
+) -> GqlResult<User> {
+    let follower = user_by_token(db, cfg, token).await?;
+    let user = user_by_id(db, user_id).await?;
+
+    if follower._id == user._id {
+        return Err(AppError::FollowSelf.extend());
+    }
+
+    // the unique index on (follower_id, user_id) keeps one follow per pair.
+    let coll = db.collection::<Document>("users_follows");
+    coll.update_one(
+        doc! {"follower_id": follower._id, "user_id": user._id},
+        doc! {"$setOnInsert": {"created_at": DateTime::now()}},
+        UpdateOptions::builder().upsert(true).build(),
+    )
+    .await?;
+
+    Ok(user)
+}
+
+// unfollow user by the signed-in user
+pub async fn user_unfollow(
+    db: &Database,
+    cfg: &Config,
+    user_id: ObjectId,
+    token: String,
+) -> GqlResult<User> {
+    let follower = user_by_token(db, cfg, token).await?;
+
+    let coll = db.collection::<Document>("users_follows");
+    coll.delete_one(
+        doc! {"follower_id": follower._id, "user_id": user_id},
+        None,
+    )
+    .await?;
+
+    user_by_id(db, user_id).await
+}
+
+// check if the user is followed by the user of token
+pub async fn user_followed_by_token(
+    db: &Database,
+    cfg: &Config,
+    user_id: ObjectId,
+    token: String,
+) -> GqlResult<bool> {
+    if let Ok(follower) = user_by_token(db, cfg, token).await {
+        let coll = db.collection::<Document>("users_follows");
+        let follow_document = coll
+            .find_one(
+                doc! {"follower_id": follower._id, "user_id": user_id},
+                None,
+            )
+            .await?;
+
+        Ok(follow_document.is_some())
+    } else {
+        Ok(false)
+    }
+}
+
+// count users who follow user_id
+pub async fn followers_count_by_user_id(
+    db: &Database,
+    user_id: ObjectId,
+) -> GqlResult<u64> {
+    let coll = db.collection::<Document>("users_follows");
+    let count = coll.count_documents(doc! {"user_id": user_id}, None).await?;
+
+    Ok(count)
+}
+
+// count users followed by user_id
+pub async fn following_count_by_user_id(
+    db: &Database,
+    user_id: ObjectId,
+) -> GqlResult<u64> {
+    let coll = db.collection::<Document>("users_follows");
+    let count =
+        coll.count_documents(doc! {"follower_id": user_id}, None).await?;
+
+    Ok(count)
+}
+
+// Get all users who follow user_id
+pub async fn followers_by_user_id(
+    db: &Database,
+    cfg: &Config,
+    user_id: ObjectId,
+    from_page: u32,
+    first_oid: String,
+    last_oid: String,
+) -> GqlResult<UsersResult> {
+    let follows = follows_by_filter(db, doc! {"user_id": user_id}).await?;
+    let follower_ids =
+        follows.into_iter().map(|follow| follow.follower_id).collect();
+
+    users_by_ids(db, cfg, follower_ids, from_page, first_oid, last_oid).await
+}
+
+// Get all users followed by user_id
+pub async fn following_by_user_id(
+    db: &Database,
+    cfg: &Config,
+    user_id: ObjectId,
+    from_page: u32,
+    first_oid: String,
+    last_oid: String,
+) -> GqlResult<UsersResult> {
+    let follows = follows_by_filter(db, doc! {"follower_id": user_id}).await?;
+    let user_ids = follows.into_iter().map(|follow| follow.user_id).collect();
+
+    users_by_ids(db, cfg, user_ids, from_page, first_oid, last_oid).await
+}
+
+async fn follows_by_filter(
+    db: &Database,
+    filter_doc: Document,
+) -> GqlResult<Vec<UserFollow>> {
+    let coll = db.collection::<Document>("users_follows");
+    let mut cursor = coll.find(filter_doc, None).await?;
+
+    let mut follows: Vec<UserFollow> = vec![];
+    while let Some(result) = cursor.next().await {
+        match result {
+            Ok(document) => {
+                let follow = from_document(document)?;
+                follows.push(follow);
+            }
+            Err(error) => {
+                println!("\n\n\n{}\n\n\n", error);
+            }
         }
+    }
+
+    Ok(follows)
+}
+
+async fn users_by_ids(
+    db: &Database,
+    cfg: &Config,
+    user_ids: Vec<ObjectId>,
+    from_page: u32,
+    first_oid: String,
+    last_oid: String,
+) -> GqlResult<UsersResult> {
+    let coll = db.collection::<Document>("users");
+
+    let mut filter_doc = doc! {
+        "_id": {"$in": user_ids},
+        "status": {"$gte": UserStatus::Active}
     };
 
-    let (pages_count, total_count) =
-        count_pages_and_total(&coll, Some(filter_doc.clone()), None).await;
+    let (pages_count, total_count) = count_pages_and_total(
+        &coll,
+        Some(filter_doc.clone()),
+        None,
+        cfg.page_size,
+    )
+    .await?;
     let (current_page, skip_x) = calculate_current_filter_skip(
         from_page,
         first_oid,
         last_oid,
         &mut filter_doc,
     )
-    .await;
+    .await?;
 
     let sort_doc = doc! {"_id": -1};
-    let find_options = find_options(Some(sort_doc), skip_x).await;
+    let find_options =
+        find_options(Some(sort_doc), skip_x, cfg.page_size).await;
 
     let mut cursor = coll.find(filter_doc, find_options).await?;
 
@@ -319,8 +635,145 @@ pub async fn users(
     Ok(users_result)
 }
 
-// Create new wish
-pub async fn wish_new(db: &Database, wish_new: WishNew) -> GqlResult<Wish> {
+// Activity feed of the signed-in user, merged on read from the new projects,
+// stars and comments of the users followed.
+pub async fn feed_by_token(
+    db: &Database,
+    cfg: &Config,
+    token: String,
+    from_page: u32,
+) -> GqlResult<Vec<FeedItem>> {
+    let user = user_by_token(db, cfg, token).await?;
+
+    let follows = follows_by_filter(db, doc! {"follower_id": user._id}).await?;
+    let user_ids: Vec<ObjectId> =
+        follows.into_iter().map(|follow| follow.user_id).collect();
+    if user_ids.is_empty() {
+        return Ok(vec![]);
+    }
+
+    // Every source is sorted by time and holds only items of public projects,
+    // so the first `from_page` pages of each source are enough to build the
+    // requested page of the merge.
+    let page_size = cfg.page_size as i64;
+    let from_page = from_page.max(1) as i64;
+    let limit = from_page * page_size;
+
+    let public_project = vec![
+        doc! {"$lookup": {
+            "from": "projects",
+            "localField": "project_id",
+            "foreignField": "_id",
+            "as": "project"
+        }},
+        doc! {"$match": {"project.status": {"$gte": ProjectStatus::Published}}},
+    ];
+
+    let mut feed_items: Vec<FeedItem> = vec![];
+    let sources = [
+        (
+            FEED_KIND_PROJECT,
+            "projects",
+            doc! {
+                "user_id": {"$in": user_ids.clone()},
+                "status": {"$gte": ProjectStatus::Published}
+            },
+        ),
+        (
+            FEED_KIND_STAR,
+            "project_stars",
+            doc! {"user_id": {"$in": user_ids.clone()}},
+        ),
+        (
+            FEED_KIND_COMMENT,
+            "comments",
+            doc! {
+                "user_id": {"$in": user_ids.clone()},
+                "status": {"$gte": COMMENT_STATUS_VISIBLE}
+            },
+        ),
+    ];
+    for (kind, coll_name, filter_doc) in sources {
+        let mut pipeline = vec![
+            doc! {"$match": filter_doc},
+            doc! {"$sort": {"created_at": -1}},
+        ];
+        if kind != FEED_KIND_PROJECT {
+            pipeline.extend(public_project.clone());
+        }
+        pipeline.push(doc! {"$limit": limit});
+
+        let coll = db.collection::<Document>(coll_name);
+        let mut cursor = coll.aggregate(pipeline, None).await?;
+
+        while let Some(result) = cursor.next().await {
+            match result {
+                Ok(document) => {
+                    let feed_item = FeedItem {
+                        kind: String::from(kind),
+                        user_id: document.get_object_id("user_id")?,
+                        project_id: match kind {
+                            FEED_KIND_PROJECT => {
+                                document.get_object_id("_id")?
+                            }
+                            _ => document.get_object_id("project_id")?,
+                        },
+                        comment_id: match kind {
+                            FEED_KIND_COMMENT => {
+                                Some(document.get_object_id("_id")?)
+                            }
+                            _ => None,
+                        },
+                        created_at: *document.get_datetime("created_at")?,
+                    };
+                    feed_items.push(feed_item);
+                }
+                Err(error) => {
+                    println!("\n\n\n{}\n\n\n", error);
+                }
+            }
+        }
+    }
+
+    feed_items.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    let feed_items = feed_items
+        .into_iter()
+        .skip(((from_page - 1) * page_size) as usize)
+        .take(page_size as usize)
+        .collect();
+
+    Ok(feed_items)
+}
+
+// Create new wish, a retry with the same idempotency key gets the wish
+// created at first
+pub async fn wish_new(
+    db: &Database,
+    wish_new: WishNew,
+    idempotency_key: Option<String>,
+) -> GqlResult<Wish> {
+    let user_id = Some(wish_new.user_id);
+    if let Some(wish_id) = idempotency_key_check(
+        db,
+        "wishes",
+        user_id,
+        &idempotency_key,
+        &wish_new,
+    )
+    .await?
+    {
+        return wish_by_id(db, wish_id).await;
+    }
+
+    let wish_res = wish_insert(db, wish_new).await;
+    let wish_id = wish_res.as_ref().ok().map(|wish| wish._id);
+    idempotency_key_settle(db, "wishes", user_id, &idempotency_key, wish_id)
+        .await?;
+
+    wish_res
+}
+
+async fn wish_insert(db: &Database, wish_new: WishNew) -> GqlResult<Wish> {
     let coll = db.collection::<Document>("wishes");
 
     let exist_document = coll
@@ -336,13 +789,12 @@ pub async fn wish_new(db: &Database, wish_new: WishNew) -> GqlResult<Wish> {
         new_document.insert("created_at", now);
         new_document.insert("updated_at", now);
 
-        let wish_res =
-            coll.insert_one(new_document, None).await.expect("写入未成功");
+        let wish_res = coll.insert_one(new_document, None).await?;
         let wish_id = from_bson(wish_res.inserted_id)?;
 
         wish_by_id(db, wish_id).await
     } else {
-        Err(Error::new("记录已存在"))
+        Err(AppError::RecordExists.extend())
     }
 }
 
@@ -350,14 +802,12 @@ pub async fn wish_new(db: &Database, wish_new: WishNew) -> GqlResult<Wish> {
 async fn wish_by_id(db: &Database, id: ObjectId) -> GqlResult<Wish> {
     let coll = db.collection::<Document>("wishes");
 
-    let wish_document = coll
-        .find_one(doc! {"_id": id}, None)
-        .await
-        .expect("查询未成功")
-        .unwrap();
+    let wish_document = coll.find_one(doc! {"_id": id}, None).await?;
 
-    let wish: Wish = from_document(wish_document)?;
-    Ok(wish)
+    match wish_document {
+        Some(document) => Ok(from_document(document)?),
+        None => Err(AppError::WishNotFound.extend()),
+    }
 }
 
 // get all wishes
@@ -414,6 +864,17 @@ async fn wish_one(db: &Database, match_doc: Document) -> GqlResult<Wish> {
         let wish = from_document(document_res?)?;
         Ok(wish)
     } else {
-        Err(Error::new("查询未成功"))
+        Err(AppError::WishNotFound.extend())
+    }
+}
+
+// timezone of the profile, an IANA timezone or none
+fn timezone_check(timezone: Option<String>) -> GqlResult<Option<String>> {
+    match timezone.as_deref().map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(timezone) => match timezone.parse::<chrono_tz::Tz>() {
+            Ok(tz) => Ok(Some(tz.name().to_string())),
+            Err(_) => Err(AppError::TimezoneInvalid.extend()),
+        },
     }
 }
@@ -19,10 +19,87 @@ pub async fn slugify(str: &str) -> String {
     slug
 }
 
-// bson::DateTime -> Y-M-D
-pub async fn bson_dt_nyr(dt: mongodb::bson::DateTime) -> String {
+// Markdown -> HTML, for contents of projects
+pub async fn md2html(content: &str) -> String {
+    use pulldown_cmark::{Parser, html};
+
+    let parser = Parser::new_ext(content, md_options());
+
+    let mut content_html = String::new();
+    html::push_html(&mut content_html, parser);
+
+    content_html
+}
+
+// Markdown -> HTML, for contents written by any signed-in user, such as
+// comments & descriptions of studios. Raw HTML is kept as escaped text, and
+// links & images with script urls are pointed to nowhere, so the output is
+// safe to render unescaped.
+pub async fn md2html_safe(content: &str) -> String {
+    use pulldown_cmark::{Parser, Event, Tag, CowStr, html};
+
+    fn safe_url(url: CowStr) -> CowStr {
+        // browsers skip whitespace & controls inside the scheme
+        let scheme: String = url
+            .chars()
+            .filter(|c| !c.is_ascii_whitespace() && !c.is_ascii_control())
+            .collect::<String>()
+            .to_lowercase();
+        if scheme.starts_with("javascript:")
+            || scheme.starts_with("vbscript:")
+            || scheme.starts_with("data:")
+        {
+            CowStr::from("#")
+        } else {
+            url
+        }
+    }
+
+    let parser =
+        Parser::new_ext(content, md_options()).map(|event| match event {
+            Event::Html(raw) | Event::InlineHtml(raw) => Event::Text(raw),
+            Event::Start(Tag::Link(kind, url, title)) => {
+                Event::Start(Tag::Link(kind, safe_url(url), title))
+            }
+            Event::End(Tag::Link(kind, url, title)) => {
+                Event::End(Tag::Link(kind, safe_url(url), title))
+            }
+            Event::Start(Tag::Image(kind, url, title)) => {
+                Event::Start(Tag::Image(kind, safe_url(url), title))
+            }
+            Event::End(Tag::Image(kind, url, title)) => {
+                Event::End(Tag::Image(kind, safe_url(url), title))
+            }
+            event => event,
+        });
+
+    let mut content_html = String::new();
+    html::push_html(&mut content_html, parser);
+
+    content_html
+}
+
+fn md_options() -> pulldown_cmark::Options {
+    use pulldown_cmark::Options;
+
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_HEADING_ATTRIBUTES);
+    // options.insert(Options::ENABLE_TABLES);
+    options.insert(Options::ENABLE_FOOTNOTES);
+    // options.insert(Options::ENABLE_STRIKETHROUGH);
+    options.insert(Options::ENABLE_TASKLISTS);
+    options.insert(Options::ENABLE_SMART_PUNCTUATION);
+
+    options
+}
+
+// bson::DateTime -> Y-M-D in the timezone
+pub async fn bson_dt_nyr(
+    dt: mongodb::bson::DateTime,
+    tz: chrono_tz::Tz,
+) -> String {
     dt.to_chrono()
-        .with_timezone(&chrono::FixedOffset::east_opt(8 * 3600).unwrap())
+        .with_timezone(&tz)
         .format(crate::util::constant::DTF_YMD)
         .to_string()
 }
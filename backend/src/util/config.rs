@@ -0,0 +1,189 @@
+use std::{collections::HashMap, fmt, fs, str::FromStr};
+
+use dotenv::dotenv;
+
+// Configuration of the backend. Values are taken from the environment, which
+// includes the .env file, and then from an optional TOML file with the same
+// keys in lowercase, such as `page_size = 16`. The TOML file is CONFIG_FILE,
+// or config.toml in the working directory if it exists.
+#[derive(Clone, Debug)]
+pub struct Config {
+    pub addr: String,
+    pub port: u16,
+    pub log_level: String,
+    pub timezone: String,
+
+    pub site_kid: String,
+    pub site_key: String,
+    pub visitor_key: String,
+    pub activation_key: String,
+    pub claim_exp: usize,
+
+    pub gql_uri: String,
+    pub gql_ver: String,
+    pub giql_ver: String,
+    pub gql_ws_ver: String,
+
+    pub mongodb_uri: String,
+    pub mongodb_name: String,
+    pub page_size: u32,
+
+    pub trash_retention_days: i64,
+    pub view_dedup_minutes: u64,
+    pub idempotency_key_hours: u64,
+}
+
+// All problems of the configuration, reported together at startup
+#[derive(Debug)]
+pub struct ConfigError(Vec<String>);
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Invalid configuration:")?;
+        for error in &self.0 {
+            writeln!(f, "  - {}", error)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Config {
+    // timezone of the dates, when the request does not ask for one
+    pub fn tz(&self) -> chrono_tz::Tz {
+        self.timezone.parse().unwrap_or(chrono_tz::UTC)
+    }
+
+    pub fn load() -> Result<Config, ConfigError> {
+        let mut sources = Sources::load();
+
+        let config = Config {
+            addr: sources.get("ADDR"),
+            port: sources.get("PORT"),
+            log_level: sources.get("LOG_LEVEL"),
+            timezone: sources.get("TIMEZONE"),
+
+            site_kid: sources.get("SITE_KID"),
+            site_key: sources.get("SITE_KEY"),
+            visitor_key: sources.get("VISITOR_KEY"),
+            activation_key: sources.get("ACTIVATION_KEY"),
+            claim_exp: sources.get("CLAIM_EXP"),
+
+            gql_uri: sources.get("GQL_URI"),
+            gql_ver: sources.get("GQL_VER"),
+            giql_ver: sources.get("GIQL_VER"),
+            gql_ws_ver: sources.get("GQL_WS_VER"),
+
+            mongodb_uri: sources.get("MONGODB_URI"),
+            mongodb_name: sources.get("MONGODB_NAME"),
+            page_size: sources.get("PAGE_SIZE"),
+
+            trash_retention_days: sources.get("TRASH_RETENTION_DAYS"),
+            view_dedup_minutes: sources.get("VIEW_DEDUP_MINUTES"),
+            idempotency_key_hours: sources.get("IDEMPOTENCY_KEY_HOURS"),
+        };
+
+        if femme::LevelFilter::from_str(&config.log_level).is_err() {
+            sources.invalid("LOG_LEVEL", "not a log level");
+        }
+        if config.timezone.parse::<chrono_tz::Tz>().is_err() {
+            sources.invalid("TIMEZONE", "not an IANA timezone");
+        }
+        if config.visitor_key.trim().is_empty() {
+            sources.invalid("VISITOR_KEY", "must not be empty");
+        }
+        if config.activation_key.trim().is_empty() {
+            sources.invalid("ACTIVATION_KEY", "must not be empty");
+        }
+        if config.page_size == 0 {
+            sources.invalid("PAGE_SIZE", "must be greater than 0");
+        }
+        if config.trash_retention_days < 1 {
+            sources.invalid("TRASH_RETENTION_DAYS", "must be at least 1");
+        }
+
+        if sources.errors.is_empty() {
+            Ok(config)
+        } else {
+            Err(ConfigError(sources.errors))
+        }
+    }
+}
+
+// values of the TOML file, and the errors found while reading the values
+struct Sources {
+    file_values: HashMap<String, String>,
+    errors: Vec<String>,
+}
+
+impl Sources {
+    fn load() -> Self {
+        dotenv().ok();
+
+        let mut sources =
+            Sources { file_values: HashMap::new(), errors: vec![] };
+
+        let (file_path, required) = match dotenv::var("CONFIG_FILE") {
+            Ok(file_path) => (file_path, true),
+            Err(_) => (String::from("config.toml"), false),
+        };
+        match fs::read_to_string(&file_path) {
+            Ok(content) => match content.parse::<toml::Table>() {
+                Ok(table) => {
+                    for (key, value) in table {
+                        let value = match value {
+                            toml::Value::String(value) => value,
+                            value => value.to_string(),
+                        };
+                        sources.file_values.insert(key.to_uppercase(), value);
+                    }
+                }
+                Err(error) => {
+                    sources.errors.push(format!("{}: {}", file_path, error))
+                }
+            },
+            Err(error) if required => {
+                sources.errors.push(format!("{}: {}", file_path, error))
+            }
+            Err(_) => {}
+        }
+
+        sources
+    }
+
+    // The value of the key, the environment first. A missing or invalid value
+    // is recorded as an error, and the default is returned in its place.
+    fn get<T>(&mut self, key: &str) -> T
+    where
+        T: FromStr + Default,
+        T::Err: fmt::Display,
+    {
+        let value = dotenv::var(key)
+            .ok()
+            .or_else(|| self.file_values.get(key).cloned());
+
+        match value {
+            Some(value) => match value.trim().parse::<T>() {
+                Ok(value) => value,
+                Err(error) => {
+                    self.invalid(key, &error.to_string());
+                    T::default()
+                }
+            },
+            None => {
+                self.errors.push(format!("{} is not set", key));
+                T::default()
+            }
+        }
+    }
+
+    // only the first error of each key is kept
+    fn invalid(&mut self, key: &str, reason: &str) {
+        let prefix = format!("{} ", key);
+        if !self.errors.iter().any(|error| error.starts_with(&prefix)) {
+            self.errors.push(format!("{} is invalid: {}", key, reason));
+        }
+    }
+}
@@ -1,10 +1,14 @@
 use std::num::NonZeroU32;
-use ring::{digest, pbkdf2};
-use base64::{Engine, engine::general_purpose::STANDARD};
+use ring::{digest, hmac, pbkdf2};
+use base64::{
+    Engine,
+    engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD},
+};
 use serde::{Serialize, Deserialize};
-use jsonwebtoken::{TokenData, Algorithm, DecodingKey, Validation, errors::Error};
-
-use crate::util::constant::CFG;
+use jsonwebtoken::{
+    TokenData, Algorithm, DecodingKey, EncodingKey, Header, Validation,
+    errors::Error,
+};
 
 static PBKDF2_ALG: pbkdf2::Algorithm = pbkdf2::PBKDF2_HMAC_SHA256;
 
@@ -50,7 +54,11 @@ pub async fn cred_verify(
     actual_cred: &str,
 ) -> bool {
     let salt = salt(username).await;
-    let actual_cred_decode = STANDARD.decode(actual_cred.as_bytes()).unwrap();
+    // a broken credential never matches
+    let actual_cred_decode = match STANDARD.decode(actual_cred.as_bytes()) {
+        Ok(cred_decode) => cred_decode,
+        Err(_) => return false,
+    };
 
     pbkdf2::verify(
         PBKDF2_ALG,
@@ -62,6 +70,48 @@ pub async fn cred_verify(
     .is_ok()
 }
 
+// One-way digest of the visitor fingerprint, so that addresses and agents of
+// visitors are never kept as they are
+pub async fn fingerprint_encode(fingerprint: &str, site_key: &str) -> String {
+    let mut context = digest::Context::new(&digest::SHA256);
+    context.update(site_key.as_bytes());
+    context.update(fingerprint.as_bytes());
+
+    STANDARD.encode(context.finish())
+}
+
+// The fingerprint is signed by the frontend with the shared visitor key, as
+// it is taken from the request of the browser, not the one of the backend
+pub async fn fingerprint_verify(
+    fingerprint: &str,
+    signature: &str,
+    visitor_key: &str,
+) -> bool {
+    let signature_decode = match STANDARD.decode(signature.as_bytes()) {
+        Ok(signature_decode) => signature_decode,
+        Err(_) => return false,
+    };
+
+    let key = hmac::Key::new(hmac::HMAC_SHA256, visitor_key.as_bytes());
+    hmac::verify(&key, fingerprint.as_bytes(), &signature_decode).is_ok()
+}
+
+// The code of the activation link is signed by the frontend, which sends the
+// activation email, so only the owner of the email could activate the account
+pub async fn activation_verify(
+    user_id: &str,
+    code: &str,
+    activation_key: &str,
+) -> bool {
+    let code_decode = match URL_SAFE_NO_PAD.decode(code.as_bytes()) {
+        Ok(code_decode) => code_decode,
+        Err(_) => return false,
+    };
+
+    let key = hmac::Key::new(hmac::HMAC_SHA256, activation_key.as_bytes());
+    hmac::verify(&key, user_id.as_bytes(), &code_decode).is_ok()
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
     pub email: String,
@@ -69,14 +119,60 @@ pub struct Claims {
     pub exp: usize,
 }
 
-pub async fn token_data(token: &str) -> Result<TokenData<Claims>, Error> {
-    let site_key = CFG.get("SITE_KEY").unwrap().as_bytes();
-
+pub async fn token_data(
+    token: &str,
+    site_key: &str,
+) -> Result<TokenData<Claims>, Error> {
     let data = jsonwebtoken::decode::<Claims>(
         token,
-        &DecodingKey::from_secret(site_key),
+        &DecodingKey::from_secret(site_key.as_bytes()),
         &Validation::new(Algorithm::HS512),
     );
 
     data
 }
+
+// audience & lifetime of the tickets of subscriptions
+const TICKET_AUD: &str = "subscriptions";
+const TICKET_SECS: i64 = 60;
+
+// Claims of a ticket of the notifications subscription. The browser opens
+// the websocket to the backend itself, so it gets a ticket which expires
+// soon instead of the session token, which never leaves the cookie.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TicketClaims {
+    pub sub: String,
+    pub aud: String,
+    pub exp: usize,
+}
+
+pub async fn ticket_encode(
+    user_id: &str,
+    site_key: &str,
+) -> Result<String, Error> {
+    let claims = TicketClaims {
+        sub: String::from(user_id),
+        aud: String::from(TICKET_AUD),
+        exp: (chrono::Utc::now().timestamp() + TICKET_SECS) as usize,
+    };
+
+    jsonwebtoken::encode(
+        &Header::new(Algorithm::HS512),
+        &claims,
+        &EncodingKey::from_secret(site_key.as_bytes()),
+    )
+}
+
+pub async fn ticket_data(
+    ticket: &str,
+    site_key: &str,
+) -> Result<TokenData<TicketClaims>, Error> {
+    let mut validation = Validation::new(Algorithm::HS512);
+    validation.set_audience(&[TICKET_AUD]);
+
+    jsonwebtoken::decode::<TicketClaims>(
+        ticket,
+        &DecodingKey::from_secret(site_key.as_bytes()),
+        &validation,
+    )
+}
@@ -0,0 +1,207 @@
+use std::{collections::HashMap, fs::read_to_string, path::PathBuf};
+
+use async_graphql::{Context, ErrorExtensions};
+use chrono::Utc;
+use chrono_tz::Tz;
+use fluent_bundle::{FluentArgs, FluentResource, concurrent::FluentBundle};
+use lazy_static::lazy_static;
+use mongodb::bson::DateTime;
+use unic_langid::LanguageIdentifier;
+
+use crate::util::{
+    config::Config,
+    constant::{GqlResult, DTF_YMDHMSZ},
+    error::AppError,
+};
+
+// locales of the formatted dates, the first one is the default
+pub const DATE_LOCALES: [&str; 2] = ["en-us", "zh-cn"];
+
+lazy_static! {
+    // formats & relative phrases of the dates, by the locales. They are in
+    // the fluent files shared with the frontend.
+    static ref DATE_BUNDLES: HashMap<&'static str, FluentBundle<FluentResource>> =
+        DATE_LOCALES
+            .iter()
+            .map(|locale| (*locale, date_bundle(locale)))
+            .collect();
+}
+
+#[derive(async_graphql::Enum, Copy, Clone, Default, Eq, PartialEq, Debug)]
+pub enum DateStyle {
+    // Jun 1, 2023
+    Date,
+    // Jun 1, 2023 08:30 CST
+    #[default]
+    DateTime,
+    // 3 days ago
+    Relative,
+}
+
+// Timezone & locale asked by the `Time-Zone` and `Accept-Language` headers of
+// the graphql request, or the profile timezone of the signed-in user. The
+// subscriptions have none.
+#[derive(Clone, Debug, Default)]
+pub struct DatePrefs {
+    pub tz: Option<String>,
+    pub locale: Option<String>,
+}
+
+// The timezone & locale of a date field: the arguments of the field first,
+// then the headers of the request or the profile of the signed-in user, then
+// the configured timezone and the default locale. Invalid arguments are
+// errors, invalid headers are ignored.
+pub fn date_zone(
+    ctx: &Context<'_>,
+    tz: Option<String>,
+    locale: Option<String>,
+) -> GqlResult<(Tz, &'static str)> {
+    let prefs = ctx.data_opt::<DatePrefs>();
+
+    let tz = match tz {
+        Some(tz) => tz
+            .trim()
+            .parse::<Tz>()
+            .map_err(|_| AppError::TimezoneInvalid.extend())?,
+        None => prefs
+            .and_then(|prefs| prefs.tz.as_deref())
+            .and_then(|tz| tz.trim().parse().ok())
+            .unwrap_or_else(|| ctx.data_unchecked::<Config>().tz()),
+    };
+
+    let locale = match locale {
+        Some(locale) => date_locale(&locale)
+            .ok_or_else(|| AppError::LocaleInvalid.extend())?,
+        None => prefs
+            .and_then(|prefs| prefs.locale.as_deref())
+            .and_then(date_locale)
+            .unwrap_or(DATE_LOCALES[0]),
+    };
+
+    Ok((tz, locale))
+}
+
+// the date field formatted by its arguments, see `date_zone`
+pub fn date_formatted(
+    ctx: &Context<'_>,
+    dt: DateTime,
+    tz: Option<String>,
+    locale: Option<String>,
+    style: DateStyle,
+) -> GqlResult<String> {
+    let (tz, locale) = date_zone(ctx, tz, locale)?;
+
+    Ok(format_date(dt, tz, locale, style))
+}
+
+// Y-M-D h:m:s in the timezone of the request, or the configured timezone
+pub fn date_nyrsq(ctx: &Context<'_>, dt: DateTime) -> String {
+    let tz = ctx
+        .data_opt::<DatePrefs>()
+        .and_then(|prefs| prefs.tz.as_deref())
+        .and_then(|tz| tz.trim().parse().ok())
+        .unwrap_or_else(|| ctx.data_unchecked::<Config>().tz());
+
+    dt.to_chrono().with_timezone(&tz).format(DTF_YMDHMSZ).to_string()
+}
+
+pub fn format_date(
+    dt: DateTime,
+    tz: Tz,
+    locale: &str,
+    style: DateStyle,
+) -> String {
+    let dt = dt.to_chrono();
+
+    match style {
+        DateStyle::Date => dt
+            .with_timezone(&tz)
+            .format(&date_msg(locale, "date-format", None))
+            .to_string(),
+        DateStyle::DateTime => dt
+            .with_timezone(&tz)
+            .format(&date_msg(locale, "date-time-format", None))
+            .to_string(),
+        DateStyle::Relative => {
+            let seconds = (Utc::now() - dt).num_seconds();
+
+            let abs = seconds.abs();
+            let (unit, count) = if abs < 60 {
+                return date_msg(locale, "date-just-now", None);
+            } else if abs < 3600 {
+                ("minutes", abs / 60)
+            } else if abs < 86400 {
+                ("hours", abs / 3600)
+            } else if abs < 30 * 86400 {
+                ("days", abs / 86400)
+            } else if abs < 365 * 86400 {
+                ("months", abs / (30 * 86400))
+            } else {
+                ("years", abs / (365 * 86400))
+            };
+
+            let msg_id = if seconds > 0 {
+                format!("date-{}-ago", unit)
+            } else {
+                format!("date-in-{}", unit)
+            };
+            date_msg(locale, &msg_id, Some(count))
+        }
+    }
+}
+
+// the supported locale of a language tag, such as "en-US", "zh_CN" or "zh"
+fn date_locale(tag: &str) -> Option<&'static str> {
+    let tag = tag.trim().to_lowercase().replace('_', "-");
+    let language = tag.split('-').next().unwrap_or_default();
+
+    DATE_LOCALES
+        .iter()
+        .find(|locale| tag.eq(*locale))
+        .or_else(|| {
+            DATE_LOCALES
+                .iter()
+                .find(|locale| locale.split('-').next() == Some(language))
+        })
+        .copied()
+}
+
+fn date_msg(locale: &str, msg_id: &str, count: Option<i64>) -> String {
+    let bundle = &DATE_BUNDLES[locale];
+
+    let mut args = FluentArgs::new();
+    if let Some(count) = count {
+        args.set("count", count);
+    }
+
+    match bundle.get_message(msg_id).and_then(|msg| msg.value()) {
+        Some(pattern) => {
+            let mut errors = vec![];
+            bundle.format_pattern(pattern, Some(&args), &mut errors).to_string()
+        }
+        None => String::from(msg_id),
+    }
+}
+
+fn date_bundle(locale: &str) -> FluentBundle<FluentResource> {
+    let res_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("../assets/locales")
+        .join(locale)
+        .join("dates.lang");
+    let res_source = read_to_string(&res_path).unwrap_or_else(|_| {
+        panic!("Failed to read res file: {}.", res_path.display())
+    });
+
+    let resource = FluentResource::try_new(res_source)
+        .unwrap_or_else(|_| panic!("{} could not be parsed.", locale));
+    let lang_id: LanguageIdentifier =
+        locale.parse().expect("Locales must be language identifiers.");
+
+    let mut bundle = FluentBundle::new_concurrent(vec![lang_id]);
+    bundle.set_use_isolating(false);
+    bundle
+        .add_resource(resource)
+        .expect("Failed to add LANG resources to the bundle.");
+
+    bundle
+}
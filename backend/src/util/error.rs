@@ -0,0 +1,164 @@
+use std::fmt;
+
+use async_graphql::{Error, ErrorExtensions};
+
+// Errors of the services. Each has a stable code, which is both the message
+// and the "code" extension of the graphql error, and the frontend translates
+// it by the code. Arguments of the message are in the extensions too.
+#[derive(Debug, Clone)]
+pub enum AppError {
+    // accounts
+    RegisterExists,
+    SignInIncorrect,
+    SignInNotActivation { user_id: String },
+    SignInBanned,
+    SignInSecurityProblem,
+    SignInNotRegistration,
+    ActivationCodeInvalid,
+    UserNotFound,
+    TokenInvalid,
+    PasswordIncorrect,
+    NoPermission,
+    FollowSelf,
+
+    // projects & files
+    ProjectNotFound,
+    ProjectExists { created_at: String },
+    ProjectUnpublished,
+    ProjectInTrash,
+    ProjectNotRestorable,
+    RemixOwnProject,
+    RemixExists { created_at: String },
+    FileNotFound,
+
+    // categories & topics
+    CategoryNotFound,
+    CategoryExists { name: String, locale: String },
+    CategoryMoveCycle,
+    CategoryReassignDeleted,
+    CategoryNotEmpty,
+    LocaleInvalid,
+    TimezoneInvalid,
+    TopicNotFound,
+    TopicExists { name: String },
+    MergeSelf,
+
+    // comments, notifications & studios
+    CommentNotFound,
+    CommentEmpty,
+    CommentsClosed,
+    CommentParentMismatch,
+    NotificationNotFound,
+    StudioNotFound,
+    StudioExists,
+    StudioInviteSelf,
+    StudioInviteNotFound,
+    StudioProjectNotFound,
+    WishNotFound,
+
+    // common
+    NameInvalid,
+    FieldInvalid,
+    StatusInvalid,
+    RecordExists,
+    RecordNotFound,
+    CursorInvalid,
+    IdempotencyKeyInvalid,
+    IdempotencyKeyReused,
+    VisitorInvalid,
+    RequestInProgress,
+    DatabaseFailed,
+}
+
+impl AppError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::RegisterExists => "register-failed-username-email-exists",
+            AppError::SignInIncorrect => "sign-in-incorrect",
+            AppError::SignInNotActivation { .. } => "sign-in-not-activation",
+            AppError::SignInBanned => "sign-in-banned",
+            AppError::SignInSecurityProblem => "sign-in-security-problem",
+            AppError::SignInNotRegistration => "sign-in-not-registration",
+            AppError::ActivationCodeInvalid => "activation-code-invalid",
+            AppError::UserNotFound => "user-not-found",
+            AppError::TokenInvalid => "token-invalid",
+            AppError::PasswordIncorrect => "password-incorrect",
+            AppError::NoPermission => "no-permission",
+            AppError::FollowSelf => "follow-self",
+
+            AppError::ProjectNotFound => "project-not-found",
+            AppError::ProjectExists { .. } => "project-exists",
+            AppError::ProjectUnpublished => "project-unpublished",
+            AppError::ProjectInTrash => "project-in-trash",
+            AppError::ProjectNotRestorable => "project-not-restorable",
+            AppError::RemixOwnProject => "remix-own-project",
+            AppError::RemixExists { .. } => "remix-exists",
+            AppError::FileNotFound => "file-not-found",
+
+            AppError::CategoryNotFound => "category-not-found",
+            AppError::CategoryExists { .. } => "category-exists",
+            AppError::CategoryMoveCycle => "category-move-cycle",
+            AppError::CategoryReassignDeleted => "category-reassign-deleted",
+            AppError::CategoryNotEmpty => "category-not-empty",
+            AppError::LocaleInvalid => "locale-invalid",
+            AppError::TimezoneInvalid => "timezone-invalid",
+            AppError::TopicNotFound => "topic-not-found",
+            AppError::TopicExists { .. } => "topic-exists",
+            AppError::MergeSelf => "merge-self",
+
+            AppError::CommentNotFound => "comment-not-found",
+            AppError::CommentEmpty => "comment-empty",
+            AppError::CommentsClosed => "comments-closed",
+            AppError::CommentParentMismatch => "comment-parent-mismatch",
+            AppError::NotificationNotFound => "notification-not-found",
+            AppError::StudioNotFound => "studio-not-found",
+            AppError::StudioExists => "studio-exists",
+            AppError::StudioInviteSelf => "studio-invite-self",
+            AppError::StudioInviteNotFound => "studio-invite-not-found",
+            AppError::StudioProjectNotFound => "studio-project-not-found",
+            AppError::WishNotFound => "wish-not-found",
+
+            AppError::NameInvalid => "name-invalid",
+            AppError::FieldInvalid => "field-invalid",
+            AppError::StatusInvalid => "status-invalid",
+            AppError::RecordExists => "record-exists",
+            AppError::RecordNotFound => "record-not-found",
+            AppError::CursorInvalid => "cursor-invalid",
+            AppError::IdempotencyKeyInvalid => "idempotency-key-invalid",
+            AppError::IdempotencyKeyReused => "idempotency-key-reused",
+            AppError::VisitorInvalid => "visitor-invalid",
+            AppError::RequestInProgress => "request-in-progress",
+            AppError::DatabaseFailed => "database-failed",
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+impl ErrorExtensions for AppError {
+    fn extend(&self) -> Error {
+        Error::new(self.code()).extend_with(|_, e| {
+            e.set("code", self.code());
+
+            match self {
+                AppError::SignInNotActivation { user_id } => {
+                    e.set("user_id", user_id.as_str())
+                }
+                AppError::ProjectExists { created_at }
+                | AppError::RemixExists { created_at } => {
+                    e.set("created_at", created_at.as_str())
+                }
+                AppError::CategoryExists { name, locale } => {
+                    e.set("name", name.as_str());
+                    e.set("locale", locale.as_str());
+                }
+                AppError::TopicExists { name } => e.set("name", name.as_str()),
+                _ => {}
+            }
+        })
+    }
+}
@@ -1,4 +1,8 @@
 pub mod cred;
+pub mod config;
 pub mod constant;
+pub mod datetime;
+pub mod error;
 pub mod common;
 pub mod pagination;
+pub mod broker;
@@ -4,10 +4,11 @@ use mongodb::{
     options::{CountOptions, FindOptions},
 };
 use serde::{Serialize, Deserialize};
+use async_graphql::ErrorExtensions;
 
-use crate::util::constant::CFG;
+use crate::util::{constant::GqlResult, error::AppError};
 
-use crate::{users, projects};
+use crate::{users, projects, comments, notifications, studios};
 
 #[derive(async_graphql::SimpleObject, Serialize, Deserialize, Clone, Debug)]
 pub struct PageInfo {
@@ -29,18 +30,18 @@ pub async fn count_pages_and_total(
     coll: &Collection<Document>,
     filter_doc: Option<Document>,
     count_opt: Option<CountOptions>,
-) -> (u32, u64) {
-    let total_count =
-        coll.count_documents(filter_doc, count_opt).await.unwrap();
+    page_size: u32,
+) -> GqlResult<(u32, u64)> {
+    let total_count = coll.count_documents(filter_doc, count_opt).await?;
 
-    let page_size = CFG.get("PAGE_SIZE").unwrap().parse::<u64>().unwrap();
+    let page_size = page_size as u64;
     let pages_mod = total_count % page_size;
     let pages_count = match pages_mod {
         0 => total_count / page_size,
         _ => total_count / page_size + 1,
     } as u32;
 
-    (pages_count, total_count)
+    Ok((pages_count, total_count))
 }
 
 pub async fn calculate_current_filter_skip(
@@ -48,28 +49,31 @@ pub async fn calculate_current_filter_skip(
     first_oid: String,
     last_oid: String,
     filter_doc: &mut Document,
-) -> (u32, u64) {
+) -> GqlResult<(u32, u64)> {
     let mut current_page = from_page;
     let mut skip_x = 0;
     if "".ne(&first_oid) && "-".ne(&first_oid) {
-        let first_cursor = ObjectId::parse_str(first_oid).unwrap();
+        let first_cursor = ObjectId::parse_str(first_oid)
+            .map_err(|_| AppError::CursorInvalid.extend())?;
         filter_doc.insert("_id", doc! {"$gte": first_cursor});
         current_page = from_page - 1;
         skip_x = (current_page - 1) as u64;
     } else if "".ne(&last_oid) && "-".ne(&last_oid) {
-        let last_cursor = ObjectId::parse_str(last_oid).unwrap();
+        let last_cursor = ObjectId::parse_str(last_oid)
+            .map_err(|_| AppError::CursorInvalid.extend())?;
         filter_doc.insert("_id", doc! {"$lte": last_cursor});
         current_page = from_page + 1;
     };
 
-    (current_page, skip_x)
+    Ok((current_page, skip_x))
 }
 
 pub async fn find_options(
     sort_doc: Option<Document>,
     skip_x: u64,
+    page_size: u32,
 ) -> FindOptions {
-    let page_size = CFG.get("PAGE_SIZE").unwrap().parse::<i64>().unwrap();
+    let page_size = page_size as i64;
     let find_options = FindOptions::builder()
         .sort(sort_doc)
         .skip(skip_x * (page_size as u64))
@@ -92,3 +96,24 @@ pub struct ProjectsResult {
     pub res_count: ResCount,
     pub current_items: Vec<projects::models::Project>,
 }
+
+#[derive(async_graphql::SimpleObject, Serialize, Deserialize, Clone, Debug)]
+pub struct CommentsResult {
+    pub page_info: PageInfo,
+    pub res_count: ResCount,
+    pub current_items: Vec<comments::models::Comment>,
+}
+
+#[derive(async_graphql::SimpleObject, Serialize, Deserialize, Clone, Debug)]
+pub struct NotificationsResult {
+    pub page_info: PageInfo,
+    pub res_count: ResCount,
+    pub current_items: Vec<notifications::models::Notification>,
+}
+
+#[derive(async_graphql::SimpleObject, Serialize, Deserialize, Clone, Debug)]
+pub struct StudiosResult {
+    pub page_info: PageInfo,
+    pub res_count: ResCount,
+    pub current_items: Vec<studios::models::Studio>,
+}
@@ -0,0 +1,69 @@
+use serde::{Serialize, Deserialize};
+use mongodb::bson::{oid::ObjectId, DateTime};
+
+// views of a project in one day, which starts at 00:00 UTC
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ProjectViewsDaily {
+    pub _id: ObjectId,
+    pub project_id: ObjectId,
+    pub day: DateTime,
+    pub views: u64,
+}
+
+// downloads of a project in one day, which starts at 00:00 UTC
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ProjectDownloadsDaily {
+    pub _id: ObjectId,
+    pub project_id: ObjectId,
+    pub day: DateTime,
+    pub downloads: u64,
+}
+
+// range of the statistics, counted by day until today
+#[derive(
+    async_graphql::Enum,
+    Serialize,
+    Deserialize,
+    Copy,
+    Clone,
+    Eq,
+    PartialEq,
+    Debug,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum StatsRange {
+    Week,
+    Month,
+    Quarter,
+}
+
+impl StatsRange {
+    pub fn days(&self) -> i64 {
+        match self {
+            StatsRange::Week => 7,
+            StatsRange::Month => 30,
+            StatsRange::Quarter => 90,
+        }
+    }
+}
+
+// counts of one day in the statistics
+#[derive(async_graphql::SimpleObject, Clone, Debug)]
+pub struct StatsDaily {
+    pub day: String,
+    pub views: u64,
+    pub downloads: u64,
+    pub stars: u64,
+    pub comments: u64,
+}
+
+// statistics of projects in the range, totals and counts of each day
+#[derive(async_graphql::SimpleObject, Clone, Debug)]
+pub struct Stats {
+    pub range: StatsRange,
+    pub views: u64,
+    pub downloads: u64,
+    pub stars: u64,
+    pub comments: u64,
+    pub daily: Vec<StatsDaily>,
+}
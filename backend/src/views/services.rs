@@ -0,0 +1,321 @@
+use futures::stream::StreamExt;
+use mongodb::{
+    Database,
+    bson::{oid::ObjectId, DateTime, Document, doc, from_document},
+    options::UpdateOptions,
+};
+use async_graphql::ErrorExtensions;
+
+use crate::util::{
+    config::Config,
+    constant::{GqlResult, DTF_YMD, DAY_MILLIS},
+    error::AppError,
+    cred::{fingerprint_encode, fingerprint_verify},
+};
+
+use crate::dbs::mongo::is_duplicate_key;
+use crate::{users, users::models::UserStatus};
+use crate::comments::services::COMMENT_STATUS_VISIBLE;
+use crate::projects::{
+    models::{Project, File, FileKind, ProjectStar},
+    services::{project_by_id, file_by_kind_project_id},
+};
+use super::models::{
+    ProjectViewsDaily, ProjectDownloadsDaily, Stats, StatsDaily, StatsRange,
+};
+
+// Record a view of the project, counted once per visitor within the dedup
+// window, and never for its author or moderators. Signed-in visitors are
+// known by their id, the others by the fingerprint digest. The fingerprint
+// must be signed by the frontend, or anyone could count views at will.
+pub async fn project_view_record(
+    db: &Database,
+    cfg: &Config,
+    project_id: ObjectId,
+    fingerprint: String,
+    signature: String,
+    token: String,
+) -> GqlResult<Project> {
+    if !fingerprint_verify(&fingerprint, &signature, &cfg.visitor_key).await {
+        return Err(AppError::VisitorInvalid.extend());
+    }
+
+    let project = project_by_id(db, project_id).await?;
+
+    let visitor = match users::services::user_by_token(db, cfg, token).await {
+        Ok(user) => {
+            if user._id == project.user_id
+                || user.status >= UserStatus::Moderator
+            {
+                return Ok(project);
+            }
+            user._id.to_hex()
+        }
+        Err(_) => fingerprint_encode(&fingerprint, &cfg.site_key).await,
+    };
+
+    // views of one visitor expire with the dedup window
+    let coll_views = db.collection::<Document>("projects_views");
+    let now = DateTime::now();
+    let upsert_options = UpdateOptions::builder().upsert(true).build();
+    let view_res = coll_views
+        .update_one(
+            doc! {"project_id": project_id, "visitor": &visitor},
+            doc! {"$setOnInsert": {"viewed_at": now}},
+            upsert_options.clone(),
+        )
+        .await;
+    match view_res {
+        Ok(update_res) if update_res.upserted_id.is_some() => {}
+        // viewed within the window
+        Ok(_) => return Ok(project),
+        // a concurrent view of the same visitor just counted
+        Err(error) if is_duplicate_key(&error) => {
+            return Ok(project);
+        }
+        Err(error) => {
+            println!("\n\n\n{}\n\n\n", error);
+            return Err(AppError::DatabaseFailed.extend());
+        }
+    }
+
+    let coll_daily = db.collection::<Document>("projects_views_daily");
+    coll_daily
+        .update_one(
+            doc! {"project_id": project_id, "day": day_start(now)},
+            doc! {"$inc": {"views": 1_i64}},
+            upsert_options,
+        )
+        .await?;
+
+    let coll = db.collection::<Document>("projects");
+    coll.update_one(
+        doc! {"_id": project_id},
+        doc! {"$inc": {"hits": 1_i64}},
+        None,
+    )
+    .await?;
+
+    project_by_id(db, project_id).await
+}
+
+// Record a download of the source file by a signed-in user, and get the
+// file. Downloads by the author are not counted.
+pub async fn project_download_record(
+    db: &Database,
+    cfg: &Config,
+    project_id: ObjectId,
+    token: String,
+) -> GqlResult<File> {
+    let user = users::services::user_by_token(db, cfg, token).await?;
+    let project = project_by_id(db, project_id).await?;
+    let source_file =
+        file_by_kind_project_id(db, FileKind::Source, project_id).await?;
+
+    if user._id != project.user_id {
+        let upsert_options = UpdateOptions::builder().upsert(true).build();
+        let coll_daily = db.collection::<Document>("projects_downloads_daily");
+        coll_daily
+            .update_one(
+                doc! {
+                    "project_id": project_id,
+                    "day": day_start(DateTime::now())
+                },
+                doc! {"$inc": {"downloads": 1_i64}},
+                upsert_options,
+            )
+            .await?;
+    }
+
+    Ok(source_file)
+}
+
+// statistics of the project by day, only for its owner
+pub async fn project_stats(
+    db: &Database,
+    cfg: &Config,
+    project: &Project,
+    range: StatsRange,
+    token: String,
+) -> GqlResult<Stats> {
+    let user = users::services::user_by_token(db, cfg, token).await?;
+    if user._id != project.user_id {
+        return Err(AppError::NoPermission.extend());
+    }
+
+    stats_by_project_ids(db, vec![project._id], range).await
+}
+
+// statistics of all projects of the user by day, only for the user self
+pub async fn user_stats(
+    db: &Database,
+    cfg: &Config,
+    user_id: ObjectId,
+    range: StatsRange,
+    token: String,
+) -> GqlResult<Stats> {
+    let user = users::services::user_by_token(db, cfg, token).await?;
+    if user._id != user_id {
+        return Err(AppError::NoPermission.extend());
+    }
+
+    let coll = db.collection::<Document>("projects");
+    let mut cursor = coll.find(doc! {"user_id": user_id}, None).await?;
+
+    let mut project_ids: Vec<ObjectId> = vec![];
+    while let Some(result) = cursor.next().await {
+        match result {
+            Ok(document) => {
+                project_ids.push(document.get_object_id("_id")?);
+            }
+            Err(error) => {
+                println!("\n\n\n{}\n\n\n", error);
+            }
+        }
+    }
+
+    stats_by_project_ids(db, project_ids, range).await
+}
+
+// count views, downloads, stars and comments of the projects by day, from
+// the first day of the range until today
+async fn stats_by_project_ids(
+    db: &Database,
+    project_ids: Vec<ObjectId>,
+    range: StatsRange,
+) -> GqlResult<Stats> {
+    let today = day_start(DateTime::now());
+    let since = DateTime::from_millis(
+        today.timestamp_millis() - (range.days() - 1) * DAY_MILLIS,
+    );
+
+    let mut daily: Vec<StatsDaily> = (0..range.days())
+        .map(|index| StatsDaily {
+            day: DateTime::from_millis(
+                since.timestamp_millis() + index * DAY_MILLIS,
+            )
+            .to_chrono()
+            .format(DTF_YMD)
+            .to_string(),
+            views: 0,
+            downloads: 0,
+            stars: 0,
+            comments: 0,
+        })
+        .collect();
+    // index of the day in the range, None if out of it
+    let day_index = |datetime: DateTime| {
+        let index = (day_start(datetime).timestamp_millis()
+            - since.timestamp_millis())
+            / DAY_MILLIS;
+        if (0..range.days()).contains(&index) {
+            Some(index as usize)
+        } else {
+            None
+        }
+    };
+
+    let filter_doc = doc! {
+        "project_id": {"$in": &project_ids},
+        "day": {"$gte": since}
+    };
+
+    let coll_views = db.collection::<Document>("projects_views_daily");
+    let mut cursor = coll_views.find(filter_doc.clone(), None).await?;
+    while let Some(result) = cursor.next().await {
+        match result {
+            Ok(document) => {
+                let views_daily: ProjectViewsDaily = from_document(document)?;
+                if let Some(index) = day_index(views_daily.day) {
+                    daily[index].views += views_daily.views;
+                }
+            }
+            Err(error) => {
+                println!("\n\n\n{}\n\n\n", error);
+            }
+        }
+    }
+
+    let coll_downloads = db.collection::<Document>("projects_downloads_daily");
+    let mut cursor = coll_downloads.find(filter_doc, None).await?;
+    while let Some(result) = cursor.next().await {
+        match result {
+            Ok(document) => {
+                let downloads_daily: ProjectDownloadsDaily =
+                    from_document(document)?;
+                if let Some(index) = day_index(downloads_daily.day) {
+                    daily[index].downloads += downloads_daily.downloads;
+                }
+            }
+            Err(error) => {
+                println!("\n\n\n{}\n\n\n", error);
+            }
+        }
+    }
+
+    let coll_stars = db.collection::<Document>("project_stars");
+    let mut cursor = coll_stars
+        .find(
+            doc! {
+                "project_id": {"$in": &project_ids},
+                "created_at": {"$gte": since}
+            },
+            None,
+        )
+        .await?;
+    while let Some(result) = cursor.next().await {
+        match result {
+            Ok(document) => {
+                let star: ProjectStar = from_document(document)?;
+                if let Some(index) = day_index(star.created_at) {
+                    daily[index].stars += 1;
+                }
+            }
+            Err(error) => {
+                println!("\n\n\n{}\n\n\n", error);
+            }
+        }
+    }
+
+    let coll_comments = db.collection::<Document>("comments");
+    let mut cursor = coll_comments
+        .find(
+            doc! {
+                "project_id": {"$in": &project_ids},
+                "created_at": {"$gte": since},
+                "status": {"$gte": COMMENT_STATUS_VISIBLE}
+            },
+            None,
+        )
+        .await?;
+    while let Some(result) = cursor.next().await {
+        match result {
+            Ok(document) => {
+                let created_at = *document.get_datetime("created_at")?;
+                if let Some(index) = day_index(created_at) {
+                    daily[index].comments += 1;
+                }
+            }
+            Err(error) => {
+                println!("\n\n\n{}\n\n\n", error);
+            }
+        }
+    }
+
+    Ok(Stats {
+        range,
+        views: daily.iter().map(|day| day.views).sum(),
+        downloads: daily.iter().map(|day| day.downloads).sum(),
+        stars: daily.iter().map(|day| day.stars).sum(),
+        comments: daily.iter().map(|day| day.comments).sum(),
+        daily,
+    })
+}
+
+// start of the day which the datetime is in, at 00:00 UTC
+fn day_start(datetime: DateTime) -> DateTime {
+    DateTime::from_millis(
+        datetime.timestamp_millis()
+            - datetime.timestamp_millis().rem_euclid(DAY_MILLIS),
+    )
+}
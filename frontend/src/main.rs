@@ -2,29 +2,41 @@ mod util;
 mod routes;
 mod models;
 
-use crate::util::constant::CFG;
+use std::sync::Arc;
+
+use crate::util::config::Config;
 
 #[async_std::main]
 async fn main() -> Result<(), std::io::Error> {
-    let app_state = State {};
+    // configuration from env & the optional TOML file, all problems of it
+    // are reported together before exiting
+    let cfg = match Config::load() {
+        Ok(cfg) => cfg,
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    };
+
+    // the state is cloned for every request, so the config is shared
+    let app_state = State { config: Arc::new(cfg.clone()) };
     let mut app = tide::with_state(app_state);
     // app = push_res(app).await;
     routes::push_res(&mut app).await;
 
-    let log_level = CFG.get("LOG_LEVEL").unwrap();
     use std::str::FromStr;
-    femme::with_level(femme::LevelFilter::from_str(log_level).unwrap());
+    femme::with_level(
+        femme::LevelFilter::from_str(&cfg.log_level)
+            .unwrap_or(femme::LevelFilter::Info),
+    );
     app.with(tide::log::LogMiddleware::new());
 
-    app.listen(format!(
-        "{}:{}",
-        CFG.get("ADDR").unwrap(),
-        CFG.get("PORT").unwrap()
-    ))
-    .await?;
+    app.listen(format!("{}:{}", cfg.addr, cfg.port)).await?;
 
     Ok(())
 }
 
 #[derive(Clone, Debug)]
-pub struct State {}
+pub struct State {
+    pub config: Arc<Config>,
+}
@@ -1,3 +1,50 @@
+use std::collections::BTreeMap;
+use serde::{Serialize, Deserialize};
+
+// translations are posted as `name-<locale>` fields, and a new one as the
+// `locale` & `name` pair
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CategoryInfo {
+    #[serde(default)]
+    pub parent_id: String,
+    #[serde(default)]
+    pub locale: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(flatten)]
+    pub names: BTreeMap<String, String>,
+}
+
+impl CategoryInfo {
+    // (locale, name) pairs of all translations in the form
+    pub fn translations(&self) -> Vec<(String, String)> {
+        let mut translations: Vec<(String, String)> = self
+            .names
+            .iter()
+            .filter_map(|(key, name)| {
+                key.strip_prefix("name-")
+                    .map(|locale| (locale.to_string(), name.clone()))
+            })
+            .collect();
+        if !self.locale.trim().is_empty() {
+            translations.push((self.locale.clone(), self.name.clone()));
+        }
+
+        translations
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CategoryMoveInfo {
+    pub parent_id: String,
+    pub position: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CategoryDeleteInfo {
+    pub reassign_id: String,
+}
+
 // -------------------------------
 // GraphQLQuery for graphql_client
 // -------------------------------
@@ -19,3 +66,38 @@ pub struct CategoriesData;
     query_path = "../assets/graphql/categories.graphql"
 )]
 pub struct CategoryBySlugData;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "../assets/graphql/schema.graphql",
+    query_path = "../assets/graphql/categories.graphql"
+)]
+pub struct CategoriesTreeData;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "../assets/graphql/schema.graphql",
+    query_path = "../assets/graphql/categories.graphql"
+)]
+pub struct CategoryNewData;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "../assets/graphql/schema.graphql",
+    query_path = "../assets/graphql/categories.graphql"
+)]
+pub struct CategoryUpdateData;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "../assets/graphql/schema.graphql",
+    query_path = "../assets/graphql/categories.graphql"
+)]
+pub struct CategoryMoveData;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "../assets/graphql/schema.graphql",
+    query_path = "../assets/graphql/categories.graphql"
+)]
+pub struct CategoryDeleteData;
@@ -0,0 +1,50 @@
+use serde::{Serialize, Deserialize};
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CommentInfo {
+    pub parent_id: String,
+    pub content: String,
+}
+
+// -------------------------------
+// GraphQLQuery for graphql_client
+// -------------------------------
+
+use graphql_client::GraphQLQuery;
+
+type ObjectId = String;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "../assets/graphql/schema.graphql",
+    query_path = "../assets/graphql/comments.graphql"
+)]
+pub struct CommentsByProjectData;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "../assets/graphql/schema.graphql",
+    query_path = "../assets/graphql/comments.graphql"
+)]
+pub struct CommentNewData;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "../assets/graphql/schema.graphql",
+    query_path = "../assets/graphql/comments.graphql"
+)]
+pub struct CommentUpdateData;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "../assets/graphql/schema.graphql",
+    query_path = "../assets/graphql/comments.graphql"
+)]
+pub struct CommentDeleteData;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "../assets/graphql/schema.graphql",
+    query_path = "../assets/graphql/comments.graphql"
+)]
+pub struct CommentHideData;
@@ -36,6 +36,13 @@ type ObjectId = String;
 )]
 pub struct HomeData;
 
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "../assets/graphql/schema.graphql",
+    query_path = "../assets/graphql/home.graphql"
+)]
+pub struct FeedData;
+
 #[derive(GraphQLQuery)]
 #[graphql(
     schema_path = "../assets/graphql/schema.graphql",
@@ -3,6 +3,9 @@ pub mod categories;
 pub mod topics;
 pub mod projects;
 pub mod users;
+pub mod comments;
+pub mod notifications;
+pub mod studios;
 
 use serde::{Serialize, Deserialize};
 
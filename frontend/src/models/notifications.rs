@@ -0,0 +1,42 @@
+// -------------------------------
+// GraphQLQuery for graphql_client
+// -------------------------------
+
+use graphql_client::GraphQLQuery;
+
+type ObjectId = String;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "../assets/graphql/schema.graphql",
+    query_path = "../assets/graphql/notifications.graphql"
+)]
+pub struct NotificationsData;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "../assets/graphql/schema.graphql",
+    query_path = "../assets/graphql/notifications.graphql"
+)]
+pub struct NotificationsUnreadCountData;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "../assets/graphql/schema.graphql",
+    query_path = "../assets/graphql/notifications.graphql"
+)]
+pub struct NotificationMarkReadData;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "../assets/graphql/schema.graphql",
+    query_path = "../assets/graphql/notifications.graphql"
+)]
+pub struct NotificationsMarkAllReadData;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "../assets/graphql/schema.graphql",
+    query_path = "../assets/graphql/notifications.graphql"
+)]
+pub struct SubscriptionTicketData;
@@ -14,6 +14,21 @@ pub struct ProjectInfo {
     pub contact_email: String,
     pub contact_im: String,
     pub language: String,
+    // the same key for resubmissions of one form
+    #[serde(default)]
+    pub idempotency_key: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TrendingInfo {
+    #[serde(default)]
+    pub window: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct StatsInfo {
+    #[serde(default)]
+    pub range: String,
 }
 
 // -------------------------------
@@ -31,6 +46,13 @@ type ObjectId = String;
 )]
 pub struct ProjectsData;
 
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "../assets/graphql/schema.graphql",
+    query_path = "../assets/graphql/projects.graphql"
+)]
+pub struct ProjectsTrendingData;
+
 #[derive(GraphQLQuery)]
 #[graphql(
     schema_path = "../assets/graphql/schema.graphql",
@@ -57,7 +79,7 @@ pub struct ProjectsByTopicData;
     schema_path = "../assets/graphql/schema.graphql",
     query_path = "../assets/graphql/projects.graphql"
 )]
-pub struct ProjectNewData;
+pub struct ProjectPublishData;
 
 #[derive(GraphQLQuery)]
 #[graphql(
@@ -71,25 +93,116 @@ pub struct ProjectData;
     schema_path = "../assets/graphql/schema.graphql",
     query_path = "../assets/graphql/projects.graphql"
 )]
-pub struct ProjectUpdateOneFieldByIdData;
+pub struct ProjectBySlugData;
 
 #[derive(GraphQLQuery)]
 #[graphql(
     schema_path = "../assets/graphql/schema.graphql",
     query_path = "../assets/graphql/projects.graphql"
 )]
-pub struct ProjectRandomData;
+pub struct ProjectSetStatusData;
 
 #[derive(GraphQLQuery)]
 #[graphql(
     schema_path = "../assets/graphql/schema.graphql",
     query_path = "../assets/graphql/projects.graphql"
 )]
-pub struct FileNewData;
+pub struct ProjectViewRecordData;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "../assets/graphql/schema.graphql",
+    query_path = "../assets/graphql/projects.graphql"
+)]
+pub struct ProjectDownloadRecordData;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "../assets/graphql/schema.graphql",
+    query_path = "../assets/graphql/projects.graphql"
+)]
+pub struct ProjectDeleteData;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "../assets/graphql/schema.graphql",
+    query_path = "../assets/graphql/projects.graphql"
+)]
+pub struct ProjectRestoreData;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "../assets/graphql/schema.graphql",
+    query_path = "../assets/graphql/projects.graphql"
+)]
+pub struct ProjectsTrashData;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "../assets/graphql/schema.graphql",
+    query_path = "../assets/graphql/projects.graphql"
+)]
+pub struct ProjectStarData;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "../assets/graphql/schema.graphql",
+    query_path = "../assets/graphql/projects.graphql"
+)]
+pub struct ProjectUnstarData;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "../assets/graphql/schema.graphql",
+    query_path = "../assets/graphql/projects.graphql"
+)]
+pub struct ProjectRemixData;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "../assets/graphql/schema.graphql",
+    query_path = "../assets/graphql/projects.graphql"
+)]
+pub struct ProjectRemixTreeData;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "../assets/graphql/schema.graphql",
+    query_path = "../assets/graphql/projects.graphql"
+)]
+pub struct ProjectStatsData;
 
 #[derive(GraphQLQuery)]
 #[graphql(
     schema_path = "../assets/graphql/schema.graphql",
     query_path = "../assets/graphql/projects.graphql"
 )]
-pub struct ProjectFileNewData;
+pub struct UserStatsData;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "../assets/graphql/schema.graphql",
+    query_path = "../assets/graphql/projects.graphql"
+)]
+pub struct ProjectsStarredByUserData;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "../assets/graphql/schema.graphql",
+    query_path = "../assets/graphql/projects.graphql"
+)]
+pub struct ProjectCommentsEnableData;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "../assets/graphql/schema.graphql",
+    query_path = "../assets/graphql/projects.graphql"
+)]
+pub struct ProjectRandomData;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "../assets/graphql/schema.graphql",
+    query_path = "../assets/graphql/projects.graphql"
+)]
+pub struct FileNewData;
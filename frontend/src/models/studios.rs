@@ -0,0 +1,108 @@
+use serde::{Serialize, Deserialize};
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct StudioInfo {
+    pub name: String,
+    pub description: String,
+    pub cover_image_id: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct StudioCuratorInfo {
+    pub username: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct StudioProjectInfo {
+    pub project: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct StudioProjectPosition {
+    pub position: i64,
+}
+
+// -------------------------------
+// GraphQLQuery for graphql_client
+// -------------------------------
+
+use graphql_client::GraphQLQuery;
+
+type ObjectId = String;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "../assets/graphql/schema.graphql",
+    query_path = "../assets/graphql/studios.graphql"
+)]
+pub struct StudiosData;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "../assets/graphql/schema.graphql",
+    query_path = "../assets/graphql/studios.graphql"
+)]
+pub struct StudioInvitationsData;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "../assets/graphql/schema.graphql",
+    query_path = "../assets/graphql/studios.graphql"
+)]
+pub struct StudioBySlugData;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "../assets/graphql/schema.graphql",
+    query_path = "../assets/graphql/studios.graphql"
+)]
+pub struct StudioNewData;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "../assets/graphql/schema.graphql",
+    query_path = "../assets/graphql/studios.graphql"
+)]
+pub struct StudioUpdateData;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "../assets/graphql/schema.graphql",
+    query_path = "../assets/graphql/studios.graphql"
+)]
+pub struct StudioCuratorInviteData;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "../assets/graphql/schema.graphql",
+    query_path = "../assets/graphql/studios.graphql"
+)]
+pub struct StudioCuratorAcceptData;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "../assets/graphql/schema.graphql",
+    query_path = "../assets/graphql/studios.graphql"
+)]
+pub struct StudioCuratorRemoveData;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "../assets/graphql/schema.graphql",
+    query_path = "../assets/graphql/studios.graphql"
+)]
+pub struct StudioProjectAddData;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "../assets/graphql/schema.graphql",
+    query_path = "../assets/graphql/studios.graphql"
+)]
+pub struct StudioProjectRemoveData;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "../assets/graphql/schema.graphql",
+    query_path = "../assets/graphql/studios.graphql"
+)]
+pub struct StudioProjectMoveData;
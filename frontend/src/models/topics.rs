@@ -1,3 +1,19 @@
+use serde::{Serialize, Deserialize};
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TopicsSuggestInfo {
+    #[serde(default)]
+    pub prefix: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TopicsContentInfo {
+    #[serde(default)]
+    pub subject: String,
+    #[serde(default)]
+    pub content: String,
+}
+
 // -------------------------------
 // GraphQLQuery for graphql_client
 // -------------------------------
@@ -32,4 +48,11 @@ pub struct TopicUserNewData;
     schema_path = "../assets/graphql/schema.graphql",
     query_path = "../assets/graphql/topics.graphql"
 )]
-pub struct TopicProjectNewData;
+pub struct TopicsSuggestData;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "../assets/graphql/schema.graphql",
+    query_path = "../assets/graphql/topics.graphql"
+)]
+pub struct TopicsForContentData;
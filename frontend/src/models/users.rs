@@ -48,7 +48,7 @@ pub struct UsersData;
     schema_path = "../assets/graphql/schema.graphql",
     query_path = "../assets/graphql/users.graphql"
 )]
-pub struct UserUpdateOneFieldByIdData;
+pub struct UserActivateData;
 
 #[derive(GraphQLQuery)]
 #[graphql(
@@ -56,3 +56,31 @@ pub struct UserUpdateOneFieldByIdData;
     query_path = "../assets/graphql/users.graphql"
 )]
 pub struct WishRandomData;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "../assets/graphql/schema.graphql",
+    query_path = "../assets/graphql/users.graphql"
+)]
+pub struct UserFollowersData;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "../assets/graphql/schema.graphql",
+    query_path = "../assets/graphql/users.graphql"
+)]
+pub struct UserFollowingData;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "../assets/graphql/schema.graphql",
+    query_path = "../assets/graphql/users.graphql"
+)]
+pub struct UserFollowData;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "../assets/graphql/schema.graphql",
+    query_path = "../assets/graphql/users.graphql"
+)]
+pub struct UserUnfollowData;
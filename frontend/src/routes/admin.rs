@@ -5,7 +5,7 @@ use serde_json::json;
 
 use crate::State;
 use crate::util::{
-    common::{gql_uri, sign_status},
+    common::{gql_post, sign_status},
     tpl::{Hbs, insert_user_by_username},
 };
 
@@ -13,11 +13,12 @@ use crate::models::{
     Page,
     projects::{
         ProjectsData, projects_data, ProjectData, project_data,
-        ProjectUpdateOneFieldByIdData, project_update_one_field_by_id_data,
+        ProjectSetStatusData, project_set_status_data,
     },
 };
 
 pub async fn admin_index(req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
     let sign_status = sign_status(&req).await;
     if sign_status.sign_in {
         let mut admin_index_tpl: Hbs = Hbs::new("admin/admin-index").await;
@@ -35,7 +36,7 @@ pub async fn admin_index(req: Request<State>) -> tide::Result {
         let mut data: BTreeMap<&str, serde_json::Value> = BTreeMap::new();
         data.insert("language", json!("zh-cn"));
         data.insert("nav-admin-selected", json!("is-selected"));
-        insert_user_by_username(sign_status.username, &mut data).await;
+        insert_user_by_username(&cfg, &sign_status, &mut data).await;
 
         admin_index_tpl.render(&data).await
     } else {
@@ -46,6 +47,7 @@ pub async fn admin_index(req: Request<State>) -> tide::Result {
 }
 
 pub async fn projects_admin(req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
     let sign_status = sign_status(&req).await;
     if sign_status.sign_in {
         let mut admin_projects_tpl: Hbs =
@@ -66,7 +68,7 @@ pub async fn projects_admin(req: Request<State>) -> tide::Result {
         let mut data: BTreeMap<&str, serde_json::Value> = BTreeMap::new();
         data.insert("language", json!("zh-cn"));
         data.insert("nav-admin-selected", json!("is-selected"));
-        insert_user_by_username(sign_status.username, &mut data).await;
+        insert_user_by_username(&cfg, &sign_status, &mut data).await;
 
         let page: Page = req.query()?;
         let projects_build_query =
@@ -74,12 +76,14 @@ pub async fn projects_admin(req: Request<State>) -> tide::Result {
                 from_page: page.from,
                 first_oid: page.first,
                 last_oid: page.last,
-                status: 0,
+                status: None,
+                locale: String::from("zh-cn"),
             });
         let projects_query = json!(projects_build_query);
 
         let projects_resp_body: GqlResponse<serde_json::Value> =
-            surf::post(&gql_uri().await)
+            gql_post(&req, &cfg)
+                .await
                 .body(projects_query)
                 .recv_json()
                 .await
@@ -98,6 +102,7 @@ pub async fn projects_admin(req: Request<State>) -> tide::Result {
 }
 
 pub async fn project_admin(req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
     let sign_status = sign_status(&req).await;
     if sign_status.sign_in {
         let mut project_index_tpl: Hbs =
@@ -122,36 +127,20 @@ pub async fn project_admin(req: Request<State>) -> tide::Result {
         let mut data: BTreeMap<&str, serde_json::Value> = BTreeMap::new();
         data.insert("language", json!("zh-cn"));
         data.insert("nav-admin-selected", json!("is-selected"));
-        insert_user_by_username(sign_status.username, &mut data).await;
+        insert_user_by_username(&cfg, &sign_status, &mut data).await;
 
         let project_id = req.param("project_id")?;
 
-        let project_update_hits_build_query =
-            ProjectUpdateOneFieldByIdData::build_query(
-                project_update_one_field_by_id_data::Variables {
-                    project_id: project_id.to_string(),
-                    field_name: String::from("hits"),
-                    field_val: String::from("3"),
-                },
-            );
-        let project_update_hits_query = json!(project_update_hits_build_query);
-        let _project_update_hits_resp_body: GqlResponse<serde_json::Value> =
-            surf::post(&gql_uri().await)
-                .body(project_update_hits_query)
-                .recv_json()
-                .await?;
-
         let project_build_query =
             ProjectData::build_query(project_data::Variables {
                 project_id: project_id.to_string(),
+                token: sign_status.token,
+                locale: String::from("zh-cn"),
             });
         let project_query = json!(project_build_query);
 
         let project_resp_body: GqlResponse<serde_json::Value> =
-            surf::post(&gql_uri().await)
-                .body(project_query)
-                .recv_json()
-                .await?;
+            gql_post(&req, &cfg).await.body(project_query).recv_json().await?;
         let project_resp_data = project_resp_body.data.expect("无响应数据");
 
         let project = project_resp_data["projectById"].clone();
@@ -165,25 +154,27 @@ pub async fn project_admin(req: Request<State>) -> tide::Result {
     }
 }
 
-pub async fn project_update_one_field(req: Request<State>) -> tide::Result {
+pub async fn project_set_status(req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
     let sign_status = sign_status(&req).await;
     if sign_status.sign_in {
         let project_id = req.param("project_id")?;
-        let field_name = req.param("field_name")?;
-        let field_val = req.param("field_val")?;
-
-        let project_update_hits_build_query =
-            ProjectUpdateOneFieldByIdData::build_query(
-                project_update_one_field_by_id_data::Variables {
-                    project_id: String::from(project_id),
-                    field_name: String::from(field_name),
-                    field_val: String::from(field_val),
-                },
-            );
-        let project_update_hits_query = json!(project_update_hits_build_query);
-        let _project_update_hits_resp_body: GqlResponse<serde_json::Value> =
-            surf::post(&gql_uri().await)
-                .body(project_update_hits_query)
+        // the status in the path is the lowercase name of the enum value
+        let status: project_set_status_data::ProjectStatus =
+            serde_json::from_value(json!(req.param("status")?.to_uppercase()))?;
+
+        let project_set_status_build_query = ProjectSetStatusData::build_query(
+            project_set_status_data::Variables {
+                project_id: String::from(project_id),
+                status,
+                token: sign_status.token.clone(),
+            },
+        );
+        let project_set_status_query = json!(project_set_status_build_query);
+        let _project_set_status_resp_body: GqlResponse<serde_json::Value> =
+            gql_post(&req, &cfg)
+                .await
+                .body(project_set_status_query)
                 .recv_json()
                 .await?;
 
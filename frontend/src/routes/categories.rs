@@ -1 +1,238 @@
+use std::collections::BTreeMap;
+use tide::{Request, Response, Redirect};
+use graphql_client::{GraphQLQuery, Response as GqlResponse};
+use serde_json::json;
 
+use crate::State;
+use crate::util::{
+    config::Config,
+    common::{gql_uri, gql_post, sign_status, gql_error},
+    tpl::{Hbs, insert_user_by_username},
+};
+
+use crate::models::{
+    users::SignStatus,
+    categories::{
+        CategoryInfo, CategoryMoveInfo, CategoryDeleteInfo, CategoriesTreeData,
+        categories_tree_data, CategoryNewData, category_new_data,
+        CategoryUpdateData, category_update_data, CategoryMoveData,
+        category_move_data, CategoryDeleteData, category_delete_data,
+    },
+};
+
+pub async fn categories_admin(req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
+    let sign_status = sign_status(&req).await;
+    if sign_status.sign_in {
+        categories_admin_render(&cfg, &sign_status, None).await
+    } else {
+        let resp: Response = Redirect::new("/zh-cn/sign-in").into();
+
+        Ok(resp.into())
+    }
+}
+
+pub async fn category_new(mut req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
+    let sign_status = sign_status(&req).await;
+    if sign_status.sign_in {
+        let category_info: CategoryInfo = req.body_form().await?;
+
+        let category_new_build_query =
+            CategoryNewData::build_query(category_new_data::Variables {
+                translations: category_info
+                    .translations()
+                    .into_iter()
+                    .map(|(locale, name)| {
+                        category_new_data::CategoryNameInput { locale, name }
+                    })
+                    .collect(),
+                parent_id: optional_id(&category_info.parent_id),
+            });
+        let category_new_query = json!(category_new_build_query);
+
+        let category_new_resp_body: GqlResponse<serde_json::Value> =
+            gql_post(&req, &cfg)
+                .await
+                .body(category_new_query)
+                .recv_json()
+                .await?;
+
+        categories_admin_done(&cfg, &sign_status, category_new_resp_body).await
+    } else {
+        let resp: Response = Redirect::new("/zh-cn/sign-in").into();
+
+        Ok(resp.into())
+    }
+}
+
+pub async fn category_update(mut req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
+    let sign_status = sign_status(&req).await;
+    if sign_status.sign_in {
+        let category_id = String::from(req.param("category_id")?);
+        let category_info: CategoryInfo = req.body_form().await?;
+
+        let category_update_build_query =
+            CategoryUpdateData::build_query(category_update_data::Variables {
+                category_id,
+                translations: category_info
+                    .translations()
+                    .into_iter()
+                    .map(|(locale, name)| {
+                        category_update_data::CategoryNameInput { locale, name }
+                    })
+                    .collect(),
+                token: sign_status.token.clone(),
+            });
+        let category_update_query = json!(category_update_build_query);
+
+        let category_update_resp_body: GqlResponse<serde_json::Value> =
+            gql_post(&req, &cfg)
+                .await
+                .body(category_update_query)
+                .recv_json()
+                .await?;
+
+        categories_admin_done(&cfg, &sign_status, category_update_resp_body)
+            .await
+    } else {
+        let resp: Response = Redirect::new("/zh-cn/sign-in").into();
+
+        Ok(resp.into())
+    }
+}
+
+pub async fn category_move(mut req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
+    let sign_status = sign_status(&req).await;
+    if sign_status.sign_in {
+        let category_id = String::from(req.param("category_id")?);
+        let move_info: CategoryMoveInfo = req.body_form().await?;
+
+        let category_move_build_query =
+            CategoryMoveData::build_query(category_move_data::Variables {
+                category_id,
+                parent_id: optional_id(&move_info.parent_id),
+                position: move_info.position,
+                token: sign_status.token.clone(),
+            });
+        let category_move_query = json!(category_move_build_query);
+
+        let category_move_resp_body: GqlResponse<serde_json::Value> =
+            gql_post(&req, &cfg)
+                .await
+                .body(category_move_query)
+                .recv_json()
+                .await?;
+
+        categories_admin_done(&cfg, &sign_status, category_move_resp_body).await
+    } else {
+        let resp: Response = Redirect::new("/zh-cn/sign-in").into();
+
+        Ok(resp.into())
+    }
+}
+
+pub async fn category_delete(mut req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
+    let sign_status = sign_status(&req).await;
+    if sign_status.sign_in {
+        let category_id = String::from(req.param("category_id")?);
+        let delete_info: CategoryDeleteInfo = req.body_form().await?;
+
+        let category_delete_build_query =
+            CategoryDeleteData::build_query(category_delete_data::Variables {
+                category_id,
+                reassign_id: optional_id(&delete_info.reassign_id),
+                token: sign_status.token.clone(),
+            });
+        let category_delete_query = json!(category_delete_build_query);
+
+        let category_delete_resp_body: GqlResponse<serde_json::Value> =
+            gql_post(&req, &cfg)
+                .await
+                .body(category_delete_query)
+                .recv_json()
+                .await?;
+
+        categories_admin_done(&cfg, &sign_status, category_delete_resp_body)
+            .await
+    } else {
+        let resp: Response = Redirect::new("/zh-cn/sign-in").into();
+
+        Ok(resp.into())
+    }
+}
+
+// back to the categories admin, or show the error of the failed operation
+async fn categories_admin_done(
+    cfg: &Config,
+    sign_status: &SignStatus,
+    resp_body: GqlResponse<serde_json::Value>,
+) -> tide::Result {
+    match resp_body.errors {
+        Some(errors) if !errors.is_empty() => {
+            let err = gql_error(Some(errors));
+            categories_admin_render(cfg, sign_status, Some(err)).await
+        }
+        _ => {
+            let resp: Response = Redirect::new("/admin/categories").into();
+
+            Ok(resp.into())
+        }
+    }
+}
+
+async fn categories_admin_render(
+    cfg: &Config,
+    sign_status: &SignStatus,
+    err: Option<serde_json::Value>,
+) -> tide::Result {
+    let mut admin_categories_tpl: Hbs =
+        Hbs::new("admin/admin-categories").await;
+    admin_categories_tpl
+        .reg_head()
+        .await
+        .reg_header()
+        .await
+        .reg_container()
+        .await
+        .reg_footer()
+        .await;
+    admin_categories_tpl.reg_script_values().await.reg_script_lang().await;
+
+    let mut data: BTreeMap<&str, serde_json::Value> = BTreeMap::new();
+    data.insert("language", json!("zh-cn"));
+    data.insert("nav-admin-selected", json!("is-selected"));
+    insert_user_by_username(cfg, sign_status, &mut data).await;
+    if let Some(err) = err {
+        data.insert("err", err);
+    }
+
+    let categories_tree_build_query =
+        CategoriesTreeData::build_query(categories_tree_data::Variables {
+            locale: String::from("zh-cn"),
+        });
+    let categories_tree_query = json!(categories_tree_build_query);
+
+    let categories_tree_resp_body: GqlResponse<serde_json::Value> =
+        surf::post(&gql_uri(cfg).await)
+            .body(categories_tree_query)
+            .recv_json()
+            .await?;
+    let categories_tree_resp_data =
+        categories_tree_resp_body.data.expect("无响应数据");
+
+    let categories_tree = categories_tree_resp_data["categoriesTree"].clone();
+    data.insert("categories_tree", categories_tree);
+
+    admin_categories_tpl.render(&data).await
+}
+
+fn optional_id(id: &str) -> Option<String> {
+    match id.trim() {
+        "" => None,
+        id => Some(id.to_string()),
+    }
+}
@@ -0,0 +1,186 @@
+use tide::{Request, Response, Redirect};
+use graphql_client::{GraphQLQuery, Response as GqlResponse};
+use serde_json::json;
+
+use crate::State;
+use crate::util::common::{gql_post, sign_status};
+
+use crate::models::comments::{
+    CommentInfo, CommentNewData, comment_new_data, CommentUpdateData,
+    comment_update_data, CommentDeleteData, comment_delete_data,
+    CommentHideData, comment_hide_data,
+};
+
+pub async fn comment_new(mut req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
+    let language = String::from(req.param("language")?);
+    let project_id = String::from(req.param("project_id")?);
+
+    let sign_status = sign_status(&req).await;
+    if sign_status.sign_in {
+        let comment_info: CommentInfo = req.body_form().await?;
+        let parent_id = match comment_info.parent_id.trim() {
+            "" => None,
+            parent_id => Some(parent_id.to_string()),
+        };
+
+        let comment_new_build_query =
+            CommentNewData::build_query(comment_new_data::Variables {
+                project_id: project_id.clone(),
+                parent_id,
+                content: comment_info.content,
+                token: sign_status.token,
+            });
+        let comment_new_query = json!(comment_new_build_query);
+
+        let _comment_new_resp_body: GqlResponse<serde_json::Value> =
+            gql_post(&req, &cfg)
+                .await
+                .body(comment_new_query)
+                .recv_json()
+                .await?;
+
+        let resp: Response =
+            Redirect::new(format!("/{}/project/{}", language, project_id))
+                .into();
+
+        Ok(resp.into())
+    } else {
+        let resp: Response =
+            Redirect::new(format!("/{}/sign-in", language)).into();
+
+        Ok(resp.into())
+    }
+}
+
+pub async fn comment_update(mut req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
+    let language = String::from(req.param("language")?);
+    let comment_id = String::from(req.param("comment_id")?);
+
+    let sign_status = sign_status(&req).await;
+    if sign_status.sign_in {
+        let comment_info: CommentInfo = req.body_form().await?;
+
+        let comment_update_build_query =
+            CommentUpdateData::build_query(comment_update_data::Variables {
+                comment_id,
+                content: comment_info.content,
+                token: sign_status.token,
+            });
+        let comment_update_query = json!(comment_update_build_query);
+
+        let comment_update_resp_body: GqlResponse<serde_json::Value> =
+            gql_post(&req, &cfg)
+                .await
+                .body(comment_update_query)
+                .recv_json()
+                .await?;
+
+        let resp: Response = Redirect::new(comment_redirect_uri(
+            &language,
+            comment_update_resp_body.data,
+            "commentUpdate",
+        ))
+        .into();
+
+        Ok(resp.into())
+    } else {
+        let resp: Response =
+            Redirect::new(format!("/{}/sign-in", language)).into();
+
+        Ok(resp.into())
+    }
+}
+
+pub async fn comment_delete(req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
+    let language = String::from(req.param("language")?);
+
+    let sign_status = sign_status(&req).await;
+    if sign_status.sign_in {
+        let comment_id = req.param("comment_id")?;
+
+        let comment_delete_build_query =
+            CommentDeleteData::build_query(comment_delete_data::Variables {
+                comment_id: comment_id.to_string(),
+                token: sign_status.token,
+            });
+        let comment_delete_query = json!(comment_delete_build_query);
+
+        let comment_delete_resp_body: GqlResponse<serde_json::Value> =
+            gql_post(&req, &cfg)
+                .await
+                .body(comment_delete_query)
+                .recv_json()
+                .await?;
+
+        let resp: Response = Redirect::new(comment_redirect_uri(
+            &language,
+            comment_delete_resp_body.data,
+            "commentDelete",
+        ))
+        .into();
+
+        Ok(resp.into())
+    } else {
+        let resp: Response =
+            Redirect::new(format!("/{}/sign-in", language)).into();
+
+        Ok(resp.into())
+    }
+}
+
+pub async fn comment_hide(req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
+    let language = String::from(req.param("language")?);
+
+    let sign_status = sign_status(&req).await;
+    if sign_status.sign_in {
+        let comment_id = req.param("comment_id")?;
+
+        let comment_hide_build_query =
+            CommentHideData::build_query(comment_hide_data::Variables {
+                comment_id: comment_id.to_string(),
+                hidden: true,
+                token: sign_status.token,
+            });
+        let comment_hide_query = json!(comment_hide_build_query);
+
+        let comment_hide_resp_body: GqlResponse<serde_json::Value> =
+            gql_post(&req, &cfg)
+                .await
+                .body(comment_hide_query)
+                .recv_json()
+                .await?;
+
+        let resp: Response = Redirect::new(comment_redirect_uri(
+            &language,
+            comment_hide_resp_body.data,
+            "commentHide",
+        ))
+        .into();
+
+        Ok(resp.into())
+    } else {
+        let resp: Response =
+            Redirect::new(format!("/{}/sign-in", language)).into();
+
+        Ok(resp.into())
+    }
+}
+
+// back to the project of the comment, or home if the mutation failed
+fn comment_redirect_uri(
+    language: &str,
+    resp_data: Option<serde_json::Value>,
+    mutation: &str,
+) -> String {
+    match resp_data {
+        Some(data) => match data[mutation]["projectId"].as_str() {
+            Some(project_id) => format!("/{}/project/{}", language, project_id),
+            None => format!("/{}", language),
+        },
+        None => format!("/{}", language),
+    }
+}
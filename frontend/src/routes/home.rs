@@ -8,23 +8,25 @@ use serde_json::{json, Value};
 
 use crate::State;
 use crate::util::{
-    constant::CFG,
-    common::gql_uri,
+    config::Config,
+    common::{gql_post, gql_post_as, sign_status, gql_error},
     email::send_email,
     tpl::{Hbs, insert_wish_random},
 };
 
 use crate::models::{
     home::{
-        HomeData, home_data, RegisterInfo, SignInInfo, RegisterData,
-        register_data, SignInData, sign_in_data,
+        HomeData, home_data, FeedData, feed_data, RegisterInfo, SignInInfo,
+        RegisterData, register_data, SignInData, sign_in_data,
     },
+    users::SignStatus,
     topics::{
         TopicsNewData, topics_new_data, TopicUserNewData, topic_user_new_data,
     },
 };
 
 pub async fn init(req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
     let accept_language = req.header("accept-language");
     let language =
         String::from(if let Some(client_language) = accept_language {
@@ -37,21 +39,26 @@ pub async fn init(req: Request<State>) -> tide::Result {
             "en-us"
         });
 
-    let (init_tpl, data) = init_index(language).await;
+    let sign_status = sign_status(&req).await;
+    let (init_tpl, data) = init_index(&cfg, language, sign_status).await;
 
     init_tpl.render(&data).await
 }
 
 pub async fn index(req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
     let language = String::from(req.param("language")?);
 
-    let (index_tpl, data) = init_index(language).await;
+    let sign_status = sign_status(&req).await;
+    let (index_tpl, data) = init_index(&cfg, language, sign_status).await;
 
     index_tpl.render(&data).await
 }
 
 async fn init_index<'ii>(
+    cfg: &Config,
     language: String,
+    sign_status: SignStatus,
 ) -> (Hbs<'ii>, BTreeMap<&'ii str, Value>) {
     let mut tpl: Hbs = Hbs::new("index").await;
     tpl.reg_head().await.reg_container().await.reg_footer().await;
@@ -59,16 +66,18 @@ async fn init_index<'ii>(
 
     let mut data: BTreeMap<&str, serde_json::Value> = BTreeMap::new();
     data.insert("language", json!(language));
-    insert_wish_random(&mut data).await;
+    insert_wish_random(cfg, &mut data).await;
 
     // insert home data
     let home_build_query = HomeData::build_query(home_data::Variables {
         username: "-".to_string(),
+        locale: language.clone(),
     });
     let home_query = json!(home_build_query);
 
     let home_resp_body: GqlResponse<serde_json::Value> =
-        surf::post(&gql_uri().await)
+        gql_post_as(cfg, &language, &sign_status.token)
+            .await
             .body(home_query)
             .recv_json()
             .await
@@ -84,10 +93,32 @@ async fn init_index<'ii>(
     let published_projects = home_resp_data["publishedProjects"].clone();
     data.insert("published_projects", published_projects);
 
+    // insert activity feed of the users followed
+    if sign_status.sign_in {
+        let feed_build_query = FeedData::build_query(feed_data::Variables {
+            token: sign_status.token.clone(),
+            from_page: 1,
+        });
+        let feed_query = json!(feed_build_query);
+
+        let feed_resp_body: GqlResponse<serde_json::Value> =
+            gql_post_as(cfg, &language, &sign_status.token)
+                .await
+                .body(feed_query)
+                .recv_json()
+                .await
+                .unwrap();
+        if let Some(feed_resp_data) = feed_resp_body.data {
+            data.insert("sign-in", json!(sign_status.sign_in));
+            data.insert("feed", feed_resp_data["feed"].clone());
+        }
+    }
+
     (tpl, data)
 }
 
 pub async fn register(mut req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
     let language = String::from(req.param("language")?);
 
     let mut register_tpl: Hbs = Hbs::new("register").await;
@@ -105,7 +136,7 @@ pub async fn register(mut req: Request<State>) -> tide::Result {
     let mut data: BTreeMap<&str, serde_json::Value> = BTreeMap::new();
     data.insert("language", json!(language));
     data.insert("register-nav-selected", json!("is-selected"));
-    insert_wish_random(&mut data).await;
+    insert_wish_random(&cfg, &mut data).await;
 
     if req.method().eq(&Method::Post) {
         let register_info: RegisterInfo = req.body_form().await?;
@@ -125,7 +156,7 @@ pub async fn register(mut req: Request<State>) -> tide::Result {
         let query = json!(build_query);
 
         let resp_body: GqlResponse<serde_json::Value> =
-            surf::post(&gql_uri().await).body(query).recv_json().await?;
+            gql_post(&req, &cfg).await.body(query).recv_json().await?;
         let resp_data = resp_body.data;
 
         if let Some(register_val) = resp_data {
@@ -140,7 +171,8 @@ pub async fn register(mut req: Request<State>) -> tide::Result {
             let topics_query = json!(topics_build_query);
 
             let topics_resp_body: GqlResponse<serde_json::Value> =
-                surf::post(&gql_uri().await)
+                gql_post(&req, &cfg)
+                    .await
                     .body(topics_query)
                     .recv_json()
                     .await?;
@@ -158,7 +190,8 @@ pub async fn register(mut req: Request<State>) -> tide::Result {
                     );
                     let topic_user_query = json!(topic_user_build_query);
                     let _topic_user_resp_body: GqlResponse<serde_json::Value> =
-                        surf::post(&gql_uri().await)
+                        gql_post(&req, &cfg)
+                            .await
                             .body(topic_user_query)
                             .recv_json()
                             .await?;
@@ -166,6 +199,7 @@ pub async fn register(mut req: Request<State>) -> tide::Result {
             }
 
             send_email(
+                &cfg,
                 language,
                 user_id.to_string(),
                 register_info.username,
@@ -176,10 +210,7 @@ pub async fn register(mut req: Request<State>) -> tide::Result {
 
             data.insert("register_result", register_result);
         } else {
-            data.insert(
-                "register_failed",
-                json!(resp_body.errors.unwrap()[0].message),
-            );
+            data.insert("register_failed", gql_error(resp_body.errors));
         }
     }
 
@@ -187,6 +218,7 @@ pub async fn register(mut req: Request<State>) -> tide::Result {
 }
 
 pub async fn sign_in(mut req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
     let language = String::from(req.param("language")?);
 
     let mut sign_in_tpl: Hbs = Hbs::new("sign-in").await;
@@ -204,7 +236,7 @@ pub async fn sign_in(mut req: Request<State>) -> tide::Result {
     let mut data: BTreeMap<&str, serde_json::Value> = BTreeMap::new();
     data.insert("language", json!(language));
     data.insert("sign-in-nav-selected", json!("is-selected"));
-    insert_wish_random(&mut data).await;
+    insert_wish_random(&cfg, &mut data).await;
 
     match req.method() {
         Method::Post => {
@@ -218,7 +250,7 @@ pub async fn sign_in(mut req: Request<State>) -> tide::Result {
             let query = json!(build_query);
 
             let resp_body: GqlResponse<serde_json::Value> =
-                surf::post(&gql_uri().await).body(query).recv_json().await?;
+                gql_post(&req, &cfg).await.body(query).recv_json().await?;
             let resp_data = resp_body.data;
 
             if let Some(sign_in_val) = resp_data {
@@ -231,29 +263,23 @@ pub async fn sign_in(mut req: Request<State>) -> tide::Result {
                     "username",
                     String::from(sign_in_user["username"].as_str().unwrap()),
                 );
-                set_cookie(&mut username_cookie).await;
+                set_cookie(&cfg, &mut username_cookie).await;
                 resp.insert_cookie(username_cookie);
 
                 let mut token_cookie = Cookie::new(
                     "token",
                     String::from(sign_in_user["token"].as_str().unwrap()),
                 );
-                set_cookie(&mut token_cookie).await;
+                set_cookie(&cfg, &mut token_cookie).await;
                 resp.insert_cookie(token_cookie);
 
                 Ok(resp.into())
             } else {
-                let error = resp_body.errors.unwrap()[0].clone();
-                data.insert("sign_in_failed", json!(error.message));
-
-                if let Some(eev) = error.extensions {
-                    let sign_in_failed_user_id =
-                        eev.get("user_id").unwrap().as_str().unwrap();
-                    data.insert(
-                        "sign_in_failed_user_id",
-                        json!(sign_in_failed_user_id),
-                    );
+                let sign_in_failed = gql_error(resp_body.errors);
+                if let Some(user_id) = sign_in_failed.get("user_id") {
+                    data.insert("sign_in_failed_user_id", user_id.clone());
                 }
+                data.insert("sign_in_failed", sign_in_failed);
 
                 sign_in_tpl.render(&data).await
             }
@@ -263,28 +289,27 @@ pub async fn sign_in(mut req: Request<State>) -> tide::Result {
 }
 
 pub async fn sign_out(req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
     let language = String::from(req.param("language")?);
     let mut resp: Response = Redirect::new(format!("/{}", language)).into();
 
     let username_cookie = req.cookie("username");
     if let Some(mut cookie) = username_cookie {
-        set_cookie(&mut cookie).await;
+        set_cookie(&cfg, &mut cookie).await;
         resp.remove_cookie(cookie);
     }
 
     let token_cookie = req.cookie("token");
     if let Some(mut cookie) = token_cookie {
-        set_cookie(&mut cookie).await;
+        set_cookie(&cfg, &mut cookie).await;
         resp.remove_cookie(cookie);
     }
 
     Ok(resp.into())
 }
 
-async fn set_cookie<'c>(cookie: &mut Cookie<'c>) {
-    let domain = CFG.get("DOMAIN").unwrap();
-
-    cookie.set_domain(domain);
+async fn set_cookie<'c>(cfg: &Config, cookie: &mut Cookie<'c>) {
+    cookie.set_domain(cfg.domain.clone());
     cookie.set_path("/");
     cookie.set_secure(true);
     cookie.set_http_only(true);
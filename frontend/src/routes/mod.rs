@@ -5,6 +5,9 @@ pub mod users;
 pub mod projects;
 pub mod categories;
 pub mod topics;
+pub mod comments;
+pub mod notifications;
+pub mod studios;
 pub mod admin;
 
 use crate::State;
@@ -28,8 +31,19 @@ pub async fn push_res(app: &mut Server<State>) {
     admin.at("/projects").get(super::routes::admin::projects_admin);
     admin.at("/project/:project_id").get(super::routes::admin::project_admin);
     admin
-        .at("/project/:project_id/:field_name/:field_val")
-        .get(super::routes::admin::project_update_one_field);
+        .at("/project/:project_id/status/:status")
+        .get(super::routes::admin::project_set_status);
+    admin.at("/categories").get(super::routes::categories::categories_admin);
+    admin.at("/category/new").post(super::routes::categories::category_new);
+    admin
+        .at("/category/:category_id/update")
+        .post(super::routes::categories::category_update);
+    admin
+        .at("/category/:category_id/move")
+        .post(super::routes::categories::category_move);
+    admin
+        .at("/category/:category_id/delete")
+        .post(super::routes::categories::category_delete);
 
     let mut home = app.at("/:language");
     home.at("/").get(super::routes::home::index);
@@ -52,9 +66,21 @@ pub async fn push_res(app: &mut Server<State>) {
     user.at("/:author_username").get(super::routes::users::user_index);
     user.at("/:author_username/projects")
         .get(super::routes::projects::projects_by_user);
+    user.at("/:author_username/starred")
+        .get(super::routes::projects::projects_starred_by_user);
+    user.at("/:author_username/stats")
+        .get(super::routes::projects::projects_stats_by_user);
+    user.at("/:author_username/followers")
+        .get(super::routes::users::user_followers);
+    user.at("/:author_username/following")
+        .get(super::routes::users::user_following);
+    user.at("/:author_username/follow").get(super::routes::users::user_follow);
+    user.at("/:author_username/unfollow")
+        .get(super::routes::users::user_unfollow);
 
     let mut projects = home.at("/projects");
     projects.at("/").get(super::routes::projects::projects_index);
+    projects.at("/trash").get(super::routes::projects::projects_trash);
     projects.at("/:filter_str").get(super::routes::projects::projects_filter);
 
     let mut project = home.at("/project");
@@ -64,17 +90,99 @@ pub async fn push_res(app: &mut Server<State>) {
         .get(super::routes::projects::project_new)
         .post(super::routes::projects::project_new);
     project.at("/:project_id").get(super::routes::projects::project_index);
+    project
+        .at("/:project_id/delete")
+        .get(super::routes::projects::project_delete);
+    project
+        .at("/:project_id/restore")
+        .get(super::routes::projects::project_restore);
+    project
+        .at("/:project_id/download")
+        .get(super::routes::projects::project_download);
+    project
+        .at("/:project_id/stats")
+        .get(super::routes::projects::project_stats);
+    project.at("/:project_id/star").get(super::routes::projects::project_star);
+    project
+        .at("/:project_id/unstar")
+        .get(super::routes::projects::project_unstar);
+    project
+        .at("/:project_id/remix")
+        .get(super::routes::projects::project_remix);
+    project
+        .at("/:project_id/comments/:switch")
+        .get(super::routes::projects::project_comments_enable);
+    project
+        .at("/:project_id/comment")
+        .post(super::routes::comments::comment_new);
+
+    let mut notifications = home.at("/notifications");
+    notifications
+        .at("/")
+        .get(super::routes::notifications::notifications_index);
+    notifications
+        .at("/read-all")
+        .get(super::routes::notifications::notifications_read_all);
+    notifications
+        .at("/ticket")
+        .get(super::routes::notifications::notifications_ticket);
+    home.at("/notification/:notification_id/read")
+        .get(super::routes::notifications::notification_read);
+
+    let mut comment = home.at("/comment");
+    comment
+        .at("/:comment_id/edit")
+        .post(super::routes::comments::comment_update);
+    comment
+        .at("/:comment_id/delete")
+        .get(super::routes::comments::comment_delete);
+    comment.at("/:comment_id/hide").get(super::routes::comments::comment_hide);
     project
         .at("/file/new/:file_name/:file_kind")
         .put(super::routes::projects::file_new);
 
+    let mut studios = home.at("/studios");
+    studios.at("/").get(super::routes::studios::studios_index);
+    studios
+        .at("/new")
+        .get(super::routes::studios::studio_new)
+        .post(super::routes::studios::studio_new);
+
+    let mut studio = home.at("/studio");
+    studio.at("/:studio_slug").get(super::routes::studios::studio_index);
+    studio
+        .at("/:studio_slug/edit")
+        .get(super::routes::studios::studio_edit)
+        .post(super::routes::studios::studio_edit);
+    studio
+        .at("/:studio_id/curator/invite")
+        .post(super::routes::studios::studio_curator_invite);
+    studio
+        .at("/:studio_id/curator/accept")
+        .get(super::routes::studios::studio_curator_accept);
+    studio
+        .at("/:studio_id/curator/:user_id/remove")
+        .get(super::routes::studios::studio_curator_remove);
+    studio
+        .at("/:studio_id/project/add")
+        .post(super::routes::studios::studio_project_add);
+    studio
+        .at("/:studio_id/project/:project_id/remove")
+        .get(super::routes::studios::studio_project_remove);
+    studio
+        .at("/:studio_id/project/:project_id/move")
+        .get(super::routes::studios::studio_project_move);
+
     // let mut categories = app.at("/categories");
     let mut category = home.at("/category");
     category
         .at("/:category_slug/projects")
         .get(super::routes::projects::projects_by_category);
 
-    // let mut topics = app.at("/topics");
+    let mut topics = home.at("/topics");
+    topics.at("/suggest").get(super::routes::topics::topics_suggest);
+    topics.at("/for-content").post(super::routes::topics::topics_for_content);
+
     let mut topic = home.at("/topic");
     topic
         .at("/:topic_slug/projects")
@@ -0,0 +1,210 @@
+use std::collections::BTreeMap;
+use tide::{Request, Response, Redirect, StatusCode};
+use graphql_client::{GraphQLQuery, Response as GqlResponse};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::State;
+use crate::util::{
+    common::{gql_post, sign_status},
+    tpl::{Hbs, insert_user_by_username, insert_wish_random},
+};
+
+use crate::models::{
+    Page,
+    notifications::{
+        NotificationsData, notifications_data, NotificationMarkReadData,
+        notification_mark_read_data, NotificationsMarkAllReadData,
+        notifications_mark_all_read_data, SubscriptionTicketData,
+        subscription_ticket_data,
+    },
+};
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct NotificationsFilter {
+    unread: bool,
+}
+
+pub async fn notifications_index(req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
+    let language = String::from(req.param("language")?);
+
+    let sign_status = sign_status(&req).await;
+    if !sign_status.sign_in {
+        let resp: Response =
+            Redirect::new(format!("/{}/sign-in", language)).into();
+
+        return Ok(resp.into());
+    }
+
+    let mut notifications_index_tpl: Hbs =
+        Hbs::new("users/users-notifications").await;
+    notifications_index_tpl
+        .reg_head()
+        .await
+        .reg_header()
+        .await
+        .reg_container()
+        .await
+        .reg_pagination()
+        .await
+        .reg_footer()
+        .await;
+    notifications_index_tpl
+        .reg_script_values()
+        .await
+        .reg_script_ops()
+        .await
+        .reg_script_lang()
+        .await;
+
+    let mut data: BTreeMap<&str, serde_json::Value> = BTreeMap::new();
+    data.insert("language", json!(language));
+    insert_wish_random(&cfg, &mut data).await;
+    insert_user_by_username(&cfg, &sign_status, &mut data).await;
+
+    let filter: NotificationsFilter = req.query()?;
+    data.insert("unread_only", json!(filter.unread));
+
+    let page: Page = req.query()?;
+    let notifications_build_query =
+        NotificationsData::build_query(notifications_data::Variables {
+            token: sign_status.token,
+            unread_only: filter.unread,
+            from_page: page.from,
+            first_oid: page.first,
+            last_oid: page.last,
+        });
+    let notifications_query = json!(notifications_build_query);
+
+    let notifications_resp_body: GqlResponse<serde_json::Value> =
+        gql_post(&req, &cfg)
+            .await
+            .body(notifications_query)
+            .recv_json()
+            .await?;
+    let notifications_resp_data =
+        notifications_resp_body.data.expect("无响应数据");
+
+    let notifications = notifications_resp_data["notifications"].clone();
+    data.insert("pagination", notifications);
+
+    notifications_index_tpl.render(&data).await
+}
+
+// mark the notification as read, then go to its project
+pub async fn notification_read(req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
+    let language = String::from(req.param("language")?);
+
+    let sign_status = sign_status(&req).await;
+    if sign_status.sign_in {
+        let notification_id = req.param("notification_id")?;
+
+        let notification_mark_read_build_query =
+            NotificationMarkReadData::build_query(
+                notification_mark_read_data::Variables {
+                    notification_id: notification_id.to_string(),
+                    token: sign_status.token,
+                },
+            );
+        let notification_mark_read_query =
+            json!(notification_mark_read_build_query);
+
+        let notification_mark_read_resp_body: GqlResponse<serde_json::Value> =
+            gql_post(&req, &cfg)
+                .await
+                .body(notification_mark_read_query)
+                .recv_json()
+                .await?;
+
+        let redirect_uri = match notification_mark_read_resp_body.data {
+            Some(data) => {
+                match data["notificationMarkRead"]["projectId"].as_str() {
+                    Some(project_id) => {
+                        format!("/{}/project/{}", language, project_id)
+                    }
+                    None => format!("/{}/notifications", language),
+                }
+            }
+            None => format!("/{}/notifications", language),
+        };
+        let resp: Response = Redirect::new(redirect_uri).into();
+
+        Ok(resp.into())
+    } else {
+        let resp: Response =
+            Redirect::new(format!("/{}/sign-in", language)).into();
+
+        Ok(resp.into())
+    }
+}
+
+pub async fn notifications_read_all(req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
+    let language = String::from(req.param("language")?);
+
+    let sign_status = sign_status(&req).await;
+    if sign_status.sign_in {
+        let notifications_mark_all_read_build_query =
+            NotificationsMarkAllReadData::build_query(
+                notifications_mark_all_read_data::Variables {
+                    token: sign_status.token,
+                },
+            );
+        let notifications_mark_all_read_query =
+            json!(notifications_mark_all_read_build_query);
+
+        let _notifications_mark_all_read_resp_body: GqlResponse<
+            serde_json::Value,
+        > = gql_post(&req, &cfg)
+            .await
+            .body(notifications_mark_all_read_query)
+            .recv_json()
+            .await?;
+
+        let resp: Response =
+            Redirect::new(format!("/{}/notifications", language)).into();
+
+        Ok(resp.into())
+    } else {
+        let resp: Response =
+            Redirect::new(format!("/{}/sign-in", language)).into();
+
+        Ok(resp.into())
+    }
+}
+
+// Ticket of the notifications subscription, fetched by the page from the
+// same origin, so that the session token is never written into the page.
+pub async fn notifications_ticket(req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
+
+    let sign_status = sign_status(&req).await;
+    if !sign_status.sign_in {
+        return Ok(Response::new(StatusCode::Unauthorized));
+    }
+
+    let subscription_ticket_build_query = SubscriptionTicketData::build_query(
+        subscription_ticket_data::Variables { token: sign_status.token },
+    );
+    let subscription_ticket_query = json!(subscription_ticket_build_query);
+
+    let subscription_ticket_resp_body: GqlResponse<serde_json::Value> =
+        gql_post(&req, &cfg)
+            .await
+            .body(subscription_ticket_query)
+            .recv_json()
+            .await?;
+    match subscription_ticket_resp_body.data {
+        Some(subscription_ticket_resp_data) => {
+            let res = json!({
+                "ticket": subscription_ticket_resp_data["subscriptionTicket"]
+            });
+
+            Ok(res.into())
+        }
+        None => Ok(Response::new(StatusCode::Unauthorized)),
+    }
+}
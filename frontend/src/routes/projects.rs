@@ -4,40 +4,53 @@ use std::{
 };
 use async_std::path::Path;
 
-use tide::{Request, Response, Redirect, http::Method};
+use tide::{Request, Response, Redirect, StatusCode, http::Method};
 use graphql_client::{GraphQLQuery, Response as GqlResponse};
 use serde_json::json;
 use percent_encoding::percent_decode;
 
 use crate::State;
 use crate::util::{
-    common::{gql_uri, sign_status},
+    config::Config,
+    common::{
+        gql_uri, gql_post, gql_ws_uri, sign_status, visitor_fingerprint,
+        visitor_signature, gql_error,
+    },
     tpl::{
         Hbs, insert_user_by_username, insert_wish_random, insert_categories,
     },
     upload::file_copy,
+    chart::bars_svg,
 };
 
 use crate::models::{
     Page,
-    users::{UserByUsernameData, user_by_username_data},
+    users::{SignStatus, UserByUsernameData, user_by_username_data},
     projects::{
-        ProjectInfo, ProjectsData, projects_data, ProjectsByUserData,
+        ProjectInfo, TrendingInfo, StatsInfo, ProjectsData, projects_data,
+        ProjectsTrendingData, projects_trending_data, ProjectsByUserData,
         projects_by_user_data, ProjectsByCategoryData,
         projects_by_category_data, ProjectsByTopicData, projects_by_topic_data,
-        ProjectData, project_data, ProjectNewData, project_new_data,
-        ProjectUpdateOneFieldByIdData, project_update_one_field_by_id_data,
-        ProjectRandomData, project_random_data, FileNewData, file_new_data,
-        ProjectFileNewData, project_file_new_data,
+        ProjectData, project_data, ProjectBySlugData, project_by_slug_data,
+        ProjectPublishData, project_publish_data, ProjectViewRecordData,
+        project_view_record_data, ProjectDownloadRecordData,
+        project_download_record_data, ProjectStatsData, project_stats_data,
+        UserStatsData, user_stats_data, ProjectRandomData, project_random_data,
+        FileNewData, file_new_data, ProjectDeleteData, project_delete_data,
+        ProjectRestoreData, project_restore_data, ProjectsTrashData,
+        projects_trash_data, ProjectStarData, project_star_data,
+        ProjectUnstarData, project_unstar_data, ProjectRemixData,
+        project_remix_data, ProjectRemixTreeData, project_remix_tree_data,
+        ProjectsStarredByUserData, projects_starred_by_user_data,
+        ProjectCommentsEnableData, project_comments_enable_data,
     },
+    comments::{CommentsByProjectData, comments_by_project_data},
     categories::{CategoryBySlugData, category_by_slug_data},
-    topics::{
-        TopicsNewData, topics_new_data, TopicProjectNewData,
-        topic_project_new_data, TopicBySlugData, topic_by_slug_data,
-    },
+    topics::{TopicBySlugData, topic_by_slug_data},
 };
 
 pub async fn projects_index(req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
     let language = String::from(req.param("language")?);
 
     let mut projects_index_tpl: Hbs = Hbs::new("projects/projects-index").await;
@@ -58,13 +71,14 @@ pub async fn projects_index(req: Request<State>) -> tide::Result {
     data.insert("language", json!(language));
     data.insert("nav-projects-selected", json!("is-selected"));
     data.insert("projects-all-selected", json!("is-selected"));
-    insert_wish_random(&mut data).await;
+    data.insert("gql_ws", json!(gql_ws_uri(&cfg).await));
+    insert_wish_random(&cfg, &mut data).await;
 
     let sign_status = sign_status(&req).await;
     if sign_status.sign_in {
-        insert_user_by_username(sign_status.username, &mut data).await;
+        insert_user_by_username(&cfg, &sign_status, &mut data).await;
     }
-    insert_categories(&mut data).await;
+    insert_categories(&cfg, &language, &mut data).await;
 
     let page: Page = req.query().unwrap();
     let projects_build_query =
@@ -72,12 +86,14 @@ pub async fn projects_index(req: Request<State>) -> tide::Result {
             from_page: page.from,
             first_oid: page.first,
             last_oid: page.last,
-            status: 1,
+            status: Some(projects_data::ProjectStatus::PUBLISHED),
+            locale: language.clone(),
         });
     let projects_query = json!(projects_build_query);
 
     let projects_resp_body: GqlResponse<serde_json::Value> =
-        surf::post(&gql_uri().await)
+        gql_post(&req, &cfg)
+            .await
             .body(projects_query)
             .recv_json()
             .await
@@ -91,6 +107,7 @@ pub async fn projects_index(req: Request<State>) -> tide::Result {
 }
 
 pub async fn projects_by_user(req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
     let language = String::from(req.param("language")?);
 
     let mut projects_by_user_tpl: Hbs =
@@ -111,13 +128,13 @@ pub async fn projects_by_user(req: Request<State>) -> tide::Result {
     let mut data: BTreeMap<&str, serde_json::Value> = BTreeMap::new();
     data.insert("language", json!(language));
     data.insert("nav-projects-selected", json!("is-selected"));
-    insert_wish_random(&mut data).await;
+    insert_wish_random(&cfg, &mut data).await;
 
     let sign_status = sign_status(&req).await;
     if sign_status.sign_in {
-        insert_user_by_username(sign_status.username, &mut data).await;
+        insert_user_by_username(&cfg, &sign_status, &mut data).await;
     }
-    insert_categories(&mut data).await;
+    insert_categories(&cfg, &language, &mut data).await;
 
     let author_username = req.param("author_username")?;
     let author_by_username_build_query =
@@ -127,7 +144,8 @@ pub async fn projects_by_user(req: Request<State>) -> tide::Result {
     let author_by_username_query = json!(author_by_username_build_query);
 
     let author_by_username_resp_body: GqlResponse<serde_json::Value> =
-        surf::post(&gql_uri().await)
+        gql_post(&req, &cfg)
+            .await
             .body(author_by_username_query)
             .recv_json()
             .await
@@ -155,12 +173,14 @@ pub async fn projects_by_user(req: Request<State>) -> tide::Result {
             from_page: page.from,
             first_oid: page.first,
             last_oid: page.last,
-            status: 1,
+            status: Some(projects_by_user_data::ProjectStatus::PUBLISHED),
+            locale: language.clone(),
         });
     let projects_by_user_query = json!(projects_by_user_build_query);
 
     let projects_by_user_resp_body: GqlResponse<serde_json::Value> =
-        surf::post(&gql_uri().await)
+        gql_post(&req, &cfg)
+            .await
             .body(projects_by_user_query)
             .recv_json()
             .await?;
@@ -174,7 +194,80 @@ pub async fn projects_by_user(req: Request<State>) -> tide::Result {
     projects_by_user_tpl.render(&data).await
 }
 
+pub async fn projects_starred_by_user(req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
+    let language = String::from(req.param("language")?);
+
+    let mut projects_starred_by_user_tpl: Hbs =
+        Hbs::new("projects/projects-index").await;
+    projects_starred_by_user_tpl
+        .reg_head()
+        .await
+        .reg_header()
+        .await
+        .reg_container()
+        .await
+        .reg_pagination()
+        .await
+        .reg_footer()
+        .await;
+    projects_starred_by_user_tpl
+        .reg_script_values()
+        .await
+        .reg_script_lang()
+        .await;
+
+    let mut data: BTreeMap<&str, serde_json::Value> = BTreeMap::new();
+    data.insert("language", json!(language));
+    data.insert("nav-projects-selected", json!("is-selected"));
+    insert_wish_random(&cfg, &mut data).await;
+
+    let sign_status = sign_status(&req).await;
+    if sign_status.sign_in {
+        insert_user_by_username(&cfg, &sign_status, &mut data).await;
+    }
+    insert_categories(&cfg, &language, &mut data).await;
+
+    let author_username = req.param("author_username")?;
+    data.insert(
+        "filter_desc",
+        json!({
+            "condition": "starred",
+            "content": "projects-filter-starred"
+        }),
+    );
+
+    let page: Page = req.query()?;
+    let projects_starred_by_user_build_query =
+        ProjectsStarredByUserData::build_query(
+            projects_starred_by_user_data::Variables {
+                username: String::from(author_username),
+                from_page: page.from,
+                first_oid: page.first,
+                last_oid: page.last,
+                locale: language.clone(),
+            },
+        );
+    let projects_starred_by_user_query =
+        json!(projects_starred_by_user_build_query);
+
+    let projects_starred_by_user_resp_body: GqlResponse<serde_json::Value> =
+        gql_post(&req, &cfg)
+            .await
+            .body(projects_starred_by_user_query)
+            .recv_json()
+            .await?;
+    let projects_starred_by_user_resp_data =
+        projects_starred_by_user_resp_body.data.expect("无响应数据");
+
+    let author = projects_starred_by_user_resp_data["userByUsername"].clone();
+    data.insert("pagination", author["starredProjects"].clone());
+
+    projects_starred_by_user_tpl.render(&data).await
+}
+
 pub async fn projects_by_category(req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
     let language = String::from(req.param("language")?);
 
     let mut projects_by_category_tpl: Hbs =
@@ -196,23 +289,25 @@ pub async fn projects_by_category(req: Request<State>) -> tide::Result {
     data.insert("language", json!(language));
     data.insert("nav-projects-selected", json!("is-selected"));
     data.insert("projects-all-selected", json!("is-selected"));
-    insert_wish_random(&mut data).await;
+    insert_wish_random(&cfg, &mut data).await;
 
     let sign_status = sign_status(&req).await;
     if sign_status.sign_in {
-        insert_user_by_username(sign_status.username, &mut data).await;
+        insert_user_by_username(&cfg, &sign_status, &mut data).await;
     }
-    insert_categories(&mut data).await;
+    insert_categories(&cfg, &language, &mut data).await;
 
     let category_slug = req.param("category_slug")?;
     let category_by_slug_build_query =
         CategoryBySlugData::build_query(category_by_slug_data::Variables {
             slug: String::from(category_slug),
+            locale: language.clone(),
         });
     let category_by_slug_query = json!(category_by_slug_build_query);
 
     let category_by_slug_resp_body: GqlResponse<serde_json::Value> =
-        surf::post(&gql_uri().await)
+        gql_post(&req, &cfg)
+            .await
             .body(category_by_slug_query)
             .recv_json()
             .await
@@ -221,14 +316,24 @@ pub async fn projects_by_category(req: Request<State>) -> tide::Result {
         category_by_slug_resp_body.data.expect("无响应数据");
 
     let category = category_by_slug_resp_data["categoryBySlug"].clone();
+    // an old slug before renaming, moved to the current one permanently
+    let current_slug = category["slug"].as_str().unwrap_or(category_slug);
+    if current_slug != category_slug {
+        let resp: Response = Redirect::permanent(format!(
+            "/{}/category/{}/projects",
+            language, current_slug
+        ))
+        .into();
+
+        return Ok(resp.into());
+    }
+    data.insert("category_breadcrumb", category["breadcrumb"].clone());
+    data.insert("category_children", category["children"].clone());
     data.insert(
         "filter_desc",
         json!({
-            "condition": category["nameEn"].as_str().unwrap(),
-            "content": match language.as_str() {
-                "zh-cn" => category["nameZh"].as_str().unwrap(),
-                _ => category["nameEn"].as_str().unwrap(),
-            }
+            "condition": category["slug"].as_str().unwrap(),
+            "content": category["name"].as_str().unwrap()
         }),
     );
 
@@ -239,13 +344,15 @@ pub async fn projects_by_category(req: Request<State>) -> tide::Result {
             from_page: page.from,
             first_oid: page.first,
             last_oid: page.last,
-            status: 1,
+            status: Some(projects_by_category_data::ProjectStatus::PUBLISHED),
+            locale: language.clone(),
         },
     );
     let projects_by_category_query = json!(projects_by_category_build_query);
 
     let projects_by_category_resp_body: GqlResponse<serde_json::Value> =
-        surf::post(&gql_uri().await)
+        gql_post(&req, &cfg)
+            .await
             .body(projects_by_category_query)
             .recv_json()
             .await?;
@@ -260,6 +367,7 @@ pub async fn projects_by_category(req: Request<State>) -> tide::Result {
 }
 
 pub async fn projects_by_topic(req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
     let language = String::from(req.param("language")?);
 
     let mut projects_by_topic_tpl: Hbs =
@@ -281,13 +389,13 @@ pub async fn projects_by_topic(req: Request<State>) -> tide::Result {
     data.insert("language", json!(language));
     data.insert("nav-projects-selected", json!("is-selected"));
     data.insert("projects-all-selected", json!("is-selected"));
-    insert_wish_random(&mut data).await;
+    insert_wish_random(&cfg, &mut data).await;
 
     let sign_status = sign_status(&req).await;
     if sign_status.sign_in {
-        insert_user_by_username(sign_status.username, &mut data).await;
+        insert_user_by_username(&cfg, &sign_status, &mut data).await;
     }
-    insert_categories(&mut data).await;
+    insert_categories(&cfg, &language, &mut data).await;
 
     let topic_slug = req.param("topic_slug")?;
     let topic_by_slug_build_query =
@@ -297,7 +405,8 @@ pub async fn projects_by_topic(req: Request<State>) -> tide::Result {
     let topic_by_slug_query = json!(topic_by_slug_build_query);
 
     let topic_by_slug_resp_body: GqlResponse<serde_json::Value> =
-        surf::post(&gql_uri().await)
+        gql_post(&req, &cfg)
+            .await
             .body(topic_by_slug_query)
             .recv_json()
             .await
@@ -306,6 +415,18 @@ pub async fn projects_by_topic(req: Request<State>) -> tide::Result {
         topic_by_slug_resp_body.data.expect("无响应数据");
 
     let topic = topic_by_slug_resp_data["topicBySlug"].clone();
+    // an old slug before renaming or merging, moved to the current one
+    // permanently
+    let current_slug = topic["slug"].as_str().unwrap_or(topic_slug);
+    if current_slug != topic_slug {
+        let resp: Response = Redirect::permanent(format!(
+            "/{}/topic/{}/projects",
+            language, current_slug
+        ))
+        .into();
+
+        return Ok(resp.into());
+    }
     data.insert(
         "filter_desc",
         json!({
@@ -321,12 +442,14 @@ pub async fn projects_by_topic(req: Request<State>) -> tide::Result {
             from_page: page.from,
             first_oid: page.first,
             last_oid: page.last,
-            status: 1,
+            status: Some(projects_by_topic_data::ProjectStatus::PUBLISHED),
+            locale: language.clone(),
         });
     let projects_by_topic_query = json!(projects_by_topic_build_query);
 
     let projects_by_topic_resp_body: GqlResponse<serde_json::Value> =
-        surf::post(&gql_uri().await)
+        gql_post(&req, &cfg)
+            .await
             .body(projects_by_topic_query)
             .recv_json()
             .await?;
@@ -341,6 +464,7 @@ pub async fn projects_by_topic(req: Request<State>) -> tide::Result {
 }
 
 pub async fn projects_filter(req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
     let language = String::from(req.param("language")?);
 
     let mut projects_filter_tpl: Hbs =
@@ -361,13 +485,13 @@ pub async fn projects_filter(req: Request<State>) -> tide::Result {
     let mut data: BTreeMap<&str, serde_json::Value> = BTreeMap::new();
     data.insert("language", json!(language));
     data.insert("nav-projects-selected", json!("is-selected"));
-    insert_wish_random(&mut data).await;
+    insert_wish_random(&cfg, &mut data).await;
 
     let sign_status = sign_status(&req).await;
     if sign_status.sign_in {
-        insert_user_by_username(sign_status.username, &mut data).await;
+        insert_user_by_username(&cfg, &sign_status, &mut data).await;
     }
-    insert_categories(&mut data).await;
+    insert_categories(&cfg, &language, &mut data).await;
 
     let filter_str = req.param("filter_str")?;
     let page: Page = req.query()?;
@@ -382,13 +506,15 @@ pub async fn projects_filter(req: Request<State>) -> tide::Result {
                     from_page: page.from,
                     first_oid: page.first,
                     last_oid: page.last,
-                    status: 2,
+                    status: Some(projects_data::ProjectStatus::RECOMMENDED),
+                    locale: language.clone(),
                 });
             let projects_recommended_query =
                 json!(projects_recommended_build_query);
 
             let projects_recommended_resp_body: GqlResponse<serde_json::Value> =
-                surf::post(&gql_uri().await)
+                gql_post(&req, &cfg)
+                    .await
                     .body(projects_recommended_query)
                     .recv_json()
                     .await?;
@@ -404,6 +530,50 @@ pub async fn projects_filter(req: Request<State>) -> tide::Result {
                 "content": "projects-filter-recommended"
             });
         }
+        "trending" => {
+            data.insert("projects-trending-selected", json!("is-selected"));
+
+            let trending_info: TrendingInfo = req.query()?;
+            let (window, window_str) = match trending_info.window.as_str() {
+                "day" => (projects_trending_data::TrendingWindow::DAY, "day"),
+                "month" => {
+                    (projects_trending_data::TrendingWindow::MONTH, "month")
+                }
+                _ => (projects_trending_data::TrendingWindow::WEEK, "week"),
+            };
+            data.insert("trending_window", json!(window_str));
+
+            let projects_trending_build_query =
+                ProjectsTrendingData::build_query(
+                    projects_trending_data::Variables {
+                        window,
+                        locale: language.clone(),
+                    },
+                );
+            let projects_trending_query = json!(projects_trending_build_query);
+
+            let projects_trending_resp_body: GqlResponse<serde_json::Value> =
+                gql_post(&req, &cfg)
+                    .await
+                    .body(projects_trending_query)
+                    .recv_json()
+                    .await?;
+            let projects_trending_resp_data =
+                projects_trending_resp_body.data.expect("无响应数据");
+
+            // a ranking has no pages
+            let projects_trending =
+                projects_trending_resp_data["projectsTrending"].clone();
+            data.insert(
+                "pagination",
+                json!({ "currentItems": projects_trending }),
+            );
+
+            filter_desc = json!({
+                "condition": "trending",
+                "content": "projects-filter-trending"
+            });
+        }
         _ => {
             filter_desc = json!("n/a");
         }
@@ -415,6 +585,7 @@ pub async fn projects_filter(req: Request<State>) -> tide::Result {
 }
 
 pub async fn project_new(mut req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
     let language = String::from(req.param("language")?);
 
     let sign_status = sign_status(&req).await;
@@ -435,116 +606,64 @@ pub async fn project_new(mut req: Request<State>) -> tide::Result {
         let mut data: BTreeMap<&str, serde_json::Value> = BTreeMap::new();
         data.insert("language", json!(language));
         data.insert("nav-projects-selected", json!("is-selected"));
-        insert_wish_random(&mut data).await;
-        insert_user_by_username(sign_status.username, &mut data).await;
+        insert_wish_random(&cfg, &mut data).await;
+        insert_user_by_username(&cfg, &sign_status, &mut data).await;
 
         match req.method() {
             Method::Post => {
                 let project_info: ProjectInfo = req.body_form().await?;
 
-                let project_new_build_query =
-                    ProjectNewData::build_query(project_new_data::Variables {
-                        user_id: project_info.user_id.clone(),
-                        category_id: project_info.category_id,
-                        subject: project_info.subject.clone(),
-                        content: project_info.content,
-                        contact_user: project_info.contact_user,
-                        contact_phone: project_info.contact_phone,
-                        contact_email: project_info.contact_email,
-                        contact_im: project_info.contact_im,
-                        language: project_info.language,
-                    });
-                let project_new_query = json!(project_new_build_query);
-
-                let project_new_resp_body: GqlResponse<serde_json::Value> =
-                    surf::post(&gql_uri().await)
-                        .body(project_new_query)
+                let project_publish_build_query =
+                    ProjectPublishData::build_query(
+                        project_publish_data::Variables {
+                            user_id: project_info.user_id,
+                            category_id: project_info.category_id,
+                            subject: project_info.subject.clone(),
+                            content: project_info.content,
+                            contact_user: project_info.contact_user,
+                            contact_phone: project_info.contact_phone,
+                            contact_email: project_info.contact_email,
+                            contact_im: project_info.contact_im,
+                            language: project_info.language,
+                            topic_names: project_info.topic_names,
+                            cover_image_id: project_info.cover_image_id,
+                            source_file_id: project_info.source_file_id,
+                            idempotency_key: Some(project_info.idempotency_key)
+                                .filter(|key| !key.is_empty()),
+                            token: sign_status.token.clone(),
+                        },
+                    );
+                let project_publish_query = json!(project_publish_build_query);
+
+                let project_publish_resp_body: GqlResponse<serde_json::Value> =
+                    gql_post(&req, &cfg)
+                        .await
+                        .body(project_publish_query)
                         .recv_json()
                         .await?;
-                let project_new_resp_data = project_new_resp_body.data;
+                let project_publish_resp_data = project_publish_resp_body.data;
 
-                if let Some(project_new_val) = project_new_resp_data {
+                if let Some(project_publish_val) = project_publish_resp_data {
                     let project_new_result =
-                        project_new_val["projectNew"].clone();
-                    let project_id = project_new_result["id"].as_str().unwrap();
-
-                    // create topics
-                    let topics_build_query = TopicsNewData::build_query(
-                        topics_new_data::Variables {
-                            topic_names: project_info.topic_names,
-                        },
-                    );
-                    let topics_query = json!(topics_build_query);
-
-                    let topics_resp_body: GqlResponse<serde_json::Value> =
-                        surf::post(&gql_uri().await)
-                            .body(topics_query)
-                            .recv_json()
-                            .await?;
-                    let topics_resp_data = topics_resp_body.data;
-
-                    // create TopicProject
-                    if let Some(topics_info) = topics_resp_data {
-                        let topic_ids =
-                            topics_info["topicsNew"].as_array().unwrap();
-                        for topic_id in topic_ids {
-                            let topic_id = topic_id["id"].as_str().unwrap();
-                            let topic_project_new_build_query =
-                                TopicProjectNewData::build_query(
-                                    topic_project_new_data::Variables {
-                                        user_id: project_info.user_id.clone(),
-                                        project_id: project_id.to_string(),
-                                        topic_id: topic_id.to_string(),
-                                    },
-                                );
-                            let topic_project_new_query =
-                                json!(topic_project_new_build_query);
-                            let _topic_project_new_resp_body: GqlResponse<
-                                serde_json::Value,
-                            > = surf::post(&gql_uri().await)
-                                .body(topic_project_new_query)
-                                .recv_json()
-                                .await?;
-                        }
-                    }
-
-                    // create ProjectFile
-                    let file_ids = [
-                        project_info.cover_image_id,
-                        project_info.source_file_id,
-                    ];
-                    for file_id in file_ids {
-                        let project_file_new_build_query =
-                            ProjectFileNewData::build_query(
-                                project_file_new_data::Variables {
-                                    user_id: project_info.user_id.clone(),
-                                    project_id: project_id.to_string(),
-                                    file_id: file_id.to_string(),
-                                },
-                            );
-                        let project_file_new_query =
-                            json!(project_file_new_build_query);
-                        let _project_file_new_resp_body: GqlResponse<
-                            serde_json::Value,
-                        > = surf::post(&gql_uri().await)
-                            .body(project_file_new_query)
-                            .recv_json()
-                            .await?;
-                    }
+                        project_publish_val["projectPublish"].clone();
 
                     data.insert("project_new_result", project_new_result);
                 } else {
-                    data.insert(
-                        "project_new_failed",
-                        json!({
-                            "subject": project_info.subject,
-                            "create_at": project_new_resp_body.errors.unwrap()[0].message
-                        })
-                    );
+                    let mut project_new_failed =
+                        gql_error(project_publish_resp_body.errors);
+                    project_new_failed["subject"] = json!(project_info.subject);
+                    data.insert("project_new_failed", project_new_failed);
                 }
             }
             _ => {
-                insert_categories(&mut data).await;
+                insert_categories(&cfg, &language, &mut data).await;
+
+                let now_micros =
+                    SystemTime::now().duration_since(UNIX_EPOCH)?.as_micros();
+                data.insert(
+                    "idempotency_key",
+                    json!(format!("{}-{}", sign_status.username, now_micros)),
+                );
             }
         }
 
@@ -558,6 +677,7 @@ pub async fn project_new(mut req: Request<State>) -> tide::Result {
 }
 
 pub async fn project_index(req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
     let language = String::from(req.param("language")?);
 
     let mut project_index_tpl: Hbs =
@@ -569,6 +689,8 @@ pub async fn project_index(req: Request<State>) -> tide::Result {
         .await
         .reg_container()
         .await
+        .reg_pagination()
+        .await
         .reg_footer()
         .await;
     project_index_tpl
@@ -582,48 +704,697 @@ pub async fn project_index(req: Request<State>) -> tide::Result {
     let mut data: BTreeMap<&str, serde_json::Value> = BTreeMap::new();
     data.insert("language", json!(language));
     data.insert("nav-projects-selected", json!("is-selected"));
-    insert_wish_random(&mut data).await;
+    data.insert("gql_ws", json!(gql_ws_uri(&cfg).await));
+    insert_wish_random(&cfg, &mut data).await;
 
     let sign_status = sign_status(&req).await;
     if sign_status.sign_in {
         data.insert("sign-in", json!(sign_status.sign_in));
-        insert_user_by_username(sign_status.username, &mut data).await;
+        insert_user_by_username(&cfg, &sign_status, &mut data).await;
     }
 
-    let project_id = req.param("project_id")?;
-
-    let project_update_hits_build_query =
-        ProjectUpdateOneFieldByIdData::build_query(
-            project_update_one_field_by_id_data::Variables {
-                project_id: project_id.to_string(),
-                field_name: String::from("hits"),
-                field_val: String::from("3"),
-            },
-        );
-    let project_update_hits_query = json!(project_update_hits_build_query);
-    let _project_update_hits_resp_body: GqlResponse<serde_json::Value> =
-        surf::post(&gql_uri().await)
-            .body(project_update_hits_query)
-            .recv_json()
-            .await?;
+    // canonical url is /project/<id>-<slug>, a bare id or an old slug is
+    // moved to it permanently
+    let project_param = req.param("project_id")?;
+    let (project_id, project_slug) = match project_id_slug(project_param) {
+        Some((project_id, project_slug)) => {
+            (project_id.to_string(), project_slug.to_string())
+        }
+        None => {
+            let project_by_slug_build_query = ProjectBySlugData::build_query(
+                project_by_slug_data::Variables {
+                    slug: project_param.to_string(),
+                },
+            );
+            let project_by_slug_query = json!(project_by_slug_build_query);
+
+            let project_by_slug_resp_body: GqlResponse<serde_json::Value> =
+                gql_post(&req, &cfg)
+                    .await
+                    .body(project_by_slug_query)
+                    .recv_json()
+                    .await?;
+            // an unknown slug has no data, but the errors
+            let project_id = match project_by_slug_resp_body.data {
+                Some(project_by_slug_resp_data) => {
+                    let project = &project_by_slug_resp_data["projectBySlug"];
+                    project["id"].as_str().map(String::from)
+                }
+                None => None,
+            };
+            match project_id {
+                Some(project_id) => (project_id, String::from("")),
+                None => return Ok(Response::new(StatusCode::NotFound)),
+            }
+        }
+    };
 
     let project_build_query =
         ProjectData::build_query(project_data::Variables {
-            project_id: project_id.to_string(),
+            project_id: project_id.clone(),
+            token: sign_status.token.clone(),
+            locale: language.clone(),
         });
     let project_query = json!(project_build_query);
 
     let project_resp_body: GqlResponse<serde_json::Value> =
-        surf::post(&gql_uri().await).body(project_query).recv_json().await?;
-    let project_resp_data = project_resp_body.data.expect("无响应数据");
-
-    let project = project_resp_data["projectById"].clone();
+        gql_post(&req, &cfg).await.body(project_query).recv_json().await?;
+    // an unknown id has no data, but the errors
+    let project = match project_resp_body.data {
+        Some(project_resp_data) => project_resp_data["projectById"].clone(),
+        None => return Ok(Response::new(StatusCode::NotFound)),
+    };
+    // a trashed project is only shown to its owner
+    if project["status"].as_str() == Some("DELETED")
+        && project["user"]["username"].as_str()
+            != Some(sign_status.username.as_str())
+    {
+        return Ok(Response::new(StatusCode::NotFound));
+    }
+    let current_slug = project["slug"].as_str().unwrap_or("");
+    if current_slug != project_slug {
+        let query = req
+            .url()
+            .query()
+            .map_or(String::from(""), |query| format!("?{}", query));
+        let resp: Response = Redirect::permanent(format!(
+            "/{}/project/{}-{}{}",
+            language, project_id, current_slug, query
+        ))
+        .into();
+
+        return Ok(resp);
+    }
     data.insert("project", project);
 
+    let fingerprint = visitor_fingerprint(&req);
+    let signature = visitor_signature(&fingerprint, &cfg);
+    let project_view_record_build_query = ProjectViewRecordData::build_query(
+        project_view_record_data::Variables {
+            project_id: project_id.clone(),
+            fingerprint,
+            signature,
+            token: sign_status.token,
+        },
+    );
+    let project_view_record_query = json!(project_view_record_build_query);
+    let _project_view_record_resp_body: GqlResponse<serde_json::Value> =
+        gql_post(&req, &cfg)
+            .await
+            .body(project_view_record_query)
+            .recv_json()
+            .await?;
+
+    let remix_tree_build_query =
+        ProjectRemixTreeData::build_query(project_remix_tree_data::Variables {
+            project_id: project_id.clone(),
+        });
+    let remix_tree_query = json!(remix_tree_build_query);
+
+    let remix_tree_resp_body: GqlResponse<serde_json::Value> =
+        gql_post(&req, &cfg).await.body(remix_tree_query).recv_json().await?;
+    if let Some(remix_tree_resp_data) = remix_tree_resp_body.data {
+        let remix_tree = remix_tree_resp_data["projectRemixTree"].clone();
+        // a lone original work has no tree worth showing
+        if remix_tree.as_array().map_or(false, |nodes| nodes.len() > 1) {
+            data.insert("remix_tree", remix_tree);
+        }
+    }
+
+    let page: Page = req.query()?;
+    let comments_build_query = CommentsByProjectData::build_query(
+        comments_by_project_data::Variables {
+            project_id,
+            from_page: page.from,
+            first_oid: page.first,
+            last_oid: page.last,
+        },
+    );
+    let comments_query = json!(comments_build_query);
+
+    let comments_resp_body: GqlResponse<serde_json::Value> =
+        gql_post(&req, &cfg).await.body(comments_query).recv_json().await?;
+    if let Some(comments_resp_data) = comments_resp_body.data {
+        let comments = comments_resp_data["commentsByProjectId"].clone();
+        data.insert("pagination", comments);
+    }
+
     project_index_tpl.render(&data).await
 }
 
+// split the project param as <id> or <id>-<slug>, None if it is not led by
+// an object id
+fn project_id_slug(project_param: &str) -> Option<(&str, &str)> {
+    let (project_id, project_slug) = match project_param.split_once('-') {
+        Some((project_id, project_slug)) => (project_id, project_slug),
+        None => (project_param, ""),
+    };
+
+    if project_id.len() == 24
+        && project_id.chars().all(|c| c.is_ascii_hexdigit())
+    {
+        Some((project_id, project_slug))
+    } else {
+        None
+    }
+}
+
+pub async fn project_remix(req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
+    let language = String::from(req.param("language")?);
+
+    let sign_status = sign_status(&req).await;
+    if sign_status.sign_in {
+        let project_id = req.param("project_id")?;
+
+        let project_remix_build_query =
+            ProjectRemixData::build_query(project_remix_data::Variables {
+                project_id: project_id.to_string(),
+                token: sign_status.token,
+            });
+        let project_remix_query = json!(project_remix_build_query);
+
+        let project_remix_resp_body: GqlResponse<serde_json::Value> =
+            gql_post(&req, &cfg)
+                .await
+                .body(project_remix_query)
+                .recv_json()
+                .await?;
+
+        // go to the new remix, or back to the original one if it failed
+        let remix_id = project_remix_resp_body
+            .data
+            .and_then(|remix_data| {
+                remix_data["projectRemix"]["id"].as_str().map(String::from)
+            })
+            .unwrap_or_else(|| project_id.to_string());
+
+        let resp: Response =
+            Redirect::new(format!("/{}/project/{}", language, remix_id)).into();
+
+        Ok(resp.into())
+    } else {
+        let resp: Response =
+            Redirect::new(format!("/{}/sign-in", language)).into();
+
+        Ok(resp.into())
+    }
+}
+
+pub async fn project_comments_enable(req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
+    let language = String::from(req.param("language")?);
+
+    let sign_status = sign_status(&req).await;
+    if sign_status.sign_in {
+        let project_id = req.param("project_id")?;
+        let enabled = "enable".eq(req.param("switch")?);
+
+        let project_comments_enable_build_query =
+            ProjectCommentsEnableData::build_query(
+                project_comments_enable_data::Variables {
+                    project_id: project_id.to_string(),
+                    enabled,
+                    token: sign_status.token,
+                },
+            );
+        let project_comments_enable_query =
+            json!(project_comments_enable_build_query);
+
+        let _project_comments_enable_resp_body: GqlResponse<serde_json::Value> =
+            gql_post(&req, &cfg)
+                .await
+                .body(project_comments_enable_query)
+                .recv_json()
+                .await?;
+
+        let resp: Response =
+            Redirect::new(format!("/{}/project/{}", language, project_id))
+                .into();
+
+        Ok(resp.into())
+    } else {
+        let resp: Response =
+            Redirect::new(format!("/{}/sign-in", language)).into();
+
+        Ok(resp.into())
+    }
+}
+
+pub async fn project_delete(req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
+    let language = String::from(req.param("language")?);
+
+    let sign_status = sign_status(&req).await;
+    if sign_status.sign_in {
+        let project_id = req.param("project_id")?;
+
+        let project_delete_build_query =
+            ProjectDeleteData::build_query(project_delete_data::Variables {
+                project_id: project_id.to_string(),
+                token: sign_status.token,
+            });
+        let project_delete_query = json!(project_delete_build_query);
+
+        let project_delete_resp_body: GqlResponse<serde_json::Value> =
+            gql_post(&req, &cfg)
+                .await
+                .body(project_delete_query)
+                .recv_json()
+                .await?;
+
+        let resp: Response = if project_delete_resp_body.data.is_some() {
+            Redirect::new(format!("/{}/projects/trash", language)).into()
+        } else {
+            Redirect::new(format!("/{}/project/{}", language, project_id))
+                .into()
+        };
+
+        Ok(resp.into())
+    } else {
+        let resp: Response =
+            Redirect::new(format!("/{}/sign-in", language)).into();
+
+        Ok(resp.into())
+    }
+}
+
+pub async fn project_restore(req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
+    let language = String::from(req.param("language")?);
+
+    let sign_status = sign_status(&req).await;
+    if sign_status.sign_in {
+        let project_id = req.param("project_id")?;
+
+        let project_restore_build_query =
+            ProjectRestoreData::build_query(project_restore_data::Variables {
+                project_id: project_id.to_string(),
+                token: sign_status.token,
+            });
+        let project_restore_query = json!(project_restore_build_query);
+
+        let project_restore_resp_body: GqlResponse<serde_json::Value> =
+            gql_post(&req, &cfg)
+                .await
+                .body(project_restore_query)
+                .recv_json()
+                .await?;
+
+        let resp: Response = if project_restore_resp_body.data.is_some() {
+            Redirect::new(format!("/{}/project/{}", language, project_id))
+                .into()
+        } else {
+            Redirect::new(format!("/{}/projects/trash", language)).into()
+        };
+
+        Ok(resp.into())
+    } else {
+        let resp: Response =
+            Redirect::new(format!("/{}/sign-in", language)).into();
+
+        Ok(resp.into())
+    }
+}
+
+pub async fn project_star(req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
+    let language = String::from(req.param("language")?);
+
+    let sign_status = sign_status(&req).await;
+    if sign_status.sign_in {
+        let project_id = req.param("project_id")?;
+
+        let project_star_build_query =
+            ProjectStarData::build_query(project_star_data::Variables {
+                project_id: project_id.to_string(),
+                token: sign_status.token,
+            });
+        let project_star_query = json!(project_star_build_query);
+
+        let _project_star_resp_body: GqlResponse<serde_json::Value> =
+            gql_post(&req, &cfg)
+                .await
+                .body(project_star_query)
+                .recv_json()
+                .await?;
+
+        let resp: Response =
+            Redirect::new(format!("/{}/project/{}", language, project_id))
+                .into();
+
+        Ok(resp.into())
+    } else {
+        let resp: Response =
+            Redirect::new(format!("/{}/sign-in", language)).into();
+
+        Ok(resp.into())
+    }
+}
+
+pub async fn project_unstar(req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
+    let language = String::from(req.param("language")?);
+
+    let sign_status = sign_status(&req).await;
+    if sign_status.sign_in {
+        let project_id = req.param("project_id")?;
+
+        let project_unstar_build_query =
+            ProjectUnstarData::build_query(project_unstar_data::Variables {
+                project_id: project_id.to_string(),
+                token: sign_status.token,
+            });
+        let project_unstar_query = json!(project_unstar_build_query);
+
+        let _project_unstar_resp_body: GqlResponse<serde_json::Value> =
+            gql_post(&req, &cfg)
+                .await
+                .body(project_unstar_query)
+                .recv_json()
+                .await?;
+
+        let resp: Response =
+            Redirect::new(format!("/{}/project/{}", language, project_id))
+                .into();
+
+        Ok(resp.into())
+    } else {
+        let resp: Response =
+            Redirect::new(format!("/{}/sign-in", language)).into();
+
+        Ok(resp.into())
+    }
+}
+
+pub async fn projects_trash(req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
+    let language = String::from(req.param("language")?);
+
+    let sign_status = sign_status(&req).await;
+    if sign_status.sign_in {
+        let mut projects_trash_tpl: Hbs =
+            Hbs::new("projects/projects-trash").await;
+        projects_trash_tpl
+            .reg_head()
+            .await
+            .reg_header()
+            .await
+            .reg_container()
+            .await
+            .reg_pagination()
+            .await
+            .reg_footer()
+            .await;
+        projects_trash_tpl.reg_script_values().await.reg_script_lang().await;
+
+        let mut data: BTreeMap<&str, serde_json::Value> = BTreeMap::new();
+        data.insert("language", json!(language));
+        data.insert("nav-projects-selected", json!("is-selected"));
+        insert_wish_random(&cfg, &mut data).await;
+        insert_user_by_username(&cfg, &sign_status, &mut data).await;
+
+        let page: Page = req.query()?;
+        let projects_trash_build_query =
+            ProjectsTrashData::build_query(projects_trash_data::Variables {
+                token: sign_status.token,
+                from_page: page.from,
+                first_oid: page.first,
+                last_oid: page.last,
+                locale: language.clone(),
+            });
+        let projects_trash_query = json!(projects_trash_build_query);
+
+        let projects_trash_resp_body: GqlResponse<serde_json::Value> =
+            gql_post(&req, &cfg)
+                .await
+                .body(projects_trash_query)
+                .recv_json()
+                .await?;
+        let projects_trash_resp_data =
+            projects_trash_resp_body.data.expect("无响应数据");
+
+        let projects_trash = projects_trash_resp_data["projectsTrash"].clone();
+        data.insert("pagination", projects_trash);
+
+        projects_trash_tpl.render(&data).await
+    } else {
+        let resp: Response =
+            Redirect::new(format!("/{}/sign-in", language)).into();
+
+        Ok(resp.into())
+    }
+}
+
+// record the download, then move to the source file
+pub async fn project_download(req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
+    let language = String::from(req.param("language")?);
+
+    let sign_status = sign_status(&req).await;
+    if sign_status.sign_in {
+        let project_id = req.param("project_id")?;
+
+        let project_download_build_query =
+            ProjectDownloadRecordData::build_query(
+                project_download_record_data::Variables {
+                    project_id: project_id.to_string(),
+                    token: sign_status.token,
+                },
+            );
+        let project_download_query = json!(project_download_build_query);
+
+        let project_download_resp_body: GqlResponse<serde_json::Value> =
+            gql_post(&req, &cfg)
+                .await
+                .body(project_download_query)
+                .recv_json()
+                .await?;
+
+        let resp: Response = match project_download_resp_body.data {
+            Some(project_download_resp_data) => {
+                let source_file =
+                    &project_download_resp_data["projectDownloadRecord"];
+                Redirect::new(format!(
+                    "/files/projects/{}",
+                    source_file["location"].as_str().unwrap_or("")
+                ))
+                .into()
+            }
+            None => {
+                Redirect::new(format!("/{}/project/{}", language, project_id))
+                    .into()
+            }
+        };
+
+        Ok(resp.into())
+    } else {
+        let resp: Response =
+            Redirect::new(format!("/{}/sign-in", language)).into();
+
+        Ok(resp.into())
+    }
+}
+
+// statistics of the project, only for its owner
+pub async fn project_stats(req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
+    let language = String::from(req.param("language")?);
+
+    let sign_status = sign_status(&req).await;
+    if sign_status.sign_in {
+        let project_id = req.param("project_id")?;
+        let stats_info: StatsInfo = req.query()?;
+        let range = stats_range(&stats_info.range);
+
+        let project_stats_build_query =
+            ProjectStatsData::build_query(project_stats_data::Variables {
+                project_id: project_id.to_string(),
+                range: match range {
+                    "month" => project_stats_data::StatsRange::MONTH,
+                    "quarter" => project_stats_data::StatsRange::QUARTER,
+                    _ => project_stats_data::StatsRange::WEEK,
+                },
+                token: sign_status.token.clone(),
+            });
+        let project_stats_query = json!(project_stats_build_query);
+
+        let project_stats_resp_body: GqlResponse<serde_json::Value> =
+            gql_post(&req, &cfg)
+                .await
+                .body(project_stats_query)
+                .recv_json()
+                .await?;
+
+        // not the owner, back to the project
+        let project = match project_stats_resp_body.data {
+            Some(project_stats_resp_data) => {
+                project_stats_resp_data["projectById"].clone()
+            }
+            None => {
+                let resp: Response = Redirect::new(format!(
+                    "/{}/project/{}",
+                    language, project_id
+                ))
+                .into();
+
+                return Ok(resp);
+            }
+        };
+
+        let target = json!({
+            "name": project["subject"],
+            "href": format!(
+                "/{}/project/{}-{}",
+                language,
+                project_id,
+                project["slug"].as_str().unwrap_or("")
+            ),
+            "stats-href": format!("/{}/project/{}/stats", language, project_id),
+        });
+        stats_render(
+            &cfg,
+            &language,
+            &sign_status,
+            range,
+            target,
+            &project["stats"],
+        )
+        .await
+    } else {
+        let resp: Response =
+            Redirect::new(format!("/{}/sign-in", language)).into();
+
+        Ok(resp.into())
+    }
+}
+
+// statistics of all projects of the user, only for the user self
+pub async fn projects_stats_by_user(req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
+    let language = String::from(req.param("language")?);
+    let author_username = req.param("author_username")?;
+
+    let sign_status = sign_status(&req).await;
+    if sign_status.sign_in && sign_status.username == author_username {
+        let stats_info: StatsInfo = req.query()?;
+        let range = stats_range(&stats_info.range);
+
+        let user_stats_build_query =
+            UserStatsData::build_query(user_stats_data::Variables {
+                username: author_username.to_string(),
+                range: match range {
+                    "month" => user_stats_data::StatsRange::MONTH,
+                    "quarter" => user_stats_data::StatsRange::QUARTER,
+                    _ => user_stats_data::StatsRange::WEEK,
+                },
+                token: sign_status.token.clone(),
+            });
+        let user_stats_query = json!(user_stats_build_query);
+
+        let user_stats_resp_body: GqlResponse<serde_json::Value> =
+            gql_post(&req, &cfg)
+                .await
+                .body(user_stats_query)
+                .recv_json()
+                .await?;
+        let user_stats_resp_data =
+            user_stats_resp_body.data.expect("无响应数据");
+
+        let user = user_stats_resp_data["userByUsername"].clone();
+        let target = json!({
+            "name": user["nickname"],
+            "href": format!("/{}/user/{}/projects", language, author_username),
+            "stats-href": format!("/{}/user/{}/stats", language, author_username),
+        });
+        stats_render(
+            &cfg,
+            &language,
+            &sign_status,
+            range,
+            target,
+            &user["stats"],
+        )
+        .await
+    } else {
+        let resp: Response =
+            Redirect::new(format!("/{}/user/{}", language, author_username))
+                .into();
+
+        Ok(resp.into())
+    }
+}
+
+// range of the statistics in the query, a week by default
+fn stats_range(range: &str) -> &'static str {
+    match range {
+        "month" => "month",
+        "quarter" => "quarter",
+        _ => "week",
+    }
+}
+
+// render the statistics with a bar chart of each count
+async fn stats_render(
+    cfg: &Config,
+    language: &str,
+    sign_status: &SignStatus,
+    range: &str,
+    target: serde_json::Value,
+    stats: &serde_json::Value,
+) -> tide::Result {
+    let mut projects_stats_tpl: Hbs = Hbs::new("projects/projects-stats").await;
+    projects_stats_tpl
+        .reg_head()
+        .await
+        .reg_header()
+        .await
+        .reg_container()
+        .await
+        .reg_footer()
+        .await;
+    projects_stats_tpl.reg_script_values().await.reg_script_lang().await;
+
+    let mut data: BTreeMap<&str, serde_json::Value> = BTreeMap::new();
+    data.insert("language", json!(language));
+    data.insert("nav-projects-selected", json!("is-selected"));
+    insert_wish_random(cfg, &mut data).await;
+    insert_user_by_username(cfg, sign_status, &mut data).await;
+
+    data.insert("target", target);
+    data.insert("range", json!(range));
+
+    let daily = stats["daily"].as_array().cloned().unwrap_or_default();
+    let mut charts: Vec<serde_json::Value> = vec![];
+    for (count_name, color) in [
+        ("views", "#0a95ff"),
+        ("downloads", "#5eba7d"),
+        ("stars", "#f48225"),
+        ("comments", "#9a4edc"),
+    ] {
+        let points: Vec<(String, u64)> = daily
+            .iter()
+            .map(|day| {
+                (
+                    day["day"].as_str().unwrap_or("").to_string(),
+                    day[count_name].as_u64().unwrap_or(0),
+                )
+            })
+            .collect();
+
+        charts.push(json!({
+            "name": format!("stats-{}", count_name),
+            "total": stats[count_name],
+            "svg": bars_svg(&points, color),
+        }));
+    }
+    data.insert("charts", json!(charts));
+
+    projects_stats_tpl.render(&data).await
+}
+
 pub async fn project_random(req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
     let language = String::from(req.param("language")?);
 
     let project_random_build_query =
@@ -631,7 +1402,8 @@ pub async fn project_random(req: Request<State>) -> tide::Result {
     let project_random_query = json!(project_random_build_query);
 
     let project_random_resp_body: GqlResponse<serde_json::Value> =
-        surf::post(&gql_uri().await)
+        gql_post(&req, &cfg)
+            .await
             .body(project_random_query)
             .recv_json()
             .await?;
@@ -648,13 +1420,16 @@ pub async fn project_random(req: Request<State>) -> tide::Result {
 }
 
 pub async fn file_new(req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
     let language = String::from(req.param("language")?);
 
     let file_name_percent = req.param("file_name")?;
     let file_name_percent_de = percent_decode(file_name_percent.as_bytes());
     let file_name = String::from(file_name_percent_de.decode_utf8()?);
 
-    let file_kind = req.param("file_kind")?.parse::<i64>()?;
+    // the kind in the path is the lowercase name of the enum value
+    let file_kind: file_new_data::FileKind =
+        serde_json::from_value(json!(req.param("file_kind")?.to_uppercase()))?;
 
     let file_ext_index = file_name.rfind(".");
     let now_micros = SystemTime::now().duration_since(UNIX_EPOCH)?.as_micros();
@@ -679,7 +1454,7 @@ pub async fn file_new(req: Request<State>) -> tide::Result {
         let file_new_query = json!(file_new_build_query);
 
         let file_new_resp_body: GqlResponse<serde_json::Value> =
-            surf::post(&gql_uri().await)
+            surf::post(&gql_uri(&cfg).await)
                 .body(file_new_query)
                 .recv_json()
                 .await?;
@@ -0,0 +1,599 @@
+use std::collections::BTreeMap;
+use tide::{Request, Response, Redirect, http::Method};
+use graphql_client::{GraphQLQuery, Response as GqlResponse};
+use serde_json::json;
+
+use crate::State;
+use crate::util::{
+    config::Config,
+    common::{gql_post, gql_post_as, sign_status, gql_error},
+    tpl::{Hbs, insert_user_by_username, insert_wish_random},
+};
+
+use crate::models::{
+    Page,
+    users::SignStatus,
+    studios::{
+        StudioInfo, StudioCuratorInfo, StudioProjectInfo,
+        StudioProjectPosition, StudiosData, studios_data,
+        StudioInvitationsData, studio_invitations_data, StudioBySlugData,
+        studio_by_slug_data, StudioNewData, studio_new_data, StudioUpdateData,
+        studio_update_data, StudioCuratorInviteData,
+        studio_curator_invite_data, StudioCuratorAcceptData,
+        studio_curator_accept_data, StudioCuratorRemoveData,
+        studio_curator_remove_data, StudioProjectAddData,
+        studio_project_add_data, StudioProjectRemoveData,
+        studio_project_remove_data, StudioProjectMoveData,
+        studio_project_move_data,
+    },
+};
+
+pub async fn studios_index(req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
+    let language = String::from(req.param("language")?);
+
+    let mut studios_index_tpl: Hbs = Hbs::new("studios/studios-index").await;
+    studios_index_tpl
+        .reg_head()
+        .await
+        .reg_header()
+        .await
+        .reg_container()
+        .await
+        .reg_pagination()
+        .await
+        .reg_footer()
+        .await;
+    studios_index_tpl.reg_script_values().await.reg_script_lang().await;
+
+    let mut data: BTreeMap<&str, serde_json::Value> = BTreeMap::new();
+    data.insert("language", json!(language));
+    data.insert("nav-studios-selected", json!("is-selected"));
+    insert_wish_random(&cfg, &mut data).await;
+
+    let sign_status = sign_status(&req).await;
+    if sign_status.sign_in {
+        data.insert("sign-in", json!(sign_status.sign_in));
+        insert_user_by_username(&cfg, &sign_status, &mut data).await;
+
+        let studio_invitations_build_query = StudioInvitationsData::build_query(
+            studio_invitations_data::Variables {
+                token: sign_status.token.clone(),
+            },
+        );
+        let studio_invitations_query = json!(studio_invitations_build_query);
+
+        let studio_invitations_resp_body: GqlResponse<serde_json::Value> =
+            gql_post(&req, &cfg)
+                .await
+                .body(studio_invitations_query)
+                .recv_json()
+                .await?;
+        if let Some(studio_invitations_resp_data) =
+            studio_invitations_resp_body.data
+        {
+            let studio_invitations =
+                studio_invitations_resp_data["studioInvitations"].clone();
+            data.insert("studio_invitations", studio_invitations);
+        }
+    }
+
+    let page: Page = req.query()?;
+    let studios_build_query =
+        StudiosData::build_query(studios_data::Variables {
+            from_page: page.from,
+            first_oid: page.first,
+            last_oid: page.last,
+        });
+    let studios_query = json!(studios_build_query);
+
+    let studios_resp_body: GqlResponse<serde_json::Value> =
+        gql_post(&req, &cfg).await.body(studios_query).recv_json().await?;
+    let studios_resp_data = studios_resp_body.data.expect("无响应数据");
+
+    let studios = studios_resp_data["studios"].clone();
+    data.insert("pagination", studios);
+
+    studios_index_tpl.render(&data).await
+}
+
+pub async fn studio_new(mut req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
+    let language = String::from(req.param("language")?);
+
+    let sign_status = sign_status(&req).await;
+    if !sign_status.sign_in {
+        let resp: Response =
+            Redirect::new(format!("/{}/sign-in", language)).into();
+
+        return Ok(resp.into());
+    }
+
+    if let Method::Post = req.method() {
+        let studio_info: StudioInfo = req.body_form().await?;
+
+        let studio_new_build_query =
+            StudioNewData::build_query(studio_new_data::Variables {
+                name: studio_info.name.clone(),
+                description: studio_info.description.clone(),
+                cover_image_id: cover_image_id(&studio_info),
+                token: sign_status.token.clone(),
+            });
+        let studio_new_query = json!(studio_new_build_query);
+
+        let studio_new_resp_body: GqlResponse<serde_json::Value> =
+            gql_post(&req, &cfg)
+                .await
+                .body(studio_new_query)
+                .recv_json()
+                .await?;
+
+        match studio_new_resp_body.data {
+            Some(studio_new_data) => {
+                let slug = studio_new_data["studioNew"]["slug"]
+                    .as_str()
+                    .unwrap_or_default();
+                let resp: Response =
+                    Redirect::new(format!("/{}/studio/{}", language, slug))
+                        .into();
+
+                Ok(resp.into())
+            }
+            None => {
+                let studio_failed = json!({
+                    "name": studio_info.name,
+                    "description": studio_info.description,
+                    "err": gql_error(studio_new_resp_body.errors)
+                });
+
+                studio_form_render(&cfg, &language, &sign_status, studio_failed)
+                    .await
+            }
+        }
+    } else {
+        studio_form_render(&cfg, &language, &sign_status, json!({})).await
+    }
+}
+
+pub async fn studio_edit(mut req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
+    let language = String::from(req.param("language")?);
+    let studio_slug = String::from(req.param("studio_slug")?);
+
+    let sign_status = sign_status(&req).await;
+    if !sign_status.sign_in {
+        let resp: Response =
+            Redirect::new(format!("/{}/sign-in", language)).into();
+
+        return Ok(resp.into());
+    }
+
+    let studio = studio_by_slug(
+        &cfg,
+        &language,
+        &studio_slug,
+        &sign_status.token,
+        Page::default(),
+    )
+    .await?;
+
+    if let Method::Post = req.method() {
+        let studio_info: StudioInfo = req.body_form().await?;
+
+        let studio_update_build_query =
+            StudioUpdateData::build_query(studio_update_data::Variables {
+                studio_id: String::from(studio["id"].as_str().unwrap()),
+                name: studio_info.name.clone(),
+                description: studio_info.description.clone(),
+                cover_image_id: cover_image_id(&studio_info),
+                token: sign_status.token.clone(),
+            });
+        let studio_update_query = json!(studio_update_build_query);
+
+        let studio_update_resp_body: GqlResponse<serde_json::Value> =
+            gql_post(&req, &cfg)
+                .await
+                .body(studio_update_query)
+                .recv_json()
+                .await?;
+
+        if studio_update_resp_body.data.is_some() {
+            let resp: Response =
+                Redirect::new(format!("/{}/studio/{}", language, studio_slug))
+                    .into();
+
+            Ok(resp.into())
+        } else {
+            let mut studio_failed = studio;
+            studio_failed["name"] = json!(studio_info.name);
+            studio_failed["description"] = json!(studio_info.description);
+            studio_failed["err"] = gql_error(studio_update_resp_body.errors);
+
+            studio_form_render(&cfg, &language, &sign_status, studio_failed)
+                .await
+        }
+    } else {
+        studio_form_render(&cfg, &language, &sign_status, studio).await
+    }
+}
+
+pub async fn studio_index(req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
+    let language = String::from(req.param("language")?);
+
+    let mut studio_index_tpl: Hbs =
+        Hbs::new("studios/studios-studio-detail").await;
+    studio_index_tpl
+        .reg_head()
+        .await
+        .reg_header()
+        .await
+        .reg_container()
+        .await
+        .reg_pagination()
+        .await
+        .reg_footer()
+        .await;
+    studio_index_tpl.reg_script_values().await.reg_script_lang().await;
+
+    let mut data: BTreeMap<&str, serde_json::Value> = BTreeMap::new();
+    data.insert("language", json!(language));
+    data.insert("nav-studios-selected", json!("is-selected"));
+    insert_wish_random(&cfg, &mut data).await;
+
+    let sign_status = sign_status(&req).await;
+    if sign_status.sign_in {
+        data.insert("sign-in", json!(sign_status.sign_in));
+        insert_user_by_username(&cfg, &sign_status, &mut data).await;
+    }
+
+    let studio_slug = req.param("studio_slug")?;
+    let page: Page = req.query()?;
+    let studio =
+        studio_by_slug(&cfg, &language, studio_slug, &sign_status.token, page)
+            .await?;
+
+    data.insert("pagination", studio["projects"].clone());
+    data.insert("studio", studio);
+
+    studio_index_tpl.render(&data).await
+}
+
+pub async fn studio_curator_invite(mut req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
+    let language = String::from(req.param("language")?);
+    let studio_id = String::from(req.param("studio_id")?);
+
+    let sign_status = sign_status(&req).await;
+    if sign_status.sign_in {
+        let curator_info: StudioCuratorInfo = req.body_form().await?;
+
+        let studio_curator_invite_build_query =
+            StudioCuratorInviteData::build_query(
+                studio_curator_invite_data::Variables {
+                    studio_id,
+                    username: String::from(curator_info.username.trim()),
+                    token: sign_status.token,
+                },
+            );
+        let studio_curator_invite_query =
+            json!(studio_curator_invite_build_query);
+
+        let studio_curator_invite_resp_body: GqlResponse<serde_json::Value> =
+            gql_post(&req, &cfg)
+                .await
+                .body(studio_curator_invite_query)
+                .recv_json()
+                .await?;
+
+        let resp: Response = Redirect::new(studio_redirect_uri(
+            &language,
+            studio_curator_invite_resp_body.data,
+            "studioCuratorInvite",
+        ))
+        .into();
+
+        Ok(resp.into())
+    } else {
+        let resp: Response =
+            Redirect::new(format!("/{}/sign-in", language)).into();
+
+        Ok(resp.into())
+    }
+}
+
+pub async fn studio_curator_accept(req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
+    let language = String::from(req.param("language")?);
+    let studio_id = String::from(req.param("studio_id")?);
+
+    let sign_status = sign_status(&req).await;
+    if sign_status.sign_in {
+        let studio_curator_accept_build_query =
+            StudioCuratorAcceptData::build_query(
+                studio_curator_accept_data::Variables {
+                    studio_id,
+                    token: sign_status.token,
+                },
+            );
+        let studio_curator_accept_query =
+            json!(studio_curator_accept_build_query);
+
+        let studio_curator_accept_resp_body: GqlResponse<serde_json::Value> =
+            gql_post(&req, &cfg)
+                .await
+                .body(studio_curator_accept_query)
+                .recv_json()
+                .await?;
+
+        let resp: Response = Redirect::new(studio_redirect_uri(
+            &language,
+            studio_curator_accept_resp_body.data,
+            "studioCuratorAccept",
+        ))
+        .into();
+
+        Ok(resp.into())
+    } else {
+        let resp: Response =
+            Redirect::new(format!("/{}/sign-in", language)).into();
+
+        Ok(resp.into())
+    }
+}
+
+pub async fn studio_curator_remove(req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
+    let language = String::from(req.param("language")?);
+    let studio_id = String::from(req.param("studio_id")?);
+    let user_id = String::from(req.param("user_id")?);
+
+    let sign_status = sign_status(&req).await;
+    if sign_status.sign_in {
+        let studio_curator_remove_build_query =
+            StudioCuratorRemoveData::build_query(
+                studio_curator_remove_data::Variables {
+                    studio_id,
+                    user_id,
+                    token: sign_status.token,
+                },
+            );
+        let studio_curator_remove_query =
+            json!(studio_curator_remove_build_query);
+
+        let studio_curator_remove_resp_body: GqlResponse<serde_json::Value> =
+            gql_post(&req, &cfg)
+                .await
+                .body(studio_curator_remove_query)
+                .recv_json()
+                .await?;
+
+        let resp: Response = Redirect::new(studio_redirect_uri(
+            &language,
+            studio_curator_remove_resp_body.data,
+            "studioCuratorRemove",
+        ))
+        .into();
+
+        Ok(resp.into())
+    } else {
+        let resp: Response =
+            Redirect::new(format!("/{}/sign-in", language)).into();
+
+        Ok(resp.into())
+    }
+}
+
+pub async fn studio_project_add(mut req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
+    let language = String::from(req.param("language")?);
+    let studio_id = String::from(req.param("studio_id")?);
+
+    let sign_status = sign_status(&req).await;
+    if sign_status.sign_in {
+        let project_info: StudioProjectInfo = req.body_form().await?;
+        // both the project id & the url of project detail are accepted
+        let project_id = project_info
+            .project
+            .trim()
+            .trim_end_matches('/')
+            .rsplit('/')
+            .next()
+            .unwrap_or_default()
+            .to_string();
+
+        let studio_project_add_build_query = StudioProjectAddData::build_query(
+            studio_project_add_data::Variables {
+                studio_id,
+                project_id,
+                token: sign_status.token,
+            },
+        );
+        let studio_project_add_query = json!(studio_project_add_build_query);
+
+        let studio_project_add_resp_body: GqlResponse<serde_json::Value> =
+            gql_post(&req, &cfg)
+                .await
+                .body(studio_project_add_query)
+                .recv_json()
+                .await?;
+
+        let resp: Response = Redirect::new(studio_redirect_uri(
+            &language,
+            studio_project_add_resp_body.data,
+            "studioProjectAdd",
+        ))
+        .into();
+
+        Ok(resp.into())
+    } else {
+        let resp: Response =
+            Redirect::new(format!("/{}/sign-in", language)).into();
+
+        Ok(resp.into())
+    }
+}
+
+pub async fn studio_project_remove(req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
+    let language = String::from(req.param("language")?);
+    let studio_id = String::from(req.param("studio_id")?);
+    let project_id = String::from(req.param("project_id")?);
+
+    let sign_status = sign_status(&req).await;
+    if sign_status.sign_in {
+        let studio_project_remove_build_query =
+            StudioProjectRemoveData::build_query(
+                studio_project_remove_data::Variables {
+                    studio_id,
+                    project_id,
+                    token: sign_status.token,
+                },
+            );
+        let studio_project_remove_query =
+            json!(studio_project_remove_build_query);
+
+        let studio_project_remove_resp_body: GqlResponse<serde_json::Value> =
+            gql_post(&req, &cfg)
+                .await
+                .body(studio_project_remove_query)
+                .recv_json()
+                .await?;
+
+        let resp: Response = Redirect::new(studio_redirect_uri(
+            &language,
+            studio_project_remove_resp_body.data,
+            "studioProjectRemove",
+        ))
+        .into();
+
+        Ok(resp.into())
+    } else {
+        let resp: Response =
+            Redirect::new(format!("/{}/sign-in", language)).into();
+
+        Ok(resp.into())
+    }
+}
+
+pub async fn studio_project_move(req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
+    let language = String::from(req.param("language")?);
+    let studio_id = String::from(req.param("studio_id")?);
+    let project_id = String::from(req.param("project_id")?);
+    let position: StudioProjectPosition = req.query()?;
+
+    let sign_status = sign_status(&req).await;
+    if sign_status.sign_in {
+        let studio_project_move_build_query =
+            StudioProjectMoveData::build_query(
+                studio_project_move_data::Variables {
+                    studio_id,
+                    project_id,
+                    position: position.position,
+                    token: sign_status.token,
+                },
+            );
+        let studio_project_move_query = json!(studio_project_move_build_query);
+
+        let studio_project_move_resp_body: GqlResponse<serde_json::Value> =
+            gql_post(&req, &cfg)
+                .await
+                .body(studio_project_move_query)
+                .recv_json()
+                .await?;
+
+        let resp: Response = Redirect::new(studio_redirect_uri(
+            &language,
+            studio_project_move_resp_body.data,
+            "studioProjectMove",
+        ))
+        .into();
+
+        Ok(resp.into())
+    } else {
+        let resp: Response =
+            Redirect::new(format!("/{}/sign-in", language)).into();
+
+        Ok(resp.into())
+    }
+}
+
+async fn studio_by_slug(
+    cfg: &Config,
+    language: &str,
+    studio_slug: &str,
+    token: &str,
+    page: Page,
+) -> tide::Result<serde_json::Value> {
+    let studio_by_slug_build_query =
+        StudioBySlugData::build_query(studio_by_slug_data::Variables {
+            slug: String::from(studio_slug),
+            token: String::from(token),
+            from_page: page.from,
+            first_oid: page.first,
+            last_oid: page.last,
+            locale: String::from(language),
+        });
+    let studio_by_slug_query = json!(studio_by_slug_build_query);
+
+    let studio_by_slug_resp_body: GqlResponse<serde_json::Value> =
+        gql_post_as(cfg, language, token)
+            .await
+            .body(studio_by_slug_query)
+            .recv_json()
+            .await?;
+    let studio_by_slug_resp_data =
+        studio_by_slug_resp_body.data.expect("无响应数据");
+
+    Ok(studio_by_slug_resp_data["studioBySlug"].clone())
+}
+
+async fn studio_form_render(
+    cfg: &Config,
+    language: &str,
+    sign_status: &SignStatus,
+    studio: serde_json::Value,
+) -> tide::Result {
+    let mut studio_new_tpl: Hbs = Hbs::new("studios/studios-studio-new").await;
+    studio_new_tpl
+        .reg_head()
+        .await
+        .reg_header()
+        .await
+        .reg_container()
+        .await
+        .reg_footer()
+        .await;
+    studio_new_tpl.reg_script_values().await.reg_script_lang().await;
+
+    let mut data: BTreeMap<&str, serde_json::Value> = BTreeMap::new();
+    data.insert("language", json!(language));
+    data.insert("nav-studios-selected", json!("is-selected"));
+    data.insert("studio", studio);
+    insert_wish_random(cfg, &mut data).await;
+    insert_user_by_username(cfg, sign_status, &mut data).await;
+
+    studio_new_tpl.render(&data).await
+}
+
+fn cover_image_id(studio_info: &StudioInfo) -> Option<String> {
+    match studio_info.cover_image_id.trim() {
+        "" => None,
+        cover_image_id => Some(cover_image_id.to_string()),
+    }
+}
+
+fn studio_redirect_uri(
+    language: &str,
+    resp_data: Option<serde_json::Value>,
+    mutation: &str,
+) -> String {
+    match resp_data {
+        Some(data) => match data[mutation]["slug"].as_str() {
+            Some(slug) => format!("/{}/studio/{}", language, slug),
+            None => format!("/{}/studios", language),
+        },
+        None => format!("/{}/studios", language),
+    }
+}
@@ -1 +1,77 @@
+use tide::Request;
+use graphql_client::{GraphQLQuery, Response as GqlResponse};
+use serde_json::json;
 
+use crate::State;
+use crate::util::common::gql_post;
+
+use crate::models::topics::{
+    TopicsSuggestInfo, TopicsContentInfo, TopicsSuggestData,
+    topics_suggest_data, TopicsForContentData, topics_for_content_data,
+};
+
+// count of topics suggested while typing
+const SUGGEST_LIMIT: i64 = 8;
+
+// names of topics starting with the typed prefix, for autocomplete
+pub async fn topics_suggest(req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
+    let suggest_info: TopicsSuggestInfo = req.query()?;
+
+    let topics_suggest_build_query =
+        TopicsSuggestData::build_query(topics_suggest_data::Variables {
+            prefix: suggest_info.prefix,
+            limit: SUGGEST_LIMIT,
+        });
+    let topics_suggest_query = json!(topics_suggest_build_query);
+
+    let topics_suggest_resp_body: GqlResponse<serde_json::Value> =
+        gql_post(&req, &cfg)
+            .await
+            .body(topics_suggest_query)
+            .recv_json()
+            .await?;
+    let topics_suggest_resp_data =
+        topics_suggest_resp_body.data.expect("无响应数据");
+
+    let topics = topics_suggest_resp_data["topicsSuggest"].clone();
+
+    Ok(topic_names(&topics).into())
+}
+
+// names of existing topics found in the subject & content of a project
+pub async fn topics_for_content(mut req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
+    let content_info: TopicsContentInfo = req.body_form().await?;
+
+    let topics_for_content_build_query =
+        TopicsForContentData::build_query(topics_for_content_data::Variables {
+            subject: content_info.subject,
+            content: content_info.content,
+        });
+    let topics_for_content_query = json!(topics_for_content_build_query);
+
+    let topics_for_content_resp_body: GqlResponse<serde_json::Value> =
+        gql_post(&req, &cfg)
+            .await
+            .body(topics_for_content_query)
+            .recv_json()
+            .await?;
+    let topics_for_content_resp_data =
+        topics_for_content_resp_body.data.expect("无响应数据");
+
+    let topics = topics_for_content_resp_data["topicsForContent"].clone();
+
+    Ok(topic_names(&topics).into())
+}
+
+fn topic_names(topics: &serde_json::Value) -> serde_json::Value {
+    let names: Vec<&str> = match topics.as_array() {
+        Some(topics) => {
+            topics.iter().filter_map(|topic| topic["name"].as_str()).collect()
+        }
+        None => vec![],
+    };
+
+    json!(names)
+}
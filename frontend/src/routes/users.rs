@@ -1,11 +1,12 @@
 use std::collections::BTreeMap;
-use tide::{Request, http::Method};
+use tide::{Request, Response, Redirect, http::Method};
 use graphql_client::{GraphQLQuery, Response as GqlResponse};
+use serde::Deserialize;
 use serde_json::json;
 
 use crate::State;
 use crate::util::{
-    common::{gql_uri, sign_status},
+    common::{gql_post, sign_status, gql_error},
     email::send_email,
     tpl::{Hbs, insert_user_by_username, insert_wish_random},
 };
@@ -14,12 +15,23 @@ use crate::models::{
     Page,
     users::{
         UsersData, users_data, UserByIdData, user_by_id_data,
-        UserByUsernameDetailData, user_by_username_detail_data,
-        UserUpdateOneFieldByIdData, user_update_one_field_by_id_data,
+        UserByUsernameData, user_by_username_data, UserByUsernameDetailData,
+        user_by_username_detail_data, UserActivateData, user_activate_data,
+        UserFollowersData, user_followers_data, UserFollowingData,
+        user_following_data, UserFollowData, user_follow_data,
+        UserUnfollowData, user_unfollow_data,
     },
 };
 
+// code of the activation link, signed for the account
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct ActivationQuery {
+    code: String,
+}
+
 pub async fn users_index(req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
     let language = String::from(req.param("language")?);
 
     let mut users_index_tpl: Hbs = Hbs::new("users/users-index").await;
@@ -46,11 +58,11 @@ pub async fn users_index(req: Request<State>) -> tide::Result {
     data.insert("language", json!(language));
     data.insert("nav-users-selected", json!("is-selected"));
     data.insert("users-all-selected", json!("is-selected"));
-    insert_wish_random(&mut data).await;
+    insert_wish_random(&cfg, &mut data).await;
 
     let sign_status = sign_status(&req).await;
     if sign_status.sign_in {
-        insert_user_by_username(sign_status.username, &mut data).await;
+        insert_user_by_username(&cfg, &sign_status, &mut data).await;
     }
 
     let page: Page = req.query()?;
@@ -58,12 +70,12 @@ pub async fn users_index(req: Request<State>) -> tide::Result {
         from_page: page.from,
         first_oid: page.first,
         last_oid: page.last,
-        status: 1,
+        status: users_data::UserStatus::ACTIVE,
     });
     let users_query = json!(users_build_query);
 
     let users_resp_body: GqlResponse<serde_json::Value> =
-        surf::post(&gql_uri().await).body(users_query).recv_json().await?;
+        gql_post(&req, &cfg).await.body(users_query).recv_json().await?;
     let users_resp_data = users_resp_body.data.expect("无响应数据");
 
     let users = users_resp_data["users"].clone();
@@ -102,7 +114,7 @@ pub async fn users_index(req: Request<State>) -> tide::Result {
 
 //     let sign_status = sign_status(&req).await;
 //     if sign_status.sign_in {
-//         insert_user_by_username(sign_status.username, &mut data).await;
+//         insert_user_by_username(&sign_status, &mut data).await;
 //     }
 
 //     let filter_str = req.param("filter_str")?;
@@ -157,6 +169,7 @@ pub async fn users_index(req: Request<State>) -> tide::Result {
 // }
 
 pub async fn user_index(req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
     let language = String::from(req.param("language")?);
 
     let mut user_index_tpl: Hbs = Hbs::new("users/users-user-detail").await;
@@ -180,12 +193,12 @@ pub async fn user_index(req: Request<State>) -> tide::Result {
     let mut data: BTreeMap<&str, serde_json::Value> = BTreeMap::new();
     data.insert("language", json!(language));
     data.insert("nav-users-selected", json!("is-selected"));
-    insert_wish_random(&mut data).await;
+    insert_wish_random(&cfg, &mut data).await;
 
     let sign_status = sign_status(&req).await;
     if sign_status.sign_in {
         data.insert("sign-in", json!(sign_status.sign_in));
-        insert_user_by_username(sign_status.username, &mut data).await;
+        insert_user_by_username(&cfg, &sign_status, &mut data).await;
     }
 
     let author_username = req.param("author_username")?;
@@ -193,13 +206,15 @@ pub async fn user_index(req: Request<State>) -> tide::Result {
         UserByUsernameDetailData::build_query(
             user_by_username_detail_data::Variables {
                 username: String::from(author_username),
+                token: sign_status.token,
             },
         );
     let author_by_username_detail_query =
         json!(author_by_username_detail_build_query);
 
     let author_by_username_detail_resp_body: GqlResponse<serde_json::Value> =
-        surf::post(&gql_uri().await)
+        gql_post(&req, &cfg)
+            .await
             .body(author_by_username_detail_query)
             .recv_json()
             .await
@@ -214,7 +229,149 @@ pub async fn user_index(req: Request<State>) -> tide::Result {
     user_index_tpl.render(&data).await
 }
 
+pub async fn user_followers(req: Request<State>) -> tide::Result {
+    users_follows(req, "followers").await
+}
+
+pub async fn user_following(req: Request<State>) -> tide::Result {
+    users_follows(req, "following").await
+}
+
+// list the followers or the following of a user
+async fn users_follows(req: Request<State>, follows: &str) -> tide::Result {
+    let cfg = req.state().config.clone();
+    let language = String::from(req.param("language")?);
+
+    let mut users_index_tpl: Hbs = Hbs::new("users/users-index").await;
+    users_index_tpl
+        .reg_head()
+        .await
+        .reg_header()
+        .await
+        .reg_container()
+        .await
+        .reg_pagination()
+        .await
+        .reg_footer()
+        .await;
+    users_index_tpl
+        .reg_script_values()
+        .await
+        .reg_script_ops()
+        .await
+        .reg_script_lang()
+        .await;
+
+    let mut data: BTreeMap<&str, serde_json::Value> = BTreeMap::new();
+    data.insert("language", json!(language));
+    data.insert("nav-users-selected", json!("is-selected"));
+    insert_wish_random(&cfg, &mut data).await;
+
+    let sign_status = sign_status(&req).await;
+    if sign_status.sign_in {
+        insert_user_by_username(&cfg, &sign_status, &mut data).await;
+    }
+
+    let author_username = String::from(req.param("author_username")?);
+    let page: Page = req.query()?;
+    let follows_query = match follows {
+        "followers" => {
+            json!(UserFollowersData::build_query(
+                user_followers_data::Variables {
+                    username: author_username,
+                    from_page: page.from,
+                    first_oid: page.first,
+                    last_oid: page.last,
+                }
+            ))
+        }
+        _ => {
+            json!(UserFollowingData::build_query(
+                user_following_data::Variables {
+                    username: author_username,
+                    from_page: page.from,
+                    first_oid: page.first,
+                    last_oid: page.last,
+                }
+            ))
+        }
+    };
+
+    let follows_resp_body: GqlResponse<serde_json::Value> =
+        gql_post(&req, &cfg).await.body(follows_query).recv_json().await?;
+    let follows_resp_data = follows_resp_body.data.expect("无响应数据");
+
+    let author = follows_resp_data["userByUsername"].clone();
+    data.insert(
+        "follows_desc",
+        json!({"nickname": author["nickname"], "content": format!("user-{}", follows)}),
+    );
+    data.insert("pagination", author[follows].clone());
+
+    users_index_tpl.render(&data).await
+}
+
+pub async fn user_follow(req: Request<State>) -> tide::Result {
+    user_follow_switch(req, true).await
+}
+
+pub async fn user_unfollow(req: Request<State>) -> tide::Result {
+    user_follow_switch(req, false).await
+}
+
+// follow or unfollow a user by the signed-in user
+async fn user_follow_switch(req: Request<State>, follow: bool) -> tide::Result {
+    let cfg = req.state().config.clone();
+    let language = String::from(req.param("language")?);
+
+    let sign_status = sign_status(&req).await;
+    if sign_status.sign_in {
+        let author_username = req.param("author_username")?;
+
+        let author_build_query =
+            UserByUsernameData::build_query(user_by_username_data::Variables {
+                username: String::from(author_username),
+            });
+        let author_query = json!(author_build_query);
+
+        let author_resp_body: GqlResponse<serde_json::Value> =
+            gql_post(&req, &cfg).await.body(author_query).recv_json().await?;
+        let author_resp_data = author_resp_body.data.expect("无响应数据");
+        let author_id =
+            author_resp_data["userByUsername"]["id"].as_str().unwrap();
+
+        let follow_query = if follow {
+            json!(UserFollowData::build_query(user_follow_data::Variables {
+                user_id: author_id.to_string(),
+                token: sign_status.token,
+            }))
+        } else {
+            json!(UserUnfollowData::build_query(
+                user_unfollow_data::Variables {
+                    user_id: author_id.to_string(),
+                    token: sign_status.token,
+                }
+            ))
+        };
+
+        let _follow_resp_body: GqlResponse<serde_json::Value> =
+            gql_post(&req, &cfg).await.body(follow_query).recv_json().await?;
+
+        let resp: Response =
+            Redirect::new(format!("/{}/user/{}", language, author_username))
+                .into();
+
+        Ok(resp.into())
+    } else {
+        let resp: Response =
+            Redirect::new(format!("/{}/sign-in", language)).into();
+
+        Ok(resp.into())
+    }
+}
+
 pub async fn user_activate(req: Request<State>) -> tide::Result {
+    let cfg = req.state().config.clone();
     let language = String::from(req.param("language")?);
 
     let mut user_activate_tpl: Hbs =
@@ -233,7 +390,7 @@ pub async fn user_activate(req: Request<State>) -> tide::Result {
     let mut data: BTreeMap<&str, serde_json::Value> = BTreeMap::new();
     data.insert("language", json!(language));
     data.insert("nav-users-selected", json!("is-selected"));
-    insert_wish_random(&mut data).await;
+    insert_wish_random(&cfg, &mut data).await;
 
     let user_id = req.param("user_id")?;
     match req.method() {
@@ -245,7 +402,8 @@ pub async fn user_activate(req: Request<State>) -> tide::Result {
             let user_resend_query = json!(user_resend_build_query);
 
             let user_resend_resp_body: GqlResponse<serde_json::Value> =
-                surf::post(&gql_uri().await)
+                gql_post(&req, &cfg)
+                    .await
                     .body(user_resend_query)
                     .recv_json()
                     .await?;
@@ -255,6 +413,7 @@ pub async fn user_activate(req: Request<State>) -> tide::Result {
             let user_resend = user_resend_resp_data["userById"].clone();
 
             send_email(
+                &cfg,
                 language,
                 user_id.to_string(),
                 user_resend["username"].as_str().unwrap().to_string(),
@@ -266,28 +425,32 @@ pub async fn user_activate(req: Request<State>) -> tide::Result {
             data.insert("user_resend", user_resend);
         }
         _ => {
+            let activation: ActivationQuery = req.query()?;
             let user_activate_build_query =
-                UserUpdateOneFieldByIdData::build_query(
-                    user_update_one_field_by_id_data::Variables {
-                        user_id: user_id.to_string(),
-                        field_name: String::from("status"),
-                        field_val: String::from("1"),
-                    },
-                );
+                UserActivateData::build_query(user_activate_data::Variables {
+                    user_id: user_id.to_string(),
+                    code: activation.code,
+                });
             let user_activate_query = json!(user_activate_build_query);
 
             let user_activate_resp_body: GqlResponse<serde_json::Value> =
-                surf::post(&gql_uri().await)
+                gql_post(&req, &cfg)
+                    .await
                     .body(user_activate_query)
                     .recv_json()
                     .await?;
-            let user_activate_resp_data =
-                user_activate_resp_body.data.expect("无响应数据");
-
-            let user_activate =
-                user_activate_resp_data["userUpdateOneFieldById"].clone();
-
-            data.insert("user_activate", user_activate);
+            match user_activate_resp_body.data {
+                Some(user_activate_resp_data) => {
+                    let user_activate =
+                        user_activate_resp_data["userActivate"].clone();
+                    data.insert("user_activate", user_activate);
+                }
+                None => {
+                    let user_activate_failed =
+                        gql_error(user_activate_resp_body.errors);
+                    data.insert("user_activate_failed", user_activate_failed);
+                }
+            }
         }
     }
 
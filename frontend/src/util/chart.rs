@@ -0,0 +1,65 @@
+// width & height of the chart, and the margin left for labels
+const CHART_WIDTH: u64 = 720;
+const CHART_HEIGHT: u64 = 160;
+const CHART_MARGIN: u64 = 24;
+
+// Bar chart of counts by day, rendered as inline svg. Each bar shows its day
+// and count on hover, the first and the last days are labeled below.
+pub fn bars_svg(points: &[(String, u64)], color: &str) -> String {
+    let count_max = points.iter().map(|(_, count)| *count).max().unwrap_or(0);
+    let plot_width = CHART_WIDTH - CHART_MARGIN * 2;
+    let plot_height = CHART_HEIGHT - CHART_MARGIN * 2;
+    let slot_width = plot_width as f64 / points.len().max(1) as f64;
+    let bar_width = (slot_width * 0.8).max(1.0);
+
+    let mut svg = format!(
+        r#"<svg class="w100" viewBox="0 0 {} {}" role="img" xmlns="http://www.w3.org/2000/svg">"#,
+        CHART_WIDTH, CHART_HEIGHT
+    );
+    svg.push_str(&format!(
+        r##"<line x1="{m}" y1="{b}" x2="{r}" y2="{b}" stroke="#bbc0c4"/>"##,
+        m = CHART_MARGIN,
+        b = CHART_HEIGHT - CHART_MARGIN,
+        r = CHART_WIDTH - CHART_MARGIN
+    ));
+    svg.push_str(&format!(
+        r##"<text x="{}" y="{}" font-size="11" fill="#6a737c">{}</text>"##,
+        CHART_MARGIN,
+        CHART_MARGIN - 8,
+        count_max
+    ));
+
+    for (index, (day, count)) in points.iter().enumerate() {
+        let bar_height = match count_max {
+            0 => 0.0,
+            _ => plot_height as f64 * *count as f64 / count_max as f64,
+        };
+        let x = CHART_MARGIN as f64
+            + slot_width * index as f64
+            + (slot_width - bar_width) / 2.0;
+        let y = (CHART_HEIGHT - CHART_MARGIN) as f64 - bar_height;
+        svg.push_str(&format!(
+            r#"<rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" fill="{}"><title>{}: {}</title></rect>"#,
+            x, y, bar_width, bar_height, color, day, count
+        ));
+    }
+
+    if let (Some((first_day, _)), Some((last_day, _))) =
+        (points.first(), points.last())
+    {
+        let label_y = CHART_HEIGHT - 6;
+        svg.push_str(&format!(
+            r##"<text x="{}" y="{}" font-size="11" fill="#6a737c">{}</text>"##,
+            CHART_MARGIN, label_y, first_day
+        ));
+        svg.push_str(&format!(
+            r##"<text x="{}" y="{}" font-size="11" fill="#6a737c" text-anchor="end">{}</text>"##,
+            CHART_WIDTH - CHART_MARGIN,
+            label_y,
+            last_day
+        ));
+    }
+
+    svg.push_str("</svg>");
+    svg
+}
@@ -1,21 +1,61 @@
-use std::{path::PathBuf, fs::read_to_string, borrow::Cow};
+use std::{
+    path::PathBuf,
+    fs::read_to_string,
+    borrow::Cow,
+    net::{IpAddr, SocketAddr},
+};
 use tide::Request;
 use fluent_bundle::{FluentBundle, FluentResource, FluentArgs, FluentValue};
-use serde_json::{Map, Value};
+use serde_json::{Map, Value, json};
+use graphql_client::Error as GqlError;
+use surf::RequestBuilder;
+use ring::hmac;
+use base64::{
+    Engine,
+    engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD},
+};
 
 use crate::State;
-use crate::util::constant::CFG;
+use crate::util::config::Config;
 
 use crate::models::users::SignStatus;
 
-pub async fn gql_uri() -> String {
-    let gql_prot = CFG.get("GQL_PROT").unwrap();
-    let gql_addr = CFG.get("GQL_ADDR").unwrap();
-    let gql_port = CFG.get("GQL_PORT").unwrap();
-    let gql_uri = CFG.get("GQL_URI").unwrap();
-    let gql_path = CFG.get("GQL_VER").unwrap();
+pub async fn gql_uri(cfg: &Config) -> String {
+    format!(
+        "{}://{}:{}/{}/{}",
+        cfg.gql_prot, cfg.gql_addr, cfg.gql_port, cfg.gql_uri, cfg.gql_ver
+    )
+}
+
+// Graphql request for the page, which asks for the dates in the language of
+// the page, and in the profile timezone of the signed-in user by the token.
+pub async fn gql_post(req: &Request<State>, cfg: &Config) -> RequestBuilder {
+    let language = req.param("language").unwrap_or("-");
+    let sign_status = sign_status(req).await;
 
-    format!("{}://{}:{}/{}/{}", gql_prot, gql_addr, gql_port, gql_uri, gql_path)
+    gql_post_as(cfg, language, &sign_status.token).await
+}
+
+// the same as gql_post, for the helpers called with the values of the page
+pub async fn gql_post_as(
+    cfg: &Config,
+    language: &str,
+    token: &str,
+) -> RequestBuilder {
+    let mut gql_req = surf::post(&gql_uri(cfg).await);
+    if language != "-" {
+        gql_req = gql_req.header("Accept-Language", language);
+    }
+    if !matches!(token.trim(), "" | "-") {
+        gql_req = gql_req.header("Authorization", format!("Bearer {}", token));
+    }
+
+    gql_req
+}
+
+// websocket uri of graphql subscriptions, connected by browsers
+pub async fn gql_ws_uri(cfg: &Config) -> String {
+    cfg.gql_ws_uri.clone()
 }
 
 pub async fn scripts_dir() -> String {
@@ -44,6 +84,74 @@ pub async fn sign_status(req: &Request<State>) -> SignStatus {
     SignStatus { sign_in, username, token }
 }
 
+// address and agent of the visitor, digested by the backend before kept
+pub fn visitor_fingerprint(req: &Request<State>) -> String {
+    let forwarded_for = req
+        .header("X-Forwarded-For")
+        .map(|forwarded_for| forwarded_for.last().as_str());
+    let user_agent = match req.header("User-Agent") {
+        Some(user_agent) => user_agent.as_str(),
+        None => "-",
+    };
+
+    fingerprint_of(
+        req.peer_addr(),
+        forwarded_for,
+        user_agent,
+        &req.state().config.trusted_proxies(),
+    )
+}
+
+// The IP of the visitor without the port, which changes by connections. The
+// X-Forwarded-For header could be set by anyone, so it is only read from the
+// trusted proxies, and its last address out of them is the visitor.
+fn fingerprint_of(
+    peer_addr: Option<&str>,
+    forwarded_for: Option<&str>,
+    user_agent: &str,
+    trusted_proxies: &[IpAddr],
+) -> String {
+    let peer_ip = peer_addr.and_then(|peer_addr| {
+        peer_addr
+            .parse::<SocketAddr>()
+            .map(|socket_addr| socket_addr.ip())
+            .or_else(|_| peer_addr.parse::<IpAddr>())
+            .ok()
+    });
+
+    let visitor_ip = match (peer_ip, forwarded_for) {
+        (Some(ip), Some(forwarded_for)) if trusted_proxies.contains(&ip) => {
+            forwarded_for
+                .rsplit(',')
+                .filter_map(|addr| addr.trim().parse::<IpAddr>().ok())
+                .find(|addr| !trusted_proxies.contains(addr))
+                .or(peer_ip)
+        }
+        _ => peer_ip,
+    };
+
+    match visitor_ip {
+        Some(ip) => format!("{}|{}", ip, user_agent),
+        None => format!("-|{}", user_agent),
+    }
+}
+
+// signature of the fingerprint by the key shared with the backend, which
+// trusts no fingerprints of others
+pub fn visitor_signature(fingerprint: &str, cfg: &Config) -> String {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, cfg.visitor_key.as_bytes());
+
+    STANDARD.encode(hmac::sign(&key, fingerprint.as_bytes()))
+}
+
+// code of the activation link, signed for the account by the key shared with
+// the backend, which activates no accounts without it
+pub fn activation_code(user_id: &str, cfg: &Config) -> String {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, cfg.activation_key.as_bytes());
+
+    URL_SAFE_NO_PAD.encode(hmac::sign(&key, user_id.as_bytes()))
+}
+
 pub fn get_lang_msg(
     lang_id: &str,
     root_tpl: &str,
@@ -102,23 +210,105 @@ pub fn get_lang_msg(
     value.to_string()
 }
 
+// Code & arguments of the first error of a graphql response, the code is
+// translated in templates by {{ lang err.code err }}.
+pub fn gql_error(errors: Option<Vec<GqlError>>) -> Value {
+    let mut error_val = json!({"code": "unknown-error"});
+
+    if let Some(error) = errors.and_then(|errors| errors.into_iter().next()) {
+        error_val["code"] = json!(error.message);
+        if let Some(extensions) = error.extensions {
+            for (key, val) in extensions {
+                error_val[key] = val;
+            }
+        }
+    }
+
+    error_val
+}
+
 fn get_lang_res(root_tpl: &str) -> Vec<&str> {
     match root_tpl {
-        "index" | "register" | "sign-in" | "admin_admin-index" => {
-            vec!["common.lang", "home.lang"]
+        "index"
+        | "register"
+        | "sign-in"
+        | "admin_admin-index"
+        | "admin_admin-categories" => {
+            vec!["common.lang", "errors.lang", "home.lang"]
         }
         "projects_projects-index"
         | "projects_projects-project-detail"
         | "projects_projects-project-new"
+        | "projects_projects-trash"
+        | "projects_projects-stats"
         | "admin_admin-projects"
         | "admin_admin-project-detail" => {
-            vec!["common.lang", "pagination.lang", "projects.lang"]
+            vec![
+                "common.lang",
+                "errors.lang",
+                "pagination.lang",
+                "projects.lang",
+            ]
         }
+        "studios_studios-index"
+        | "studios_studios-studio-detail"
+        | "studios_studios-studio-new" => vec![
+            "common.lang",
+            "errors.lang",
+            "pagination.lang",
+            "projects.lang",
+            "studios.lang",
+        ],
         "users_users-index"
         | "users_users-user-detail"
+        | "users_users-notifications"
         | "users_users-user-activate" => {
-            vec!["common.lang", "pagination.lang", "users.lang"]
+            vec![
+                "common.lang",
+                "errors.lang",
+                "pagination.lang",
+                "users.lang",
+            ]
         }
         _ => vec![],
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AGENT: &str = "Mozilla/5.0";
+
+    #[test]
+    fn fingerprint_ignores_ports() {
+        let first = fingerprint_of(Some("203.0.113.7:50001"), None, AGENT, &[]);
+        let second =
+            fingerprint_of(Some("203.0.113.7:50002"), None, AGENT, &[]);
+
+        assert_eq!(first, second);
+        assert_eq!(first, "203.0.113.7|Mozilla/5.0");
+    }
+
+    #[test]
+    fn fingerprint_trusts_forwarded_for_only_from_proxies() {
+        let proxy: IpAddr = "10.0.0.1".parse().unwrap();
+        let forwarded_for = Some("198.51.100.1, 203.0.113.7");
+
+        let direct = fingerprint_of(
+            Some("203.0.113.9:443"),
+            forwarded_for,
+            AGENT,
+            &[proxy],
+        );
+        assert_eq!(direct, "203.0.113.9|Mozilla/5.0");
+
+        let proxied = fingerprint_of(
+            Some("10.0.0.1:443"),
+            forwarded_for,
+            AGENT,
+            &[proxy],
+        );
+        assert_eq!(proxied, "203.0.113.7|Mozilla/5.0");
+    }
+}
@@ -0,0 +1,192 @@
+use std::{collections::HashMap, fmt, fs, net::IpAddr, str::FromStr};
+
+use dotenv::dotenv;
+
+// Configuration of the frontend. Values are taken from the environment, which
+// includes the .env file, and then from an optional TOML file with the same
+// keys in lowercase, such as `gql_port = 8000`. The TOML file is CONFIG_FILE,
+// or config.toml in the working directory if it exists.
+#[derive(Clone, Debug)]
+pub struct Config {
+    pub domain: String,
+    pub addr: String,
+    pub port: u16,
+    pub log_level: String,
+    pub visitor_key: String,
+    pub activation_key: String,
+    // addresses of the reverse proxies, separated by commas, may be empty
+    pub trusted_proxies: String,
+
+    pub gql_prot: String,
+    pub gql_addr: String,
+    pub gql_port: u16,
+    pub gql_uri: String,
+    pub gql_ver: String,
+    pub giql_ver: String,
+    pub gql_ws_uri: String,
+
+    pub email_smtp: String,
+    pub email_from: String,
+    pub email_username: String,
+    pub email_password: String,
+}
+
+// All problems of the configuration, reported together at startup
+#[derive(Debug)]
+pub struct ConfigError(Vec<String>);
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Invalid configuration:")?;
+        for error in &self.0 {
+            writeln!(f, "  - {}", error)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Config {
+    // the reverse proxies, whose X-Forwarded-For headers are trusted
+    pub fn trusted_proxies(&self) -> Vec<IpAddr> {
+        self.proxies().filter_map(|proxy| proxy.parse().ok()).collect()
+    }
+
+    fn proxies(&self) -> impl Iterator<Item = &str> {
+        self.trusted_proxies
+            .split(',')
+            .map(str::trim)
+            .filter(|proxy| !proxy.is_empty())
+    }
+
+    pub fn load() -> Result<Config, ConfigError> {
+        let mut sources = Sources::load();
+
+        let config = Config {
+            domain: sources.get("DOMAIN"),
+            addr: sources.get("ADDR"),
+            port: sources.get("PORT"),
+            log_level: sources.get("LOG_LEVEL"),
+            visitor_key: sources.get("VISITOR_KEY"),
+            activation_key: sources.get("ACTIVATION_KEY"),
+            trusted_proxies: sources.get("TRUSTED_PROXIES"),
+
+            gql_prot: sources.get("GQL_PROT"),
+            gql_addr: sources.get("GQL_ADDR"),
+            gql_port: sources.get("GQL_PORT"),
+            gql_uri: sources.get("GQL_URI"),
+            gql_ver: sources.get("GQL_VER"),
+            giql_ver: sources.get("GIQL_VER"),
+            gql_ws_uri: sources.get("GQL_WS_URI"),
+
+            email_smtp: sources.get("EMAIL_SMTP"),
+            email_from: sources.get("EMAIL_FROM"),
+            email_username: sources.get("EMAIL_USERNAME"),
+            email_password: sources.get("EMAIL_PASSWORD"),
+        };
+
+        if femme::LevelFilter::from_str(&config.log_level).is_err() {
+            sources.invalid("LOG_LEVEL", "not a log level");
+        }
+        if config.visitor_key.trim().is_empty() {
+            sources.invalid("VISITOR_KEY", "must not be empty");
+        }
+        if config.activation_key.trim().is_empty() {
+            sources.invalid("ACTIVATION_KEY", "must not be empty");
+        }
+        if config.proxies().any(|proxy| proxy.parse::<IpAddr>().is_err()) {
+            sources.invalid("TRUSTED_PROXIES", "must be IP addresses");
+        }
+        if !matches!(config.gql_prot.as_str(), "http" | "https") {
+            sources.invalid("GQL_PROT", "must be http or https");
+        }
+        if !matches!(config.gql_ws_uri.split("://").next(), Some("ws" | "wss"))
+        {
+            sources.invalid("GQL_WS_URI", "must start with ws:// or wss://");
+        }
+
+        if sources.errors.is_empty() {
+            Ok(config)
+        } else {
+            Err(ConfigError(sources.errors))
+        }
+    }
+}
+
+// values of the TOML file, and the errors found while reading the values
+struct Sources {
+    file_values: HashMap<String, String>,
+    errors: Vec<String>,
+}
+
+impl Sources {
+    fn load() -> Self {
+        dotenv().ok();
+
+        let mut sources =
+            Sources { file_values: HashMap::new(), errors: vec![] };
+
+        let (file_path, required) = match dotenv::var("CONFIG_FILE") {
+            Ok(file_path) => (file_path, true),
+            Err(_) => (String::from("config.toml"), false),
+        };
+        match fs::read_to_string(&file_path) {
+            Ok(content) => match content.parse::<toml::Table>() {
+                Ok(table) => {
+                    for (key, value) in table {
+                        let value = match value {
+                            toml::Value::String(value) => value,
+                            value => value.to_string(),
+                        };
+                        sources.file_values.insert(key.to_uppercase(), value);
+                    }
+                }
+                Err(error) => {
+                    sources.errors.push(format!("{}: {}", file_path, error))
+                }
+            },
+            Err(error) if required => {
+                sources.errors.push(format!("{}: {}", file_path, error))
+            }
+            Err(_) => {}
+        }
+
+        sources
+    }
+
+    // The value of the key, the environment first. A missing or invalid value
+    // is recorded as an error, and the default is returned in its place.
+    fn get<T>(&mut self, key: &str) -> T
+    where
+        T: FromStr + Default,
+        T::Err: fmt::Display,
+    {
+        let value = dotenv::var(key)
+            .ok()
+            .or_else(|| self.file_values.get(key).cloned());
+
+        match value {
+            Some(value) => match value.trim().parse::<T>() {
+                Ok(value) => value,
+                Err(error) => {
+                    self.invalid(key, &error.to_string());
+                    T::default()
+                }
+            },
+            None => {
+                self.errors.push(format!("{} is not set", key));
+                T::default()
+            }
+        }
+    }
+
+    // only the first error of each key is kept
+    fn invalid(&mut self, key: &str, reason: &str) {
+        let prefix = format!("{} ", key);
+        if !self.errors.iter().any(|error| error.starts_with(&prefix)) {
+            self.errors.push(format!("{} is invalid: {}", key, reason));
+        }
+    }
+}
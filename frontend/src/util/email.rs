@@ -4,20 +4,24 @@ use lettre::{
 };
 use serde_json::{Map, json};
 
-use crate::util::{constant::CFG, common::get_lang_msg};
+use crate::util::{
+    config::Config,
+    common::{get_lang_msg, activation_code},
+};
 
 pub async fn send_email(
+    cfg: &Config,
     language: String,
     user_id: String,
     username: String,
     nickname: String,
     email_to: String,
 ) {
-    let domain = CFG.get("DOMAIN").unwrap();
-    let email_smtp = CFG.get("EMAIL_SMTP").unwrap();
-    let email_from = dotenv::var("EMAIL_FROM").unwrap();
-    let email_username = dotenv::var("EMAIL_USERNAME").unwrap();
-    let email_password = dotenv::var("EMAIL_PASSWORD").unwrap();
+    let domain = &cfg.domain;
+    let email_smtp = &cfg.email_smtp;
+    let email_from = &cfg.email_from;
+    let email_username = cfg.email_username.clone();
+    let email_password = cfg.email_password.clone();
 
     let mut email_args = Map::new();
     email_args.insert("nickname".to_string(), json!(nickname));
@@ -33,6 +37,8 @@ pub async fn send_email(
     email_args.insert("domain".to_string(), json!(domain));
     email_args.insert("language".to_string(), json!(language));
     email_args.insert("user_id".to_string(), json!(user_id));
+    email_args
+        .insert("code".to_string(), json!(activation_code(&user_id, cfg)));
 
     let email_body = get_lang_msg(
         language.as_str(),
@@ -1,6 +1,7 @@
-pub mod constant;
+pub mod config;
 pub mod common;
 pub mod tpl;
 pub mod email;
 pub mod str_trait;
 pub mod upload;
+pub mod chart;
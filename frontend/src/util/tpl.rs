@@ -5,13 +5,17 @@ use graphql_client::{GraphQLQuery, Response as GqlResponse};
 use serde::Serialize;
 use serde_json::json;
 
-use super::common::{gql_uri, scripts_dir, tpls_dir, get_lang_msg};
+use super::config::Config;
+use super::common::{gql_uri, gql_ws_uri, scripts_dir, tpls_dir, get_lang_msg};
 
 use crate::models::{
     users::{
-        UserByUsernameData, user_by_username_data, WishRandomData,
+        SignStatus, UserByUsernameData, user_by_username_data, WishRandomData,
         wish_random_data,
     },
+    notifications::{
+        NotificationsUnreadCountData, notifications_unread_count_data,
+    },
     categories::{CategoriesData, categories_data},
 };
 
@@ -134,6 +138,12 @@ impl<'hbs> Hbs<'hbs> {
                 format!("{}{}", scripts_dir().await, "values/str-cmp.rhai"),
             )
             .unwrap();
+        self.reg
+            .register_script_helper_file(
+                "status-cmp",
+                format!("{}{}", scripts_dir().await, "values/status-cmp.rhai"),
+            )
+            .unwrap();
         self.reg
             .register_script_helper_file(
                 "str-cut",
@@ -213,17 +223,18 @@ fn lang_helper(
 }
 
 pub async fn insert_user_by_username(
-    sign_username: String,
+    cfg: &Config,
+    sign_status: &SignStatus,
     data: &mut BTreeMap<&str, serde_json::Value>,
 ) {
     let user_by_username_build_query =
         UserByUsernameData::build_query(user_by_username_data::Variables {
-            username: sign_username,
+            username: sign_status.username.clone(),
         });
     let user_by_username_query = json!(user_by_username_build_query);
 
     let user_by_username_resp_body: GqlResponse<serde_json::Value> =
-        surf::post(&gql_uri().await)
+        surf::post(&gql_uri(cfg).await)
             .body(user_by_username_query)
             .recv_json()
             .await
@@ -233,9 +244,42 @@ pub async fn insert_user_by_username(
 
     let user = user_by_username_resp_data["userByUsername"].clone();
     data.insert("user", user);
+
+    // for the notifications subscription of the signed-in user, which gets
+    // its ticket by the page
+    data.insert("gql_ws", json!(gql_ws_uri(cfg).await));
+
+    // unread notifications for the badge in header
+    let notifications_unread_count_build_query =
+        NotificationsUnreadCountData::build_query(
+            notifications_unread_count_data::Variables {
+                token: sign_status.token.clone(),
+            },
+        );
+    let notifications_unread_count_query =
+        json!(notifications_unread_count_build_query);
+
+    let notifications_unread_count_resp_body: GqlResponse<serde_json::Value> =
+        surf::post(&gql_uri(cfg).await)
+            .body(notifications_unread_count_query)
+            .recv_json()
+            .await
+            .unwrap();
+    if let Some(notifications_unread_count_resp_data) =
+        notifications_unread_count_resp_body.data
+    {
+        data.insert(
+            "notifications_unread_count",
+            notifications_unread_count_resp_data["notificationsUnreadCount"]
+                .clone(),
+        );
+    }
 }
 
-pub async fn insert_wish_random(data: &mut BTreeMap<&str, serde_json::Value>) {
+pub async fn insert_wish_random(
+    cfg: &Config,
+    data: &mut BTreeMap<&str, serde_json::Value>,
+) {
     let wish_random_build_query =
         WishRandomData::build_query(wish_random_data::Variables {
             username: "-".to_string(),
@@ -243,7 +287,7 @@ pub async fn insert_wish_random(data: &mut BTreeMap<&str, serde_json::Value>) {
     let wish_random_query = json!(wish_random_build_query);
 
     let wish_random_resp_body: GqlResponse<serde_json::Value> =
-        surf::post(&gql_uri().await)
+        surf::post(&gql_uri(cfg).await)
             .body(wish_random_query)
             .recv_json()
             .await
@@ -254,13 +298,19 @@ pub async fn insert_wish_random(data: &mut BTreeMap<&str, serde_json::Value>) {
     data.insert("wish", wish);
 }
 
-pub async fn insert_categories(data: &mut BTreeMap<&str, serde_json::Value>) {
+pub async fn insert_categories(
+    cfg: &Config,
+    language: &str,
+    data: &mut BTreeMap<&str, serde_json::Value>,
+) {
     let categories_build_query =
-        CategoriesData::build_query(categories_data::Variables {});
+        CategoriesData::build_query(categories_data::Variables {
+            locale: language.to_string(),
+        });
     let categories_query = json!(categories_build_query);
 
     let categories_resp_body: GqlResponse<serde_json::Value> =
-        surf::post(&gql_uri().await)
+        surf::post(&gql_uri(cfg).await)
             .body(categories_query)
             .recv_json()
             .await